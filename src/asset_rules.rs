@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use crate::{prelude::*, Error, Meta, Px, SpotMeta, Sz};
+
+/// Prices may have at most this many significant figures, unless they are integers.
+pub const MAX_SIG_FIGS: u32 = 5;
+pub const PERP_MAX_DECIMALS: u32 = 6;
+pub const SPOT_MAX_DECIMALS: u32 = 8;
+
+/// What to do with prices and sizes that don't satisfy an asset's [`AssetRules`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoundingPolicy {
+    /// Reject the order with [`Error::InvalidPrice`] or [`Error::InvalidSize`].
+    #[default]
+    Strict,
+    /// Round prices to the nearest valid tick and sizes to the nearest lot.
+    AutoRound,
+    /// Send values as they are and let the exchange decide.
+    Off,
+}
+
+/// Tick and lot size rules of a single asset.
+///
+/// Sizes may have at most `sz_decimals` decimals. Prices may have at most
+/// `max_decimals - sz_decimals` decimals and [`MAX_SIG_FIGS`] significant figures, integer
+/// prices are always allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetRules {
+    pub sz_decimals: u32,
+    pub max_decimals: u32,
+}
+
+impl AssetRules {
+    pub fn perp(sz_decimals: u32) -> AssetRules {
+        AssetRules {
+            sz_decimals,
+            max_decimals: PERP_MAX_DECIMALS,
+        }
+    }
+
+    pub fn spot(sz_decimals: u32) -> AssetRules {
+        AssetRules {
+            sz_decimals,
+            max_decimals: SPOT_MAX_DECIMALS,
+        }
+    }
+
+    /// Rules for every perp in `meta`, keyed by coin name.
+    pub fn from_meta(meta: &Meta) -> HashMap<String, AssetRules> {
+        meta.universe
+            .iter()
+            .map(|asset| (asset.name.clone(), AssetRules::perp(asset.sz_decimals)))
+            .collect()
+    }
+
+    /// Rules for every spot pair in `spot_meta`, keyed by both the pair name (`"@1"`) and
+    /// `"BASE/QUOTE"`, matching [`SpotMeta::add_pair_and_name_to_index_map`]. Sizes follow the
+    /// base token's `sz_decimals`.
+    pub fn from_spot_meta(spot_meta: &SpotMeta) -> HashMap<String, AssetRules> {
+        let tokens: HashMap<usize, _> = spot_meta
+            .tokens
+            .iter()
+            .map(|token| (token.index, token))
+            .collect();
+
+        let mut rules = HashMap::new();
+        for asset in &spot_meta.universe {
+            let (Some(base), Some(quote)) =
+                (tokens.get(&asset.tokens[0]), tokens.get(&asset.tokens[1]))
+            else {
+                continue;
+            };
+            let asset_rules = AssetRules::spot(base.sz_decimals.into());
+            rules.insert(format!("{}/{}", base.name, quote.name), asset_rules);
+            rules.insert(asset.name.clone(), asset_rules);
+        }
+        rules
+    }
+
+    /// Maximum number of decimals a price may have.
+    pub fn px_decimals(&self) -> u32 {
+        self.max_decimals.saturating_sub(self.sz_decimals)
+    }
+
+    pub fn is_valid_px(&self, px: Px) -> bool {
+        px.scale() == 0 || (px.scale() <= self.px_decimals() && sig_figs(px) <= MAX_SIG_FIGS)
+    }
+
+    pub fn is_valid_sz(&self, sz: Sz) -> bool {
+        sz.scale() <= self.sz_decimals
+    }
+
+    /// Nearest valid price.
+    pub fn round_px(&self, px: Px) -> Px {
+        if px.scale() == 0 {
+            return px;
+        }
+        let sig_fig_decimals = (MAX_SIG_FIGS + px.scale()).saturating_sub(sig_figs(px));
+        px.round_dp(sig_fig_decimals.min(self.px_decimals()))
+    }
+
+    /// Nearest valid size.
+    pub fn round_sz(&self, sz: Sz) -> Sz {
+        sz.round_dp(self.sz_decimals)
+    }
+
+    /// Checks or rounds `px` according to `policy`.
+    pub fn apply_px(&self, asset: &str, px: Px, policy: RoundingPolicy) -> Result<Px> {
+        match policy {
+            RoundingPolicy::Off => Ok(px),
+            RoundingPolicy::AutoRound => Ok(self.round_px(px)),
+            RoundingPolicy::Strict if self.is_valid_px(px) => Ok(px),
+            RoundingPolicy::Strict => Err(Error::InvalidPrice {
+                asset: asset.to_string(),
+                px,
+            }),
+        }
+    }
+
+    /// Checks or rounds `sz` according to `policy`.
+    pub fn apply_sz(&self, asset: &str, sz: Sz, policy: RoundingPolicy) -> Result<Sz> {
+        match policy {
+            RoundingPolicy::Off => Ok(sz),
+            RoundingPolicy::AutoRound => Ok(self.round_sz(sz)),
+            RoundingPolicy::Strict if self.is_valid_sz(sz) => Ok(sz),
+            RoundingPolicy::Strict => Err(Error::InvalidSize {
+                asset: asset.to_string(),
+                sz,
+            }),
+        }
+    }
+}
+
+/// Number of significant figures of a normalized decimal.
+fn sig_figs(px: Px) -> u32 {
+    px.mantissa().unsigned_abs().to_string().len() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Px {
+        s.parse().unwrap()
+    }
+
+    fn spot_meta() -> SpotMeta {
+        serde_json::from_str(
+            r#"{
+                "universe": [
+                    {"tokens": [1, 0], "name": "PURR/USDC", "index": 0, "isCanonical": true},
+                    {"tokens": [2, 0], "name": "@1", "index": 1, "isCanonical": false}
+                ],
+                "tokens": [
+                    {"name": "USDC", "szDecimals": 8, "weiDecimals": 8, "index": 0, "tokenId": "0x6d1e7cde53ba9467b783cb7c530ce054", "isCanonical": true},
+                    {"name": "PURR", "szDecimals": 0, "weiDecimals": 5, "index": 1, "tokenId": "0xc1fb593aeffbeb02f85e0308e9956a90", "isCanonical": true},
+                    {"name": "HFUN", "szDecimals": 2, "weiDecimals": 8, "index": 2, "tokenId": "0xbaf265ef389da684513d98d68edf4eae", "isCanonical": false}
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_perp_rules() {
+        // e.g. ETH: sz_decimals 4, so prices have at most 2 decimals
+        let rules = AssetRules::perp(4);
+        assert_eq!(rules.px_decimals(), 2);
+
+        assert!(rules.is_valid_px(dec("1800.5")));
+        assert!(rules.is_valid_px(dec("123456")));
+        assert!(!rules.is_valid_px(dec("1800.55")));
+        assert!(!rules.is_valid_px(dec("12.345")));
+        assert!(rules.is_valid_sz(dec("0.0123")));
+        assert!(!rules.is_valid_sz(dec("0.01234")));
+
+        assert_eq!(rules.round_px(dec("1800.55")), dec("1800.6"));
+        assert_eq!(rules.round_px(dec("12.345")), dec("12.35"));
+        assert_eq!(rules.round_px(dec("123456.7")), dec("123457"));
+        assert_eq!(rules.round_px(dec("99999.5")), dec("100000"));
+        assert_eq!(rules.round_sz(dec("0.01235")), dec("0.0124"));
+    }
+
+    #[test]
+    fn test_spot_rules() {
+        let rules = AssetRules::from_spot_meta(&spot_meta());
+        assert_eq!(rules["PURR/USDC"], AssetRules::spot(0));
+        assert_eq!(rules["HFUN/USDC"], AssetRules::spot(2));
+        assert_eq!(rules["@1"], rules["HFUN/USDC"]);
+
+        let purr = rules["PURR/USDC"];
+        assert!(purr.is_valid_px(dec("0.00002378")));
+        assert!(!purr.is_valid_px(dec("0.000023781")));
+        assert!(!purr.is_valid_sz(dec("1.5")));
+        assert_eq!(purr.round_px(dec("0.123456")), dec("0.12346"));
+    }
+
+    #[test]
+    fn test_policies() {
+        let rules = AssetRules::perp(4);
+        let px = dec("1800.55");
+
+        assert_eq!(rules.apply_px("ETH", px, RoundingPolicy::Off).unwrap(), px);
+        assert_eq!(
+            rules
+                .apply_px("ETH", px, RoundingPolicy::AutoRound)
+                .unwrap(),
+            dec("1800.6")
+        );
+        assert!(matches!(
+            rules.apply_px("ETH", px, RoundingPolicy::Strict),
+            Err(Error::InvalidPrice { .. })
+        ));
+        assert!(matches!(
+            rules.apply_sz("ETH", dec("0.00001"), RoundingPolicy::Strict),
+            Err(Error::InvalidSize { .. })
+        ));
+    }
+}
@@ -1,5 +1,5 @@
 use alloy::{primitives::address, signers::local::PrivateKeySigner};
-use hyperliquid_rust_sdk::{BaseUrl, ExchangeClient};
+use hyperliquid_rust_sdk::{BaseUrl, ExchangeClient, RequestOptions};
 use log::info;
 
 #[tokio::main]
@@ -19,8 +19,12 @@ async fn main() {
     let max_fee_rate = "0.1%";
     let builder = address!("0x1ab189B7801140900C711E458212F9c76F8dAC79");
 
+    let options = RequestOptions {
+        wallet: Some(&wallet),
+        ..Default::default()
+    };
     let resp = exchange_client
-        .approve_builder_fee(builder, max_fee_rate.to_string(), Some(&wallet))
+        .approve_builder_fee(builder, max_fee_rate.to_string(), Some(options))
         .await;
     info!("resp: {resp:#?}");
 }
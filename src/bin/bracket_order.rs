@@ -0,0 +1,48 @@
+use alloy::signers::local::PrivateKeySigner;
+use hyperliquid_rust_sdk::{
+    BaseUrl, BracketLeg, BracketOrder, BracketOrderResponse, ClientLimit, ClientOrder,
+    ClientOrderRequest, ExchangeClient, Tif,
+};
+use log::info;
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    // Key was randomly generated for testing and shouldn't be used with any real funds
+    let wallet: PrivateKeySigner =
+        "e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e"
+            .parse()
+            .unwrap();
+
+    let exchange_client = ExchangeClient::new(None, wallet, Some(BaseUrl::Testnet), None, None)
+        .await
+        .unwrap();
+
+    let entry = ClientOrderRequest {
+        asset: "ETH".to_string(),
+        is_buy: true,
+        reduce_only: false,
+        limit_px: 1800.0.try_into().unwrap(),
+        sz: 0.01.try_into().unwrap(),
+        cloid: None,
+        order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Gtc }),
+    };
+
+    // Take profit 10% above the entry, stop loss as a market order 5% below it
+    let bracket = BracketOrder::new(entry)
+        .take_profit(BracketLeg::limit(
+            1980.0.try_into().unwrap(),
+            1975.0.try_into().unwrap(),
+        ))
+        .stop_loss(BracketLeg::market(1710.0.try_into().unwrap()));
+
+    let response = exchange_client.bracket_order(bracket, None).await.unwrap();
+    match response {
+        BracketOrderResponse::Ok(statuses) => {
+            info!("Entry: {:?}", statuses.entry());
+            info!("Take profit: {:?}", statuses.take_profit());
+            info!("Stop loss: {:?}", statuses.stop_loss());
+        }
+        BracketOrderResponse::Err(e) => info!("Error placing bracket order: {e}"),
+    }
+}
@@ -2,8 +2,8 @@ use std::{thread::sleep, time::Duration};
 
 use alloy::signers::local::PrivateKeySigner;
 use hyperliquid_rust_sdk::{
-    BaseUrl, ExchangeClient, ExchangeDataStatus, ExchangeResponseStatus, MarketCloseParams,
-    MarketOrderParams,
+    BaseUrl, BookPrice, BookSlippage, ExchangeClient, ExchangeDataStatus, ExchangeResponseStatus,
+    MarketCloseParams, MarketOrderParams,
 };
 use log::info;
 
@@ -26,9 +26,14 @@ async fn main() {
         is_buy: true,
         sz: 0.01,
         px: None,
-        slippage: Some(0.01), // 1% slippage
+        slippage: None,
+        // Price off the book: worst level needed to fill, plus 0.5%
+        book_slippage: Some(BookSlippage {
+            price: BookPrice::Worst,
+            buffer: 0.005,
+        }),
         cloid: None,
-        wallet: None,
+        options: None,
     };
 
     let response = exchange_client
@@ -57,8 +62,9 @@ async fn main() {
         sz: None, // Close entire position
         px: None,
         slippage: Some(0.01), // 1% slippage
+        book_slippage: None,
         cloid: None,
-        wallet: None,
+        options: None,
     };
 
     let response = exchange_client
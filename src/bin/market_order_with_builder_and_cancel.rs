@@ -27,8 +27,9 @@ async fn main() {
         sz: 0.01,
         px: None,
         slippage: Some(0.01), // 1% slippage
+        book_slippage: None,
         cloid: None,
-        wallet: None,
+        options: None,
     };
 
     let fee = 1;
@@ -66,8 +67,9 @@ async fn main() {
         sz: None, // Close entire position
         px: None,
         slippage: Some(0.01), // 1% slippage
+        book_slippage: None,
         cloid: None,
-        wallet: None,
+        options: None,
     };
 
     let response = exchange_client
@@ -0,0 +1,54 @@
+use alloy::{primitives::Address, signers::local::PrivateKeySigner};
+use hyperliquid_rust_sdk::{MockAsset, MockExchange};
+use log::info;
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    // Key was randomly generated for testing and shouldn't be used with any real funds
+    let wallet: PrivateKeySigner =
+        "e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e"
+            .parse()
+            .unwrap();
+
+    // Serves BaseUrl::Localhost
+    let exchange = MockExchange::start(
+        "127.0.0.1:3001",
+        vec![
+            MockAsset::new("BTC", 5, 60000),
+            MockAsset::new("ETH", 4, 2000),
+        ],
+    )
+    .await
+    .unwrap();
+    exchange.deposit(wallet.address(), 100_000.0);
+
+    // Some liquidity around the mark prices
+    let market_maker = Address::repeat_byte(1);
+    for (coin, px, sz) in [("BTC", 60000, 0.1), ("ETH", 2000, 1.0)] {
+        for level in 1..=5 {
+            let spread = px / 1000 * level;
+            exchange
+                .place_order(
+                    market_maker,
+                    coin,
+                    true,
+                    (px - spread).into(),
+                    sz.try_into().unwrap(),
+                )
+                .unwrap();
+            exchange
+                .place_order(
+                    market_maker,
+                    coin,
+                    false,
+                    (px + spread).into(),
+                    sz.try_into().unwrap(),
+                )
+                .unwrap();
+        }
+    }
+
+    info!("Mock exchange listening on {}", exchange.local_addr());
+    tokio::signal::ctrl_c().await.unwrap();
+}
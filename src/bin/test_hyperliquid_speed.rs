@@ -2,7 +2,7 @@ use alloy::signers::local::PrivateKeySigner;
 use hyperliquid_rust_sdk::{
     BaseUrl, ExchangeClient, InfoClient, 
     ExchangeResponseStatus, ExchangeDataStatus,
-    ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest,
+    ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest, Tif,
 };
 use std::env;
 use std::time::{Instant, Duration};
@@ -59,7 +59,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 预热：只做一次，建立连接池
     let _ = info.user_state(address).await;
     let all_mids = info.all_mids().await?;
-    let mid_price: f64 = all_mids.get(symbol).and_then(|p| p.parse().ok()).unwrap_or(2000.0);
+    let mid_price: f64 = all_mids.get(symbol).map(|p| p.to_f64()).unwrap_or(2000.0);
     let buy_px = (mid_price * 1.05).round() as f64;
     println!("预热完成 | 当前价格: ${:.2} | 下单价格: ${:.2}", mid_price, buy_px);
 
@@ -80,12 +80,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             asset: symbol.to_string(),
             is_buy: true,
             reduce_only: false,
-            limit_px: buy_px,
-            sz: round_to_decimals(0.01, sz_decimals),
+            limit_px: buy_px.try_into()?,
+            sz: round_to_decimals(0.01, sz_decimals).try_into()?,
             cloid: None,
-            order_type: ClientOrder::Limit(ClientLimit {
-                tif: "Ioc".to_string(),
-            }),
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Ioc }),
         };
         let build_time = build_start.elapsed().as_secs_f64() * 1000.0;
         
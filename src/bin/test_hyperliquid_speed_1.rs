@@ -1,7 +1,7 @@
 use alloy::signers::local::PrivateKeySigner;
 use hyperliquid_rust_sdk::{
     BaseUrl, InfoClient, ExchangeClient,
-    ClientLimit, ClientOrder, ClientOrderRequest,
+    ClientLimit, ClientOrder, ClientOrderRequest, Tif,
     ExchangeResponseStatus, ExchangeDataStatus,
 };
 use std::env;
@@ -61,7 +61,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     for i in 1..=5 {
         // 模拟策略：在计时器外获取最新价格
         let all_mids = info.all_mids().await?;
-        let mid_price: f64 = all_mids.get(symbol).unwrap().parse()?;
+        let mid_price: f64 = all_mids.get(symbol).unwrap().to_f64();
         let buy_px = (mid_price * 1.05).round() as f64; // 算好滑点价
 
         // --- 核心执行区 (Latency Sensitive) ---
@@ -72,12 +72,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             asset: symbol.to_string(),
             is_buy: true,
             reduce_only: false,
-            limit_px: buy_px,
-            sz: round_to_decimals(0.01, sz_decimals),
+            limit_px: buy_px.try_into()?,
+            sz: round_to_decimals(0.01, sz_decimals).try_into()?,
             cloid: None,
-            order_type: ClientOrder::Limit(ClientLimit {
-                tif: "Ioc".to_string(),
-            }),
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Ioc }),
         };
 
         let res = exchange.order(order, None).await;
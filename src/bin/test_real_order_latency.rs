@@ -1,7 +1,7 @@
 use alloy::signers::local::PrivateKeySigner;
 use hyperliquid_rust_sdk::{
     BaseUrl, InfoClient, ExchangeClient,
-    ClientLimit, ClientOrder, ClientOrderRequest,
+    ClientLimit, ClientOrder, ClientOrderRequest, Tif,
     ExchangeResponseStatus, ExchangeDataStatus,
 };
 use std::env;
@@ -56,7 +56,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 预热
     let _ = info.all_mids().await?;
     let all_mids = info.all_mids().await?;
-    let mid_price: f64 = all_mids.get(symbol).unwrap().parse()?;
+    let mid_price: f64 = all_mids.get(symbol).unwrap().to_f64();
     let buy_px = (mid_price * 1.05).round() as f64;
     println!("当前价格: ${:.2} | 下单价格: ${:.2}", mid_price, buy_px);
     println!();
@@ -78,12 +78,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             asset: symbol.to_string(),
             is_buy: true,
             reduce_only: false,
-            limit_px: buy_px,
-            sz: round_to_decimals(0.01, sz_decimals),
+            limit_px: buy_px.try_into()?,
+            sz: round_to_decimals(0.01, sz_decimals).try_into()?,
             cloid: None,
-            order_type: ClientOrder::Limit(ClientLimit {
-                tif: "Ioc".to_string(),
-            }),
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Ioc }),
         };
         let build_time = build_start.elapsed().as_secs_f64() * 1000.0;
         build_times.push(build_time);
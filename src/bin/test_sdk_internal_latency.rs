@@ -1,13 +1,12 @@
 use alloy::signers::local::PrivateKeySigner;
 use hyperliquid_rust_sdk::{
     BaseUrl, InfoClient, ExchangeClient,
-    ClientLimit, ClientOrder, ClientOrderRequest,
+    ClientLimit, ClientOrder, ClientOrderRequest, Tif, Grouping,
 };
 use std::env;
 use std::time::{Instant, Duration};
 use reqwest::Client;
 use std::net::{IpAddr, Ipv4Addr};
-use std::collections::HashMap;
 
 // Helper function to round to decimals
 fn round_to_decimals(value: f64, decimals: u32) -> f64 {
@@ -52,7 +51,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 预热
     let _ = info.all_mids().await?;
     let all_mids = info.all_mids().await?;
-    let mid_price: f64 = all_mids.get(symbol).unwrap().parse()?;
+    let mid_price: f64 = all_mids.get(symbol).unwrap().to_f64();
     let buy_px = (mid_price * 1.05).round() as f64;
     
     println!("目标: {} | 价格: ${:.2}", symbol, buy_px);
@@ -71,37 +70,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             asset: symbol.to_string(),
             is_buy: true,
             reduce_only: false,
-            limit_px: buy_px,
-            sz: round_to_decimals(0.01, sz_decimals),
+            limit_px: buy_px.try_into()?,
+            sz: round_to_decimals(0.01, sz_decimals).try_into()?,
             cloid: None,
-            order_type: ClientOrder::Limit(ClientLimit {
-                tif: "Ioc".to_string(),
-            }),
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Ioc }),
         };
         let step1_time = step1_start.elapsed().as_secs_f64() * 1000.0;
         println!("  步骤 1 - 构建订单请求: {:.2} ms", step1_time);
 
         // 步骤 2: 转换订单（order.convert）
         let step2_start = Instant::now();
-        let transformed_order = order.convert(&exchange.coin_to_asset)?;
+        let transformed_order = order.convert(
+            &exchange.coin_to_asset,
+            &exchange.asset_rules,
+            exchange.rounding_policy,
+        )?;
         let step2_time = step2_start.elapsed().as_secs_f64() * 1000.0;
         println!("  步骤 2 - 转换订单格式: {:.2} ms", step2_time);
 
         // 步骤 3: 构建 Action
-        use hyperliquid_rust_sdk::exchange::actions::BulkOrder;
+        use hyperliquid_rust_sdk::BulkOrder;
         let step3_start = Instant::now();
         let action = hyperliquid_rust_sdk::Actions::Order(BulkOrder {
             orders: vec![transformed_order],
-            grouping: "na".to_string(),
+            grouping: Grouping::Na,
             builder: None,
         });
         let step3_time = step3_start.elapsed().as_secs_f64() * 1000.0;
         println!("  步骤 3 - 构建 Action: {:.2} ms", step3_time);
 
         // 步骤 4: 生成 nonce
-        use hyperliquid_rust_sdk::helpers::next_nonce;
         let step4_start = Instant::now();
-        let timestamp = next_nonce();
+        let timestamp = chrono::Utc::now().timestamp_millis() as u64;
         let step4_time = step4_start.elapsed().as_secs_f64() * 1000.0;
         println!("  步骤 4 - 生成 nonce: {:.2} ms", step4_time);
 
@@ -118,10 +118,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("  步骤 5 - 计算 hash (序列化+hash): {:.2} ms", step5_time);
 
         // 步骤 6: 签名
-        use hyperliquid_rust_sdk::signature::sign_l1_action;
+        use hyperliquid_rust_sdk::sign_l1_action;
         let step6_start = Instant::now();
         let is_mainnet = exchange.http_client.is_mainnet();
-        let signature = sign_l1_action(&exchange.wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(&*exchange.wallet, connection_id, is_mainnet).await?;
         let step6_time = step6_start.elapsed().as_secs_f64() * 1000.0;
         println!("  步骤 6 - EIP-712 签名: {:.2} ms", step6_time);
 
@@ -0,0 +1,56 @@
+use alloy::signers::local::PrivateKeySigner;
+use log::info;
+
+use hyperliquid_rust_sdk::{
+    BaseUrl, ClientTwapRequest, ExchangeClient, TwapOrderStatus, TwapResponseStatus,
+};
+use std::{thread::sleep, time::Duration};
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    // Key was randomly generated for testing and shouldn't be used with any real funds
+    let wallet: PrivateKeySigner =
+        "e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e"
+            .parse()
+            .unwrap();
+
+    let exchange_client = ExchangeClient::new(None, wallet, Some(BaseUrl::Testnet), None, None)
+        .await
+        .unwrap();
+
+    let twap = ClientTwapRequest {
+        asset: "ETH".to_string(),
+        is_buy: true,
+        sz: 0.1.try_into().unwrap(),
+        reduce_only: false,
+        minutes: 10,
+        randomize: false,
+    };
+
+    let response = exchange_client.twap_order(twap, None).await.unwrap();
+    info!("TWAP order placed: {response:?}");
+
+    let twap_id = match response {
+        TwapResponseStatus::Ok(twap_response) => match twap_response.data.unwrap().status {
+            TwapOrderStatus::Running(running) => running.twap_id,
+            TwapOrderStatus::Error(e) => {
+                info!("Error placing TWAP: {e}");
+                return;
+            }
+        },
+        TwapResponseStatus::Err(e) => {
+            info!("Error placing TWAP: {e}");
+            return;
+        }
+    };
+
+    // So you can see the first slices on the UI
+    sleep(Duration::from_secs(30));
+
+    let response = exchange_client
+        .twap_cancel("ETH", twap_id, None)
+        .await
+        .unwrap();
+    info!("TWAP cancel response: {response:?}");
+}
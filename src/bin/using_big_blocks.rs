@@ -1,5 +1,5 @@
 use alloy::signers::local::PrivateKeySigner;
-use hyperliquid_rust_sdk::{BaseUrl, ExchangeClient};
+use hyperliquid_rust_sdk::{BaseUrl, ExchangeClient, RequestOptions};
 use log::info;
 
 #[tokio::main]
@@ -16,8 +16,12 @@ async fn main() {
             .await
             .unwrap();
 
+    let options = RequestOptions {
+        wallet: Some(&wallet),
+        ..Default::default()
+    };
     let res = exchange_client
-        .enable_big_blocks(false, Some(&wallet))
+        .enable_big_blocks(false, Some(options))
         .await
         .unwrap();
     info!("enable big blocks : {res:?}");
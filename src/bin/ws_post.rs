@@ -0,0 +1,57 @@
+use alloy::signers::local::PrivateKeySigner;
+use hyperliquid_rust_sdk::{
+    BaseUrl, ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest, ExchangeClient,
+    InfoClient, Tif,
+};
+use log::info;
+use tokio::time::Duration;
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    // Key was randomly generated for testing and shouldn't be used with any real funds
+    let wallet: PrivateKeySigner =
+        "e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e"
+            .parse()
+            .unwrap();
+
+    let mut info_client = InfoClient::new(None, Some(BaseUrl::Testnet)).await.unwrap();
+    let mut exchange_client = ExchangeClient::new(None, wallet, Some(BaseUrl::Testnet), None, None)
+        .await
+        .unwrap();
+
+    // Info requests and exchange actions now share a single websocket connection
+    let ws_post = info_client
+        .enable_ws_post(Duration::from_secs(5))
+        .await
+        .unwrap();
+    exchange_client.ws_post = Some(ws_post);
+
+    let l2_book = info_client.l2_snapshot("ETH".to_string()).await.unwrap();
+    info!("L2 book over websocket: {l2_book:?}");
+
+    let order = ClientOrderRequest {
+        asset: "ETH".to_string(),
+        is_buy: true,
+        reduce_only: false,
+        limit_px: 1800.0.try_into().unwrap(),
+        sz: 0.01.try_into().unwrap(),
+        cloid: None,
+        order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Gtc }),
+    };
+    let response = exchange_client.order(order, None).await.unwrap();
+    info!("Order placed over websocket: {response:?}");
+
+    let open_orders = info_client
+        .open_orders(exchange_client.wallet.address())
+        .await
+        .unwrap();
+    for order in open_orders.into_iter().filter(|o| o.coin == "ETH") {
+        let cancel = ClientCancelRequest {
+            asset: "ETH".to_string(),
+            oid: order.oid,
+        };
+        let response = exchange_client.cancel(cancel, None).await.unwrap();
+        info!("Order cancelled over websocket: {response:?}");
+    }
+}
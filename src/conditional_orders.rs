@@ -0,0 +1,770 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use alloy::primitives::Address;
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::unbounded_channel;
+
+use crate::{
+    helpers::{now_timestamp_ms, uuid_to_hex_string},
+    prelude::*,
+    BasicOrder, BboData, ClientCancelRequest, ClientCancelRequestCloid, ClientModifyRequest,
+    ClientOrder, ClientOrderRequest, ClientTrigger, Decimal, ExchangeClient, ExchangeDataStatus,
+    ExchangeResponseStatus, InfoClient, Message, OidOrCloid, OrderStatus, OrderUpdate, Px,
+    Subscription, Sz, TpSl,
+};
+
+/// How far a trailing stop's trigger price stays behind the best price.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TrailingDistance {
+    /// Fixed price distance.
+    Absolute(f64),
+    /// Fraction of the best price, 0.01 = 1%.
+    Relative(f64),
+}
+
+/// A resting stop-market trigger order whose trigger price follows the market. Sell stops
+/// (protecting a long) are raised as the price rises, buy stops lowered as it falls; neither is
+/// ever moved back.
+///
+/// The engine stops managing it once it fills, the exchange cancels it or a move is rejected.
+/// Remove it from the engine when canceling it yourself.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrailingStop {
+    pub coin: String,
+    /// The trigger order moved by the engine. Orders identified by oid get a new oid on every
+    /// modify, which the engine follows.
+    pub order: OidOrCloid,
+    /// Side of the stop order.
+    pub is_buy: bool,
+    pub sz: Sz,
+    pub distance: TrailingDistance,
+    /// Limit price of the triggered market order relative to the trigger price, 0.05 = 5%.
+    pub slippage: f64,
+    pub trigger_px: Px,
+    /// Best price seen so far: the highest for sell stops, the lowest for buy stops.
+    pub best_px: Option<Px>,
+}
+
+impl TrailingStop {
+    /// Moves the trigger price after a new price, returning the previous trigger price if it
+    /// changed.
+    fn ratchet(&mut self, px: Px, round: impl Fn(Px) -> Px) -> Option<Px> {
+        let best_px = match self.best_px {
+            Some(best_px) if self.is_buy => best_px.min(px),
+            Some(best_px) => best_px.max(px),
+            None => px,
+        };
+        self.best_px = Some(best_px);
+
+        let best = best_px.to_f64();
+        let distance = match self.distance {
+            TrailingDistance::Absolute(distance) => distance,
+            TrailingDistance::Relative(fraction) => best * fraction,
+        };
+        let trigger_px = if self.is_buy {
+            best + distance
+        } else {
+            best - distance
+        };
+        let trigger_px = round(Px::try_from(trigger_px).ok()?);
+
+        let improved = if self.is_buy {
+            trigger_px < self.trigger_px
+        } else {
+            trigger_px > self.trigger_px
+        };
+        improved.then(|| std::mem::replace(&mut self.trigger_px, trigger_px))
+    }
+
+    fn limit_px(&self) -> f64 {
+        let trigger_px = self.trigger_px.to_f64();
+        if self.is_buy {
+            trigger_px * (1.0 + self.slippage)
+        } else {
+            trigger_px * (1.0 - self.slippage)
+        }
+    }
+}
+
+/// An order on one side of a one-cancels-other pair.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OcoLeg {
+    pub coin: String,
+    pub order: OidOrCloid,
+}
+
+/// Two resting orders where a fill of one, even a partial one, cancels the other.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OcoPair {
+    pub legs: [OcoLeg; 2],
+    /// Index of the leg that filled, once one has.
+    pub filled: Option<usize>,
+}
+
+/// A resting order canceled once `expires_at` (milliseconds since the epoch) has passed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpiringOrder {
+    pub coin: String,
+    pub order: OidOrCloid,
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConditionalOrder {
+    TrailingStop(TrailingStop),
+    Oco(OcoPair),
+    Expiring(ExpiringOrder),
+}
+
+/// Everything the engine manages. Serialize it to persist the engine across restarts and pass
+/// it back to [`ConditionalOrderEngine::with_state`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConditionalOrders {
+    pub orders: BTreeMap<u64, ConditionalOrder>,
+    pub next_id: u64,
+    /// Ids with an exchange request in flight.
+    #[serde(skip)]
+    in_flight: HashSet<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    Modify { id: u64, previous_trigger_px: Px },
+    Cancel { id: u64, leg: OcoLeg },
+}
+
+/// Mid price of a `bbo` update, `None` while either side of the book is empty.
+fn bbo_mid(bbo: &BboData) -> Option<Px> {
+    let [Some(bid), Some(ask)] = bbo.bbo.as_slice() else {
+        return None;
+    };
+    bid.px
+        .checked_add(ask.px)
+        .and_then(|sum| sum.checked_mul(Decimal::new(5, 1)))
+        .ok()
+}
+
+fn is_order(order: &OidOrCloid, update: &BasicOrder) -> bool {
+    match order {
+        OidOrCloid::Oid(oid) => *oid == update.oid,
+        OidOrCloid::Cloid(cloid) => {
+            update.cloid.as_deref() == Some(uuid_to_hex_string(*cloid).as_str())
+        }
+    }
+}
+
+impl ConditionalOrders {
+    fn insert(&mut self, order: ConditionalOrder) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.orders.insert(id, order);
+        id
+    }
+
+    fn on_price(&mut self, coin: &str, px: Px, round: impl Fn(Px) -> Px) -> Vec<Command> {
+        let mut commands = Vec::new();
+        for (&id, order) in self.orders.iter_mut() {
+            let ConditionalOrder::TrailingStop(stop) = order else {
+                continue;
+            };
+            if stop.coin != coin || self.in_flight.contains(&id) {
+                continue;
+            }
+            if let Some(previous_trigger_px) = stop.ratchet(px, &round) {
+                commands.push(Command::Modify {
+                    id,
+                    previous_trigger_px,
+                });
+            }
+        }
+        self.start(commands)
+    }
+
+    fn on_order_update(&mut self, update: &OrderUpdate) -> Vec<Command> {
+        let order = &update.order;
+        let filled = update.status == OrderStatus::Filled
+            || (update.status.is_open() && order.sz < order.orig_sz);
+        let closed = !update.status.is_open() && update.status != OrderStatus::Unknown;
+
+        let mut done = Vec::new();
+        for (&id, conditional) in self.orders.iter_mut() {
+            match conditional {
+                // A modify replaces the order, which may be reported as a plain cancel
+                ConditionalOrder::TrailingStop(stop) => {
+                    if closed
+                        && update.status != OrderStatus::Canceled
+                        && is_order(&stop.order, order)
+                    {
+                        done.push(id);
+                    }
+                }
+                ConditionalOrder::Expiring(expiring) => {
+                    if closed && is_order(&expiring.order, order) {
+                        done.push(id);
+                    }
+                }
+                ConditionalOrder::Oco(pair) => {
+                    let Some(index) = pair.legs.iter().position(|leg| is_order(&leg.order, order))
+                    else {
+                        continue;
+                    };
+                    if pair.filled.is_none() && filled {
+                        pair.filled = Some(index);
+                    } else if pair.filled.is_none() && closed {
+                        // Canceled or rejected without filling, the other leg stands alone
+                        done.push(id);
+                    }
+                }
+            }
+        }
+        for id in done {
+            self.orders.remove(&id);
+        }
+        self.pending_cancels(None)
+    }
+
+    /// Cancels of filled OCO pairs not sent yet and, given the time, of expired orders.
+    fn pending_cancels(&mut self, now_ms: Option<u64>) -> Vec<Command> {
+        let commands = self
+            .orders
+            .iter()
+            .filter(|(id, _)| !self.in_flight.contains(id))
+            .filter_map(|(&id, order)| {
+                let leg = match order {
+                    ConditionalOrder::Oco(OcoPair {
+                        legs,
+                        filled: Some(index),
+                    }) => legs[1 - index].clone(),
+                    ConditionalOrder::Expiring(expiring)
+                        if now_ms.is_some_and(|now_ms| expiring.expires_at <= now_ms) =>
+                    {
+                        OcoLeg {
+                            coin: expiring.coin.clone(),
+                            order: expiring.order,
+                        }
+                    }
+                    _ => return None,
+                };
+                Some(Command::Cancel { id, leg })
+            })
+            .collect();
+        self.start(commands)
+    }
+
+    fn on_tick(&mut self, now_ms: u64) -> Vec<Command> {
+        self.pending_cancels(Some(now_ms))
+    }
+
+    fn start(&mut self, commands: Vec<Command>) -> Vec<Command> {
+        for command in &commands {
+            let (Command::Modify { id, .. } | Command::Cancel { id, .. }) = command;
+            self.in_flight.insert(*id);
+        }
+        commands
+    }
+
+    fn modified(
+        &mut self,
+        id: u64,
+        previous_trigger_px: Px,
+        result: &Result<ExchangeResponseStatus>,
+    ) {
+        self.in_flight.remove(&id);
+        let Some(ConditionalOrder::TrailingStop(stop)) = self.orders.get_mut(&id) else {
+            return;
+        };
+        let status = match result {
+            Ok(ExchangeResponseStatus::Ok(response)) => response
+                .data
+                .as_ref()
+                .and_then(|data| data.statuses.first().cloned()),
+            _ => None,
+        };
+        let new_oid = match status {
+            Some(ExchangeDataStatus::Resting(resting)) => Some(resting.oid),
+            Some(ExchangeDataStatus::Resolved(info)) => Some(info.order.oid),
+            Some(
+                ExchangeDataStatus::Success
+                | ExchangeDataStatus::WaitingForFill
+                | ExchangeDataStatus::WaitingForTrigger,
+            ) => None,
+            // Triggered right away, the stop has done its job
+            Some(ExchangeDataStatus::Filled(_)) => {
+                self.orders.remove(&id);
+                return;
+            }
+            Some(ExchangeDataStatus::Error(e)) => {
+                warn!("Dropping trailing stop {id}, the exchange rejected moving it: {e}");
+                self.orders.remove(&id);
+                return;
+            }
+            // Retried once the price moves again
+            None => {
+                stop.trigger_px = previous_trigger_px;
+                return;
+            }
+        };
+        if let (Some(new_oid), OidOrCloid::Oid(oid)) = (new_oid, &mut stop.order) {
+            *oid = new_oid;
+        }
+    }
+
+    fn canceled(&mut self, id: u64, result: &Result<ExchangeResponseStatus>) {
+        self.in_flight.remove(&id);
+        // A per-order error means the order is already gone; anything else is retried on the
+        // next tick
+        if let Ok(ExchangeResponseStatus::Ok(_)) = result {
+            self.orders.remove(&id);
+        }
+    }
+}
+
+/// Client-side order types the exchange doesn't offer natively:
+///
+/// - [`TrailingStop`]s, moved with `batchModify` as prices arrive,
+/// - [`OcoPair`]s, where a fill of one order cancels the other,
+/// - [`ExpiringOrder`]s, canceled at a given time.
+///
+/// The orders themselves are placed as usual and then handed to the engine. Prices come from
+/// `allMids` (and optionally `bbo`) and order fills from `orderUpdates`, see
+/// [`ConditionalOrderEngine::start`]. The engine is a cheap handle: clones share the same state.
+#[derive(Debug, Clone)]
+pub struct ConditionalOrderEngine {
+    exchange_client: Arc<ExchangeClient>,
+    state: Arc<Mutex<ConditionalOrders>>,
+}
+
+impl ConditionalOrderEngine {
+    pub fn new(exchange_client: Arc<ExchangeClient>) -> ConditionalOrderEngine {
+        Self::with_state(exchange_client, ConditionalOrders::default())
+    }
+
+    /// Resumes from a state saved with [`ConditionalOrderEngine::state`].
+    pub fn with_state(
+        exchange_client: Arc<ExchangeClient>,
+        state: ConditionalOrders,
+    ) -> ConditionalOrderEngine {
+        ConditionalOrderEngine {
+            exchange_client,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ConditionalOrders> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn add(&self, order: ConditionalOrder) -> u64 {
+        self.lock().insert(order)
+    }
+
+    pub fn add_trailing_stop(&self, stop: TrailingStop) -> u64 {
+        self.add(ConditionalOrder::TrailingStop(stop))
+    }
+
+    pub fn add_oco(&self, first: OcoLeg, second: OcoLeg) -> u64 {
+        self.add(ConditionalOrder::Oco(OcoPair {
+            legs: [first, second],
+            filled: None,
+        }))
+    }
+
+    pub fn add_expiring(&self, expiring: ExpiringOrder) -> u64 {
+        self.add(ConditionalOrder::Expiring(expiring))
+    }
+
+    /// Stops managing an order. The order itself is left on the exchange.
+    pub fn remove(&self, id: u64) -> Option<ConditionalOrder> {
+        self.lock().orders.remove(&id)
+    }
+
+    pub fn get(&self, id: u64) -> Option<ConditionalOrder> {
+        self.lock().orders.get(&id).cloned()
+    }
+
+    /// Snapshot of the managed orders, for persisting.
+    pub fn state(&self) -> ConditionalOrders {
+        self.lock().clone()
+    }
+
+    pub async fn handle_message(&self, message: &Message) {
+        let commands = match message {
+            Message::AllMids(all_mids) => {
+                let mut commands = Vec::new();
+                for (coin, &px) in &all_mids.data.mids {
+                    commands.extend(self.on_price(coin, px));
+                }
+                commands
+            }
+            Message::Bbo(bbo) => match bbo_mid(&bbo.data) {
+                Some(mid) => self.on_price(&bbo.data.coin, mid),
+                None => Vec::new(),
+            },
+            Message::OrderUpdates(updates) => {
+                let mut state = self.lock();
+                updates
+                    .data
+                    .iter()
+                    .flat_map(|update| state.on_order_update(update))
+                    .collect()
+            }
+            _ => return,
+        };
+        self.execute(commands).await;
+    }
+
+    fn on_price(&self, coin: &str, px: Px) -> Vec<Command> {
+        let rules = self.exchange_client.asset_rules.get(coin).copied();
+        self.lock().on_price(coin, px, |px| match rules {
+            Some(rules) => rules.round_px(px),
+            None => px,
+        })
+    }
+
+    /// Sends cancels that are due: expired orders and failed cancels being retried.
+    pub async fn tick(&self) {
+        let commands = self.lock().on_tick(now_timestamp_ms());
+        self.execute(commands).await;
+    }
+
+    async fn execute(&self, commands: Vec<Command>) {
+        for command in commands {
+            match command {
+                Command::Modify {
+                    id,
+                    previous_trigger_px,
+                } => {
+                    let Some(ConditionalOrder::TrailingStop(stop)) = self.get(id) else {
+                        continue;
+                    };
+                    let result = self.modify(&stop).await;
+                    if let Err(err) = &result {
+                        warn!("Failed to move trailing stop {id}: {err}");
+                    }
+                    self.lock().modified(id, previous_trigger_px, &result);
+                }
+                Command::Cancel { id, leg } => {
+                    let result = self.cancel(leg).await;
+                    if let Err(err) = &result {
+                        error!("Failed to cancel order of conditional order {id}: {err}");
+                    }
+                    self.lock().canceled(id, &result);
+                }
+            }
+        }
+    }
+
+    async fn modify(&self, stop: &TrailingStop) -> Result<ExchangeResponseStatus> {
+        let limit_px = Px::try_from(stop.limit_px())?;
+        let limit_px = match self.exchange_client.asset_rules.get(&stop.coin) {
+            Some(rules) => rules.round_px(limit_px),
+            None => limit_px,
+        };
+        let modify = ClientModifyRequest {
+            oid: stop.order,
+            order: ClientOrderRequest {
+                asset: stop.coin.clone(),
+                is_buy: stop.is_buy,
+                reduce_only: true,
+                limit_px,
+                sz: stop.sz,
+                cloid: match stop.order {
+                    OidOrCloid::Cloid(cloid) => Some(cloid),
+                    OidOrCloid::Oid(_) => None,
+                },
+                order_type: ClientOrder::Trigger(ClientTrigger {
+                    is_market: true,
+                    trigger_px: stop.trigger_px,
+                    tpsl: TpSl::Sl,
+                }),
+            },
+        };
+        self.exchange_client.modify(modify, None).await
+    }
+
+    async fn cancel(&self, leg: OcoLeg) -> Result<ExchangeResponseStatus> {
+        match leg.order {
+            OidOrCloid::Oid(oid) => {
+                self.exchange_client
+                    .cancel(
+                        ClientCancelRequest {
+                            asset: leg.coin,
+                            oid,
+                        },
+                        None,
+                    )
+                    .await
+            }
+            OidOrCloid::Cloid(cloid) => {
+                self.exchange_client
+                    .cancel_by_cloid(
+                        ClientCancelRequestCloid {
+                            asset: leg.coin,
+                            cloid,
+                        },
+                        None,
+                    )
+                    .await
+            }
+        }
+    }
+
+    /// Subscribes to `allMids`, `bbo` for each of `bbo_coins` and `orderUpdates` of `user`, and
+    /// drives the engine from them, checking expiries every `tick_interval`.
+    pub async fn start(
+        &self,
+        info_client: &mut InfoClient,
+        user: Address,
+        bbo_coins: &[String],
+        tick_interval: Duration,
+    ) -> Result<()> {
+        let (sender, mut receiver) = unbounded_channel();
+        let subscriptions = std::iter::once(Subscription::AllMids)
+            .chain(
+                bbo_coins
+                    .iter()
+                    .map(|coin| Subscription::Bbo { coin: coin.clone() }),
+            )
+            .chain(std::iter::once(Subscription::OrderUpdates { user }));
+        let mut subscription_ids = Vec::new();
+        for subscription in subscriptions {
+            match info_client.subscribe(subscription, sender.clone()).await {
+                Ok(subscription_id) => subscription_ids.push(subscription_id),
+                Err(err) => {
+                    info_client.unsubscribe_all(&subscription_ids).await;
+                    return Err(err);
+                }
+            }
+        }
+
+        let engine = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tick_interval);
+            loop {
+                tokio::select! {
+                    message = receiver.recv() => match message {
+                        Some(message) => engine.handle_message(&message).await,
+                        None => return,
+                    },
+                    _ = interval.tick() => engine.tick().await,
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Side;
+
+    fn px(px: f64) -> Px {
+        px.try_into().unwrap()
+    }
+
+    fn sell_stop() -> TrailingStop {
+        TrailingStop {
+            coin: "ETH".to_string(),
+            order: OidOrCloid::Oid(1),
+            is_buy: false,
+            sz: 1.0.try_into().unwrap(),
+            distance: TrailingDistance::Relative(0.05),
+            slippage: 0.1,
+            trigger_px: px(1900.0),
+            best_px: None,
+        }
+    }
+
+    fn update(oid: u64, status: OrderStatus, sz: f64) -> OrderUpdate {
+        OrderUpdate {
+            order: BasicOrder {
+                coin: "ETH".to_string(),
+                side: Side::Buy,
+                limit_px: px(1800.0),
+                sz: sz.try_into().unwrap(),
+                oid,
+                timestamp: 1,
+                orig_sz: 1.0.try_into().unwrap(),
+                cloid: None,
+            },
+            status,
+            status_timestamp: 1,
+        }
+    }
+
+    fn ok() -> Result<ExchangeResponseStatus> {
+        Ok(serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "order", "data": {"statuses": [{"resting": {"oid": 2}}]}}}"#,
+        )
+        .unwrap())
+    }
+
+    #[test]
+    fn test_trailing_stop() {
+        let mut state = ConditionalOrders::default();
+        let id = state.insert(ConditionalOrder::TrailingStop(sell_stop()));
+
+        // 5% below 1990 is below the current trigger
+        assert!(state.on_price("ETH", px(1990.0), |px| px).is_empty());
+        assert!(state.on_price("BTC", px(90000.0), |px| px).is_empty());
+
+        let commands = state.on_price("ETH", px(2100.0), |px| px);
+        assert_eq!(
+            commands,
+            vec![Command::Modify {
+                id,
+                previous_trigger_px: px(1900.0)
+            }]
+        );
+        // Nothing new is sent while the modify is in flight
+        assert!(state.on_price("ETH", px(2200.0), |px| px).is_empty());
+
+        state.modified(id, px(1900.0), &ok());
+        let Some(ConditionalOrder::TrailingStop(stop)) = state.orders.get(&id) else {
+            panic!("trailing stop removed");
+        };
+        assert_eq!(stop.trigger_px, px(1995.0));
+        assert_eq!(stop.order, OidOrCloid::Oid(2));
+
+        // Never moved back
+        assert!(state.on_price("ETH", px(2000.0), |px| px).is_empty());
+        assert_eq!(state.on_price("ETH", px(2300.0), |px| px).len(), 1);
+        state.modified(id, px(1995.0), &Err(crate::Error::WsPostTimeout));
+        let Some(ConditionalOrder::TrailingStop(stop)) = state.orders.get(&id) else {
+            panic!("trailing stop removed");
+        };
+        assert_eq!(stop.trigger_px, px(1995.0));
+
+        state.on_order_update(&update(2, OrderStatus::Filled, 0.0));
+        assert!(state.orders.is_empty());
+    }
+
+    #[test]
+    fn test_trigger_from_bbo() {
+        let bbo = |bbo: &str| -> BboData {
+            serde_json::from_str(&format!(r#"{{"coin": "ETH", "time": 1, "bbo": {bbo}}}"#)).unwrap()
+        };
+        let mid = bbo_mid(&bbo(
+            r#"[{"px": "2099.5", "sz": "1", "n": 1}, {"px": "2100.6", "sz": "2", "n": 3}]"#,
+        ))
+        .unwrap();
+        assert_eq!(mid, "2100.05".parse().unwrap());
+        assert!(bbo_mid(&bbo(r#"[null, {"px": "2100.6", "sz": "2", "n": 3}]"#)).is_none());
+
+        let mut state = ConditionalOrders::default();
+        let id = state.insert(ConditionalOrder::TrailingStop(sell_stop()));
+        assert_eq!(
+            state.on_price("ETH", mid, |px| px.round_dp(1)),
+            vec![Command::Modify {
+                id,
+                previous_trigger_px: px(1900.0)
+            }]
+        );
+        let Some(ConditionalOrder::TrailingStop(stop)) = state.orders.get(&id) else {
+            panic!("trailing stop removed");
+        };
+        assert_eq!(stop.best_px, Some(mid));
+        // 5% below 2100.05, rounded
+        assert_eq!(stop.trigger_px, px(1995.0));
+    }
+
+    #[test]
+    fn test_modify_responses() {
+        let response = |status: &str| -> Result<ExchangeResponseStatus> {
+            Ok(serde_json::from_str(&format!(
+                r#"{{"status": "ok", "response": {{"type": "order", "data": {{"statuses": [{status}]}}}}}}"#
+            ))
+            .unwrap())
+        };
+        let moved = |result| {
+            let mut state = ConditionalOrders::default();
+            let id = state.insert(ConditionalOrder::TrailingStop(sell_stop()));
+            state.on_price("ETH", px(2100.0), |px| px);
+            state.modified(id, px(1900.0), &result);
+            assert!(state.in_flight.is_empty());
+            match state.orders.get(&id) {
+                Some(ConditionalOrder::TrailingStop(stop)) => Some(stop.clone()),
+                _ => None,
+            }
+        };
+
+        for status in [r#""success""#, r#""waitingForTrigger""#] {
+            let stop = moved(response(status)).unwrap();
+            assert_eq!(stop.trigger_px, px(1995.0));
+            assert_eq!(stop.order, OidOrCloid::Oid(1));
+        }
+        assert!(moved(response(
+            r#"{"filled": {"totalSz": "1", "avgPx": "1995", "oid": 3}}"#
+        ))
+        .is_none());
+        assert!(moved(response(r#"{"error": "Invalid TP/SL price."}"#)).is_none());
+
+        // Nothing known about the outcome
+        let exchange_error = Ok(serde_json::from_str(
+            r#"{"status": "err", "response": "Cannot modify canceled or filled order"}"#,
+        )
+        .unwrap());
+        for result in [exchange_error, Err(crate::Error::WsPostTimeout)] {
+            let stop = moved(result).unwrap();
+            assert_eq!(stop.trigger_px, px(1900.0));
+        }
+    }
+
+    #[test]
+    fn test_oco_and_expiry() {
+        let mut state = ConditionalOrders::default();
+        let leg = |oid| OcoLeg {
+            coin: "ETH".to_string(),
+            order: OidOrCloid::Oid(oid),
+        };
+        let oco = state.insert(ConditionalOrder::Oco(OcoPair {
+            legs: [leg(1), leg(2)],
+            filled: None,
+        }));
+        let expiring = state.insert(ConditionalOrder::Expiring(ExpiringOrder {
+            coin: "ETH".to_string(),
+            order: OidOrCloid::Oid(3),
+            expires_at: 1000,
+        }));
+
+        // A partial fill of the second leg cancels the first
+        let commands = state.on_order_update(&update(2, OrderStatus::Open, 0.5));
+        assert_eq!(
+            commands,
+            vec![Command::Cancel {
+                id: oco,
+                leg: leg(1)
+            }]
+        );
+        state.canceled(oco, &Err(crate::Error::WsPostTimeout));
+        assert_eq!(state.on_tick(999).len(), 1);
+        state.canceled(oco, &ok());
+        assert!(!state.orders.contains_key(&oco));
+
+        assert_eq!(
+            state.on_tick(1000),
+            vec![Command::Cancel {
+                id: expiring,
+                leg: leg(3)
+            }]
+        );
+
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: ConditionalOrders = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.orders, state.orders);
+        assert_eq!(restored.next_id, 2);
+    }
+}
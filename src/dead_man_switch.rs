@@ -0,0 +1,436 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use log::{error, warn};
+use tokio::{sync::Notify, task::JoinHandle};
+
+use crate::{
+    helpers::now_timestamp_ms, prelude::*, Error, ExchangeClient, ExchangeResponseStatus, Message,
+};
+
+/// The exchange only accepts scheduled cancels at least this far in the future.
+pub const MIN_SCHEDULE_CANCEL_DELAY: Duration = Duration::from_secs(5);
+/// Number of times per UTC day a scheduled cancel may fire.
+pub const MAX_DAILY_SCHEDULE_CANCEL_TRIGGERS: u32 = 10;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone)]
+pub struct DeadManSwitchConfig {
+    /// How far ahead each re-arm schedules the cancel.
+    pub window: Duration,
+    /// How often the cancel is pushed back. Must be shorter than `window`.
+    pub rearm_interval: Duration,
+    /// Trigger when [`DeadManSwitch::heartbeat`] hasn't been called for this long. `None`
+    /// disables heartbeat checks; runtime stalls longer than `window` are always detected.
+    pub heartbeat_timeout: Option<Duration>,
+    /// Trigger when the websocket has been disconnected for this long.
+    pub disconnect_timeout: Duration,
+    pub max_daily_triggers: u32,
+}
+
+impl Default for DeadManSwitchConfig {
+    fn default() -> DeadManSwitchConfig {
+        DeadManSwitchConfig {
+            window: Duration::from_secs(30),
+            rearm_interval: Duration::from_secs(10),
+            heartbeat_timeout: None,
+            disconnect_timeout: Duration::from_secs(15),
+            max_daily_triggers: MAX_DAILY_SCHEDULE_CANCEL_TRIGGERS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerReason {
+    /// No heartbeat within the timeout, or the re-arm task itself was starved.
+    Stalled,
+    Disconnected,
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadManSwitchState {
+    /// Not armed yet, or the last re-arm failed.
+    Idle,
+    Armed,
+    /// Re-arming stopped, orders are canceled at `cancel_at`.
+    Triggered(TriggerReason),
+    /// Today's triggers are used up. Re-arming resumes on the next UTC day.
+    TriggerLimitReached,
+    /// Disarmed by the user, no cancel is scheduled.
+    Stopped,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeadManSwitchStatus {
+    pub state: DeadManSwitchState,
+    /// Time in milliseconds at which orders are canceled unless the switch re-arms.
+    pub cancel_at: Option<u64>,
+    pub last_armed_at: Option<u64>,
+    pub triggers_today: u32,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Wait,
+    Arm(u64),
+    Trigger(TriggerReason, u64),
+    Disarm,
+}
+
+#[derive(Debug)]
+struct Switch {
+    config: DeadManSwitchConfig,
+    status: DeadManSwitchStatus,
+    day: u64,
+    last_tick: Instant,
+    last_heartbeat: Instant,
+    disconnected_since: Option<Instant>,
+    manual: Option<TriggerReason>,
+    disarm: bool,
+}
+
+impl Switch {
+    fn new(config: DeadManSwitchConfig, now: Instant, now_ms: u64) -> Switch {
+        Switch {
+            config,
+            status: DeadManSwitchStatus {
+                state: DeadManSwitchState::Idle,
+                cancel_at: None,
+                last_armed_at: None,
+                triggers_today: 0,
+                last_error: None,
+            },
+            day: now_ms / DAY_MS,
+            last_tick: now,
+            last_heartbeat: now,
+            disconnected_since: None,
+            manual: None,
+            disarm: false,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        matches!(
+            self.status.state,
+            DeadManSwitchState::Triggered(_) | DeadManSwitchState::Stopped
+        )
+    }
+
+    fn poll(&mut self, now: Instant, now_ms: u64) -> Action {
+        let stalled = now.duration_since(self.last_tick) > self.config.window;
+        self.last_tick = now;
+        if self.is_done() {
+            return Action::Wait;
+        }
+
+        if now_ms / DAY_MS != self.day {
+            self.day = now_ms / DAY_MS;
+            self.status.triggers_today = 0;
+            if self.status.state == DeadManSwitchState::TriggerLimitReached {
+                self.status.state = DeadManSwitchState::Idle;
+            }
+        }
+
+        if self.disarm {
+            return Action::Disarm;
+        }
+
+        let heartbeat_lost = self
+            .config
+            .heartbeat_timeout
+            .is_some_and(|timeout| now.duration_since(self.last_heartbeat) > timeout);
+        let disconnected = self
+            .disconnected_since
+            .is_some_and(|since| now.duration_since(since) > self.config.disconnect_timeout);
+        let reason = if let Some(reason) = self.manual {
+            Some(reason)
+        } else if stalled || heartbeat_lost {
+            Some(TriggerReason::Stalled)
+        } else if disconnected {
+            Some(TriggerReason::Disconnected)
+        } else {
+            None
+        };
+
+        if self.status.triggers_today >= self.config.max_daily_triggers {
+            self.status.state = DeadManSwitchState::TriggerLimitReached;
+            return Action::Wait;
+        }
+        let delay = MIN_SCHEDULE_CANCEL_DELAY.as_millis() as u64;
+        match reason {
+            Some(reason) => Action::Trigger(reason, now_ms + delay),
+            None => Action::Arm(now_ms + self.config.window.as_millis() as u64),
+        }
+    }
+
+    fn result(&mut self, action: Action, now_ms: u64, result: Result<ExchangeResponseStatus>) {
+        let error = match result {
+            Ok(ExchangeResponseStatus::Ok(_)) => None,
+            Ok(ExchangeResponseStatus::Err(e)) => Some(e),
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(e) = &error {
+            warn!("Dead man's switch schedule cancel failed: {e}");
+        }
+
+        match action {
+            Action::Arm(cancel_at) if error.is_none() => {
+                self.status.state = DeadManSwitchState::Armed;
+                self.status.cancel_at = Some(cancel_at);
+                self.status.last_armed_at = Some(now_ms);
+            }
+            Action::Arm(_) if self.status.cancel_at.is_some_and(|at| at <= now_ms) => {
+                // Couldn't push the cancel back in time, so it has fired
+                self.status.state = DeadManSwitchState::Idle;
+                self.status.cancel_at = None;
+                self.status.triggers_today += 1;
+            }
+            Action::Trigger(reason, cancel_at) => {
+                // If the final schedule failed, the previously armed cancel still fires
+                if error.is_none() {
+                    self.status.cancel_at = Some(cancel_at);
+                }
+                self.status.state = DeadManSwitchState::Triggered(reason);
+                self.status.triggers_today += 1;
+            }
+            Action::Disarm if error.is_none() => {
+                self.status.state = DeadManSwitchState::Stopped;
+                self.status.cancel_at = None;
+            }
+            _ => {}
+        }
+        self.status.last_error = error;
+    }
+}
+
+/// Keeps a `scheduleCancel` armed `window` ahead while the process is healthy, so that all open
+/// orders are canceled if it stops. Re-arming stops, and the cancel is brought forward to the
+/// earliest time the exchange allows, when:
+///
+/// - [`DeadManSwitch::heartbeat`] isn't called within `heartbeat_timeout`, or the re-arm task
+///   itself doesn't run for longer than `window`,
+/// - the websocket has been disconnected for longer than `disconnect_timeout`, as reported
+///   through [`DeadManSwitch::handle_message`] or [`DeadManSwitch::set_connected`],
+/// - [`DeadManSwitch::trigger`] is called.
+///
+/// The exchange limits how often a scheduled cancel may fire per day. Once
+/// `max_daily_triggers` is reached the switch stops scheduling cancels until the next UTC day and
+/// reports [`DeadManSwitchState::TriggerLimitReached`].
+#[derive(Debug)]
+pub struct DeadManSwitch {
+    switch: Arc<Mutex<Switch>>,
+    wake: Arc<Notify>,
+    task: JoinHandle<()>,
+}
+
+impl DeadManSwitch {
+    pub fn start(
+        exchange_client: Arc<ExchangeClient>,
+        config: DeadManSwitchConfig,
+    ) -> Result<DeadManSwitch> {
+        if config.window < MIN_SCHEDULE_CANCEL_DELAY {
+            return Err(Error::DeadManSwitchConfig(format!(
+                "window must be at least {MIN_SCHEDULE_CANCEL_DELAY:?}"
+            )));
+        }
+        if config.rearm_interval.is_zero() || config.rearm_interval >= config.window {
+            return Err(Error::DeadManSwitchConfig(
+                "rearm_interval must be non-zero and shorter than window".to_string(),
+            ));
+        }
+
+        let rearm_interval = config.rearm_interval;
+        let switch = Arc::new(Mutex::new(Switch::new(
+            config,
+            Instant::now(),
+            now_timestamp_ms(),
+        )));
+        let wake = Arc::new(Notify::new());
+
+        let task = {
+            let switch = switch.clone();
+            let wake = wake.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(rearm_interval);
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {}
+                        _ = wake.notified() => {}
+                    }
+
+                    let now_ms = now_timestamp_ms();
+                    let action = lock(&switch).poll(Instant::now(), now_ms);
+                    let result = match action {
+                        Action::Wait => continue,
+                        Action::Arm(time) | Action::Trigger(_, time) => {
+                            exchange_client.schedule_cancel(Some(time), None).await
+                        }
+                        Action::Disarm => exchange_client.schedule_cancel(None, None).await,
+                    };
+
+                    let mut switch = lock(&switch);
+                    switch.result(action, now_timestamp_ms(), result);
+                    if let DeadManSwitchState::Triggered(reason) = switch.status.state {
+                        error!("Dead man's switch triggered: {reason:?}");
+                    }
+                    if switch.is_done() {
+                        return;
+                    }
+                }
+            })
+        };
+
+        Ok(DeadManSwitch { switch, wake, task })
+    }
+
+    /// Signals that the host process is healthy.
+    pub fn heartbeat(&self) {
+        lock(&self.switch).last_heartbeat = Instant::now();
+    }
+
+    pub fn set_connected(&self, connected: bool) {
+        let mut switch = lock(&self.switch);
+        if connected {
+            switch.disconnected_since = None;
+        } else if switch.disconnected_since.is_none() {
+            switch.disconnected_since = Some(Instant::now());
+        }
+    }
+
+    /// Tracks the websocket connection from a subscription's messages: [`Message::NoData`]
+    /// marks it disconnected, any other message connected.
+    pub fn handle_message(&self, message: &Message) {
+        self.set_connected(!matches!(message, Message::NoData));
+    }
+
+    /// Stops re-arming and lets orders be canceled as soon as possible.
+    pub fn trigger(&self) {
+        lock(&self.switch).manual = Some(TriggerReason::Manual);
+        self.wake.notify_one();
+    }
+
+    /// Removes the scheduled cancel and stops the switch without canceling orders.
+    pub fn disarm(&self) {
+        lock(&self.switch).disarm = true;
+        self.wake.notify_one();
+    }
+
+    pub fn status(&self) -> DeadManSwitchStatus {
+        lock(&self.switch).status.clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+}
+
+/// Dropping the switch stops re-arming, so the last scheduled cancel fires.
+impl Drop for DeadManSwitch {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn lock(switch: &Mutex<Switch>) -> MutexGuard<'_, Switch> {
+    switch.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW_MS: u64 = 1_700_000_000_000;
+
+    fn ok() -> Result<ExchangeResponseStatus> {
+        Ok(serde_json::from_str(r#"{"status": "ok", "response": {"type": "default"}}"#).unwrap())
+    }
+
+    fn config() -> DeadManSwitchConfig {
+        DeadManSwitchConfig {
+            heartbeat_timeout: Some(Duration::from_secs(20)),
+            max_daily_triggers: 2,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rearm_and_triggers() {
+        let start = Instant::now();
+        let mut switch = Switch::new(config(), start, NOW_MS);
+
+        let action = switch.poll(start, NOW_MS);
+        assert_eq!(action, Action::Arm(NOW_MS + 30_000));
+        switch.result(action, NOW_MS, ok());
+        assert_eq!(switch.status.state, DeadManSwitchState::Armed);
+        assert_eq!(switch.status.cancel_at, Some(NOW_MS + 30_000));
+
+        // Disconnects shorter than the timeout are tolerated
+        switch.disconnected_since = Some(start);
+        let now = start + Duration::from_secs(10);
+        assert_eq!(switch.poll(now, NOW_MS), Action::Arm(NOW_MS + 30_000));
+
+        let now = start + Duration::from_secs(16);
+        let action = switch.poll(now, NOW_MS);
+        assert_eq!(
+            action,
+            Action::Trigger(TriggerReason::Disconnected, NOW_MS + 5_000)
+        );
+        switch.result(action, NOW_MS, ok());
+        assert_eq!(
+            switch.status.state,
+            DeadManSwitchState::Triggered(TriggerReason::Disconnected)
+        );
+        assert_eq!(switch.status.triggers_today, 1);
+        assert_eq!(switch.poll(now, NOW_MS), Action::Wait);
+    }
+
+    #[test]
+    fn test_stall_and_daily_limit() {
+        let start = Instant::now();
+        let mut switch = Switch::new(config(), start, NOW_MS);
+        switch.status.triggers_today = 1;
+
+        // The re-arm task didn't run for longer than the window
+        let now = start + Duration::from_secs(31);
+        let action = switch.poll(now, NOW_MS);
+        assert_eq!(
+            action,
+            Action::Trigger(TriggerReason::Stalled, NOW_MS + 5_000)
+        );
+
+        // A failed final schedule keeps the previous cancel time
+        switch.status.cancel_at = Some(NOW_MS + 1_000);
+        switch.result(
+            action,
+            NOW_MS,
+            Err(Error::GenericRequest("down".to_string())),
+        );
+        assert_eq!(switch.status.cancel_at, Some(NOW_MS + 1_000));
+        assert_eq!(
+            switch.status.last_error.as_deref(),
+            Some("Generic request error: \"down\"")
+        );
+
+        // Limit reached, nothing is scheduled until the next UTC day
+        let mut switch = Switch::new(config(), start, NOW_MS);
+        switch.status.triggers_today = 2;
+        assert_eq!(switch.poll(start, NOW_MS), Action::Wait);
+        assert_eq!(switch.status.state, DeadManSwitchState::TriggerLimitReached);
+        assert_eq!(
+            switch.poll(start, NOW_MS + DAY_MS),
+            Action::Arm(NOW_MS + DAY_MS + 30_000)
+        );
+        assert_eq!(switch.status.triggers_today, 0);
+
+        switch.disarm = true;
+        let action = switch.poll(start, NOW_MS + DAY_MS);
+        assert_eq!(action, Action::Disarm);
+        switch.result(action, NOW_MS + DAY_MS, ok());
+        assert_eq!(switch.status.state, DeadManSwitchState::Stopped);
+    }
+}
@@ -0,0 +1,504 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{helpers::float_to_string_for_hashing, prelude::*, Error};
+
+/// Maximum number of fractional digits a [`Decimal`] can hold.
+pub(crate) const MAX_SCALE: u32 = 18;
+
+/// Exact base 10 number used for prices and sizes.
+///
+/// Values are stored as `mantissa * 10^-scale` and kept normalized (no trailing fractional
+/// zeros), so the [`Display`](fmt::Display) output is exactly the string sent on the wire.
+/// Converting from `f64` goes through the same 8 decimal formatting the exchange hashes and fails
+/// for NaN, infinities and values out of range. (De)serialization uses strings while also
+/// accepting JSON numbers.
+///
+/// The arithmetic operators panic on overflow like the integer ones do; use
+/// [`checked_add`](Decimal::checked_add), [`checked_sub`](Decimal::checked_sub) and
+/// [`checked_mul`](Decimal::checked_mul) for values that are not known to be in range, e.g. ones
+/// received from the exchange.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+/// Price of an order, level or fill.
+pub type Px = Decimal;
+/// Size of an order, level, fill or position.
+pub type Sz = Decimal;
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal {
+        mantissa: 0,
+        scale: 0,
+    };
+
+    /// `mantissa * 10^-scale`, e.g. `Decimal::new(12345, 2)` is `123.45`. Digits beyond 18
+    /// fractional places are truncated.
+    pub fn new(mantissa: i128, scale: u32) -> Decimal {
+        let mantissa = match scale.checked_sub(MAX_SCALE) {
+            Some(excess) if excess > 0 => {
+                10i128.checked_pow(excess).map_or(0, |pow| mantissa / pow)
+            }
+            _ => mantissa,
+        };
+        Decimal {
+            mantissa,
+            scale: scale.min(MAX_SCALE),
+        }
+        .normalize()
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    /// Number of fractional digits.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    pub fn is_sign_negative(&self) -> bool {
+        self.mantissa < 0
+    }
+
+    pub fn abs(&self) -> Decimal {
+        Decimal {
+            mantissa: self.mantissa.abs(),
+            scale: self.scale,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        // Going through the string gives the correctly rounded nearest f64
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    pub fn checked_add(&self, rhs: Decimal) -> Result<Decimal> {
+        let scale = self.scale.max(rhs.scale);
+        let mantissa = self
+            .rescaled(scale)
+            .zip(rhs.rescaled(scale))
+            .and_then(|(a, b)| a.checked_add(b))
+            .ok_or(Error::DecimalOverflow)?;
+        Ok(Decimal::new(mantissa, scale))
+    }
+
+    pub fn checked_sub(&self, rhs: Decimal) -> Result<Decimal> {
+        self.checked_add(-rhs)
+    }
+
+    /// Exact product; fails if the result needs more than 18 fractional digits.
+    pub fn checked_mul(&self, rhs: Decimal) -> Result<Decimal> {
+        let mantissa = self
+            .mantissa
+            .checked_mul(rhs.mantissa)
+            .ok_or(Error::DecimalOverflow)?;
+        let product = Decimal {
+            mantissa,
+            scale: self.scale + rhs.scale,
+        }
+        .normalize();
+        if product.scale > MAX_SCALE {
+            return Err(Error::DecimalOverflow);
+        }
+        Ok(product)
+    }
+
+    /// Drops digits beyond `decimals` fractional places, rounding towards zero.
+    pub fn trunc_dp(&self, decimals: u32) -> Decimal {
+        if self.scale <= decimals {
+            return *self;
+        }
+        let pow = pow10(self.scale - decimals);
+        Decimal::new(self.mantissa / pow, decimals)
+    }
+
+    /// Rounds to `decimals` fractional places, halves away from zero.
+    pub fn round_dp(&self, decimals: u32) -> Decimal {
+        if self.scale <= decimals {
+            return *self;
+        }
+        let pow = pow10(self.scale - decimals);
+        let quotient = self.mantissa / pow;
+        let remainder = (self.mantissa % pow).abs();
+        let quotient = if remainder * 2 >= pow {
+            quotient + self.mantissa.signum()
+        } else {
+            quotient
+        };
+        Decimal::new(quotient, decimals)
+    }
+
+    fn normalize(mut self) -> Decimal {
+        if self.mantissa == 0 {
+            return Decimal::ZERO;
+        }
+        while self.scale > 0 && self.mantissa % 10 == 0 {
+            self.mantissa /= 10;
+            self.scale -= 1;
+        }
+        self
+    }
+
+    /// Mantissa expressed at a larger `scale`, `None` if it does not fit.
+    fn rescaled(&self, scale: u32) -> Option<i128> {
+        self.mantissa.checked_mul(pow10(scale - self.scale))
+    }
+}
+
+fn pow10(exp: u32) -> i128 {
+    10i128.pow(exp)
+}
+
+impl FromStr for Decimal {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Decimal> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(Error::FloatStringParse);
+        }
+        if !int_part
+            .bytes()
+            .chain(frac_part.bytes())
+            .all(|b| b.is_ascii_digit())
+        {
+            return Err(Error::FloatStringParse);
+        }
+
+        let frac_part = frac_part.trim_end_matches('0');
+        let scale = frac_part.len() as u32;
+        if scale > MAX_SCALE {
+            return Err(Error::FloatStringParse);
+        }
+
+        let mut mantissa: i128 = 0;
+        for b in int_part.bytes().chain(frac_part.bytes()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(i128::from(b - b'0')))
+                .ok_or(Error::FloatStringParse)?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+
+        Ok(Decimal::new(mantissa, scale))
+    }
+}
+
+impl TryFrom<&str> for Decimal {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Decimal> {
+        s.parse()
+    }
+}
+
+impl TryFrom<f64> for Decimal {
+    type Error = Error;
+
+    /// Uses the wire formatting of the exchange: at most 8 fractional digits.
+    fn try_from(x: f64) -> Result<Decimal> {
+        if !x.is_finite() {
+            return Err(Error::FloatStringParse);
+        }
+        float_to_string_for_hashing(x).parse()
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Decimal {
+                fn from(x: $t) -> Decimal {
+                    Decimal::new(i128::from(x), 0)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i32, i64, u32, u64);
+
+impl From<Decimal> for f64 {
+    fn from(x: Decimal) -> f64 {
+        x.to_f64()
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{int_part}.{frac_part}")
+    }
+}
+
+impl fmt::Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        // Only the value with the smaller scale is rescaled; if that overflows its magnitude
+        // exceeds the other value's
+        match (self.rescaled(scale), other.rescaled(scale)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (None, _) if self.is_sign_negative() => Ordering::Less,
+            (None, _) => Ordering::Greater,
+            (_, None) if other.is_sign_negative() => Ordering::Greater,
+            (_, None) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+
+    /// Panics on overflow, see [`Decimal::checked_add`].
+    fn add(self, rhs: Decimal) -> Decimal {
+        self.checked_add(rhs).expect("decimal overflow")
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+
+    fn sub(self, rhs: Decimal) -> Decimal {
+        self + -rhs
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        Decimal {
+            mantissa: -self.mantissa,
+            scale: self.scale,
+        }
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Decimal;
+
+    /// Panics on overflow, see [`Decimal::checked_mul`].
+    fn mul(self, rhs: Decimal) -> Decimal {
+        self.checked_mul(rhs).expect("decimal overflow")
+    }
+}
+
+impl AddAssign for Decimal {
+    fn add_assign(&mut self, rhs: Decimal) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Decimal {
+    fn sub_assign(&mut self, rhs: Decimal) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sum for Decimal {
+    fn sum<I: Iterator<Item = Decimal>>(iter: I) -> Decimal {
+        iter.fold(Decimal::ZERO, Add::add)
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+struct DecimalVisitor;
+
+impl de::Visitor<'_> for DecimalVisitor {
+    type Value = Decimal;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a decimal string or number")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Decimal, E> {
+        v.parse()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<Decimal, E> {
+        Decimal::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Float(v), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<Decimal, E> {
+        Ok(Decimal::from(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<Decimal, E> {
+        Ok(Decimal::from(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Decimal, D::Error> {
+        deserializer.deserialize_any(DecimalVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(dec("0").to_string(), "0");
+        assert_eq!(dec("-0.000").to_string(), "0");
+        assert_eq!(dec("1800.50").to_string(), "1800.5");
+        assert_eq!(dec("-0.00076").to_string(), "-0.00076");
+        assert_eq!(dec(".5").to_string(), "0.5");
+        assert_eq!(dec("100").to_string(), "100");
+        assert_eq!(dec("87654321.12345678").to_string(), "87654321.12345678");
+        assert!("".parse::<Decimal>().is_err());
+        assert!("1.2.3".parse::<Decimal>().is_err());
+        assert!("1e5".parse::<Decimal>().is_err());
+        assert!("0.1234567890123456789".parse::<Decimal>().is_err());
+    }
+
+    #[test]
+    fn test_from_f64_matches_wire_format() {
+        for x in [
+            0.,
+            -0.,
+            0.00076,
+            0.00000001,
+            0.12345678,
+            87654321.12345678,
+            987654321.,
+            2000.0,
+            -1.5,
+            0.1 + 0.2,
+        ] {
+            assert_eq!(
+                Decimal::try_from(x).unwrap().to_string(),
+                float_to_string_for_hashing(x)
+            );
+        }
+        assert_eq!(Decimal::try_from(0.1 + 0.2).unwrap(), dec("0.3"));
+    }
+
+    #[test]
+    fn test_from_f64_rejects_non_finite() {
+        for x in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1e300, -1e300] {
+            assert!(matches!(Decimal::try_from(x), Err(Error::FloatStringParse)));
+        }
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let max = Decimal::new(i128::MAX, 0);
+        assert!(matches!(
+            max.checked_add(dec("1")),
+            Err(Error::DecimalOverflow)
+        ));
+        assert!(matches!(
+            max.checked_mul(dec("2")),
+            Err(Error::DecimalOverflow)
+        ));
+        assert!(matches!(
+            dec("0.000000001").checked_mul(dec("0.0000000001")),
+            Err(Error::DecimalOverflow)
+        ));
+        assert!(matches!(
+            max.checked_add(dec("0.5")),
+            Err(Error::DecimalOverflow)
+        ));
+        assert_eq!(dec("1.5").checked_sub(dec("2.25")).unwrap(), dec("-0.75"));
+        assert!(max > dec("0.5") && -max < dec("-0.5"));
+    }
+
+    #[test]
+    fn test_new_truncates_scale() {
+        assert_eq!(Decimal::new(12345, 2), dec("123.45"));
+        assert_eq!(Decimal::new(1, 18), dec("0.000000000000000001"));
+        assert_eq!(Decimal::new(123, 20), dec("0.000000000000000001"));
+        assert_eq!(Decimal::new(-1, 19), Decimal::ZERO);
+        assert_eq!(Decimal::new(i128::MAX, 100), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_arithmetic_is_exact() {
+        assert_eq!(dec("0.1") + dec("0.2"), dec("0.3"));
+        assert_eq!(dec("1.5") - dec("2.25"), dec("-0.75"));
+        assert_eq!(dec("1.5") * dec("2000.1"), dec("3000.15"));
+        assert_eq!(
+            [dec("0.1"), dec("0.7"), dec("0.2")]
+                .into_iter()
+                .sum::<Decimal>(),
+            dec("1")
+        );
+        assert!(dec("0.3") > dec("0.25"));
+        assert!(dec("-2") < dec("-1.5"));
+        assert_eq!(dec("3.0").to_f64(), 3.0);
+    }
+
+    #[test]
+    fn test_rounding() {
+        assert_eq!(dec("1.23456").trunc_dp(2), dec("1.23"));
+        assert_eq!(dec("-1.23956").trunc_dp(2), dec("-1.23"));
+        assert_eq!(dec("1.235").round_dp(2), dec("1.24"));
+        assert_eq!(dec("-1.235").round_dp(2), dec("-1.24"));
+        assert_eq!(dec("1.234").round_dp(2), dec("1.23"));
+        assert_eq!(dec("1.2").round_dp(4), dec("1.2"));
+    }
+
+    #[test]
+    fn test_serde() {
+        let parsed: Vec<Decimal> = serde_json::from_str(r#"["1.50", 2, 0.25, "-3"]"#).unwrap();
+        assert_eq!(parsed, vec![dec("1.5"), dec("2"), dec("0.25"), dec("-3")]);
+        assert_eq!(
+            serde_json::to_string(&parsed).unwrap(),
+            r#"["1.5","2","0.25","-3"]"#
+        );
+    }
+}
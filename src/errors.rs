@@ -1,4 +1,9 @@
+use std::{io::ErrorKind, sync::Arc, time::Duration};
+
 use thiserror::Error;
+use tokio_tungstenite::tungstenite;
+
+use crate::{OrderRejectReason, Px, Sz};
 
 #[derive(Error, Debug, Clone)]
 pub enum Error {
@@ -17,26 +22,42 @@ pub enum Error {
     },
     #[error("Generic request error: {0:?}")]
     GenericRequest(String),
+    #[error("HTTP error: {0}")]
+    Http(#[source] Arc<reqwest::Error>),
+    #[error("Could not parse response: {source}, body: {body}")]
+    ResponseParse {
+        #[source]
+        source: Arc<serde_json::Error>,
+        body: String,
+    },
     #[error("Chain type not allowed for this function")]
     ChainNotAllowed,
     #[error("Asset not found")]
     AssetNotFound,
     #[error("Error from Eip712 struct: {0:?}")]
     Eip712(String),
-    #[error("Json parse error: {0:?}")]
-    JsonParse(String),
+    #[error("Json parse error: {0}")]
+    JsonParse(#[source] Arc<serde_json::Error>),
+    #[error("Json serialization error: {0}")]
+    Serialize(#[source] Arc<serde_json::Error>),
+    #[error("I/O error: {0}")]
+    Io(#[source] Arc<std::io::Error>),
     #[error("Generic parse error: {0:?}")]
     GenericParse(String),
     #[error("Wallet error: {0:?}")]
     Wallet(String),
     #[error("Websocket error: {0:?}")]
     Websocket(String),
+    #[error("Websocket transport error: {0}")]
+    WebsocketTransport(#[source] Arc<tungstenite::Error>),
     #[error("Subscription not found")]
     SubscriptionNotFound,
     #[error("WS manager not instantiated")]
     WsManagerNotFound,
     #[error("WS send error: {0:?}")]
     WsSend(String),
+    #[error("WS post request timed out")]
+    WsPostTimeout,
     #[error("Reader data not found")]
     ReaderDataNotFound,
     #[error("Reader error: {0:?}")]
@@ -55,10 +76,175 @@ pub enum Error {
     RmpParse(String),
     #[error("Invalid input number")]
     FloatStringParse,
+    #[error("Decimal overflow")]
+    DecimalOverflow,
     #[error("No cloid found in order request when expected")]
     NoCloid,
     #[error("ECDSA signature failed: {0:?}")]
     SignatureFailure(String),
+    #[error("Insufficient book depth for {coin}: wanted {sz}, only {available} available")]
+    InsufficientBookDepth {
+        coin: String,
+        sz: f64,
+        available: f64,
+    },
+    #[error("Price {px} does not match the tick size of {asset}")]
+    InvalidPrice { asset: String, px: Px },
+    #[error("Size {sz} does not match the lot size of {asset}")]
+    InvalidSize { asset: String, sz: Sz },
+    #[error("Invalid dead man's switch config: {0}")]
+    DeadManSwitchConfig(String),
     #[error("Vault address not found")]
     VaultAddressNotFound,
+    #[error("Rate limit reached, retry after {retry_after:?}")]
+    RateLimited { retry_after: Duration },
+    #[error("Bracket order needs a take-profit or stop-loss leg")]
+    EmptyBracketOrder,
+    #[error("Order rejected: {0}")]
+    OrderRejected(#[from] OrderRejectReason),
+}
+
+impl Error {
+    pub(crate) fn response_parse(source: serde_json::Error, body: &str) -> Error {
+        Error::ResponseParse {
+            source: Arc::new(source),
+            body: body.to_string(),
+        }
+    }
+
+    pub(crate) fn json_parse(source: serde_json::Error) -> Error {
+        Error::JsonParse(Arc::new(source))
+    }
+
+    pub(crate) fn serialize(source: serde_json::Error) -> Error {
+        Error::Serialize(Arc::new(source))
+    }
+
+    /// HTTP status code of a failed request, `None` if no response was received.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::ClientRequest { status_code, .. } | Error::ServerRequest { status_code, .. } => {
+                Some(*status_code)
+            }
+            Error::Http(e) => e.status().map(|status| status.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// Raw body of the response the error came from, if any.
+    pub fn response_body(&self) -> Option<&str> {
+        match self {
+            Error::ClientRequest { error_message, .. }
+            | Error::ServerRequest { error_message, .. } => Some(error_message),
+            Error::ResponseParse { body, .. } => Some(body),
+            _ => None,
+        }
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            Error::Http(e) => e.is_timeout(),
+            Error::WebsocketTransport(e) => {
+                matches!(&**e, tungstenite::Error::Io(e) if e.kind() == ErrorKind::TimedOut)
+            }
+            Error::Io(e) => e.kind() == ErrorKind::TimedOut,
+            Error::WsPostTimeout => true,
+            _ => false,
+        }
+    }
+
+    /// Whether sending the same request again may succeed: timeouts, failed connections, rate
+    /// limits, server errors and retryable order rejections. A request that timed out may still
+    /// have reached the exchange, so resend the same signed payload rather than signing a new one.
+    pub fn is_retryable(&self) -> bool {
+        if self.is_timeout() {
+            return true;
+        }
+        match self {
+            Error::Http(e) => {
+                e.is_connect()
+                    || e.is_body()
+                    || e.status().is_some_and(|s| retryable_status(s.as_u16()))
+            }
+            Error::ClientRequest { status_code, .. } | Error::ServerRequest { status_code, .. } => {
+                retryable_status(*status_code)
+            }
+            Error::WebsocketTransport(e) => matches!(
+                &**e,
+                tungstenite::Error::ConnectionClosed
+                    | tungstenite::Error::AlreadyClosed
+                    | tungstenite::Error::Io(_)
+            ),
+            Error::RateLimited { .. } => true,
+            Error::OrderRejected(reason) => reason.is_retryable(),
+            _ => false,
+        }
+    }
+}
+
+fn retryable_status(status_code: u16) -> bool {
+    status_code == 429 || status_code >= 500
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(Arc::new(e))
+    }
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(e: tungstenite::Error) -> Self {
+        Error::WebsocketTransport(Arc::new(e))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(Arc::new(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn test_error_classification() {
+        let rate_limited = Error::ClientRequest {
+            status_code: 429,
+            error_code: None,
+            error_message: "Too many requests".to_string(),
+            error_data: None,
+        };
+        assert_eq!(rate_limited.status(), Some(429));
+        assert!(rate_limited.is_retryable() && !rate_limited.is_timeout());
+
+        let body = "<html>Bad gateway</html>";
+        let parse = Error::response_parse(serde_json::from_str::<u64>(body).unwrap_err(), body);
+        assert_eq!(parse.response_body(), Some(body));
+        assert!(parse.source().is_some());
+        assert!(!parse.is_retryable());
+        let json_parse = Error::json_parse(serde_json::from_str::<u64>(body).unwrap_err());
+        assert!(json_parse.source().is_some());
+
+        let timed_out = Error::from(std::io::Error::from(ErrorKind::TimedOut));
+        assert!(timed_out.source().is_some() && timed_out.is_timeout());
+
+        let closed = Error::from(tungstenite::Error::ConnectionClosed);
+        assert!(closed.source().is_some() && closed.is_retryable());
+
+        assert!(Error::WsPostTimeout.is_timeout() && Error::WsPostTimeout.is_retryable());
+        assert!(!Error::from(OrderRejectReason::TickSize).is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_connection_refused_is_retryable() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let refused = Error::from(reqwest::Client::new().post(url).send().await.unwrap_err());
+        assert!(refused.is_retryable());
+    }
 }
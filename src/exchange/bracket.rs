@@ -0,0 +1,275 @@
+use uuid::Uuid;
+
+use crate::{
+    errors::Error, prelude::*, ClientOrder, ClientOrderRequest, ClientTrigger, ExchangeDataStatus,
+    ExchangeResponseStatus, Grouping, Px, Sz, TpSl,
+};
+
+/// Take-profit or stop-loss leg of a [`BracketOrder`]. Without a `limit_px` the leg is a trigger
+/// market order, otherwise a trigger limit order at `limit_px`.
+#[derive(Debug, Clone, Copy)]
+pub struct BracketLeg {
+    pub trigger_px: Px,
+    pub limit_px: Option<Px>,
+    pub cloid: Option<Uuid>,
+}
+
+impl BracketLeg {
+    pub fn market(trigger_px: Px) -> BracketLeg {
+        BracketLeg {
+            trigger_px,
+            limit_px: None,
+            cloid: None,
+        }
+    }
+
+    pub fn limit(trigger_px: Px, limit_px: Px) -> BracketLeg {
+        BracketLeg {
+            trigger_px,
+            limit_px: Some(limit_px),
+            cloid: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum BracketParent {
+    Entry(Box<ClientOrderRequest>),
+    Position {
+        asset: String,
+        is_long: bool,
+        sz: Sz,
+    },
+}
+
+/// An entry order with take-profit and stop-loss triggers, sent as one `normalTpsl` group so the
+/// triggers only become active once the entry fills. Built with [`BracketOrder::for_position`]
+/// instead, the triggers are attached to the existing position with `positionTpsl` grouping.
+#[derive(Debug)]
+pub struct BracketOrder {
+    parent: BracketParent,
+    take_profit: Option<BracketLeg>,
+    stop_loss: Option<BracketLeg>,
+}
+
+impl BracketOrder {
+    pub fn new(entry: ClientOrderRequest) -> BracketOrder {
+        BracketOrder {
+            parent: BracketParent::Entry(Box::new(entry)),
+            take_profit: None,
+            stop_loss: None,
+        }
+    }
+
+    /// Protects an open position of `sz` on `asset`; `is_long` is the side of the position.
+    pub fn for_position(asset: &str, is_long: bool, sz: Sz) -> BracketOrder {
+        BracketOrder {
+            parent: BracketParent::Position {
+                asset: asset.to_string(),
+                is_long,
+                sz,
+            },
+            take_profit: None,
+            stop_loss: None,
+        }
+    }
+
+    pub fn take_profit(mut self, leg: BracketLeg) -> BracketOrder {
+        self.take_profit = Some(leg);
+        self
+    }
+
+    pub fn stop_loss(mut self, leg: BracketLeg) -> BracketOrder {
+        self.stop_loss = Some(leg);
+        self
+    }
+
+    pub fn grouping(&self) -> Grouping {
+        match self.parent {
+            BracketParent::Entry(_) => Grouping::NormalTpsl,
+            BracketParent::Position { .. } => Grouping::PositionTpsl,
+        }
+    }
+
+    /// Which legs will be sent, in the order the exchange reports their statuses.
+    pub fn layout(&self) -> BracketLayout {
+        BracketLayout {
+            entry: matches!(self.parent, BracketParent::Entry(_)),
+            take_profit: self.take_profit.is_some(),
+            stop_loss: self.stop_loss.is_some(),
+        }
+    }
+
+    /// Entry (if any) followed by the take-profit and stop-loss legs. The legs close the entry or
+    /// position: they are reduce only, on the opposite side and of the same size.
+    pub fn into_orders(self) -> Result<Vec<ClientOrderRequest>> {
+        if self.take_profit.is_none() && self.stop_loss.is_none() {
+            return Err(Error::EmptyBracketOrder);
+        }
+
+        let mut orders = Vec::with_capacity(3);
+        let (asset, is_buy, sz) = match self.parent {
+            BracketParent::Entry(entry) => {
+                let parent = (entry.asset.clone(), entry.is_buy, entry.sz);
+                orders.push(*entry);
+                parent
+            }
+            BracketParent::Position { asset, is_long, sz } => (asset, is_long, sz),
+        };
+
+        for (leg, tpsl) in [(self.take_profit, TpSl::Tp), (self.stop_loss, TpSl::Sl)] {
+            let Some(leg) = leg else {
+                continue;
+            };
+            orders.push(ClientOrderRequest {
+                asset: asset.clone(),
+                is_buy: !is_buy,
+                reduce_only: true,
+                limit_px: leg.limit_px.unwrap_or(leg.trigger_px),
+                sz,
+                cloid: leg.cloid,
+                order_type: ClientOrder::Trigger(ClientTrigger {
+                    is_market: leg.limit_px.is_none(),
+                    trigger_px: leg.trigger_px,
+                    tpsl,
+                }),
+            });
+        }
+        Ok(orders)
+    }
+}
+
+/// Legs present in a [`BracketOrder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BracketLayout {
+    pub entry: bool,
+    pub take_profit: bool,
+    pub stop_loss: bool,
+}
+
+impl BracketLayout {
+    /// Splits the statuses of an order response into the legs they belong to.
+    pub fn statuses(&self, response: &ExchangeResponseStatus) -> BracketOrderResponse {
+        let response = match response {
+            ExchangeResponseStatus::Ok(response) => response,
+            ExchangeResponseStatus::Err(e) => return BracketOrderResponse::Err(e.clone()),
+        };
+        BracketOrderResponse::Ok(BracketStatuses {
+            layout: *self,
+            statuses: response
+                .data
+                .as_ref()
+                .map(|data| data.statuses.clone())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Per-leg statuses of a placed [`BracketOrder`]. Legs that weren't sent are `None`. Trigger legs
+/// of a `normalTpsl` group report `waitingForFill` until the entry fills.
+#[derive(Debug, Clone)]
+pub struct BracketStatuses {
+    pub layout: BracketLayout,
+    /// Statuses in the order the legs were sent.
+    pub statuses: Vec<ExchangeDataStatus>,
+}
+
+impl BracketStatuses {
+    fn leg(&self, present: bool, index: usize) -> Option<&ExchangeDataStatus> {
+        present.then(|| self.statuses.get(index)).flatten()
+    }
+
+    pub fn entry(&self) -> Option<&ExchangeDataStatus> {
+        self.leg(self.layout.entry, 0)
+    }
+
+    pub fn take_profit(&self) -> Option<&ExchangeDataStatus> {
+        self.leg(self.layout.take_profit, self.layout.entry as usize)
+    }
+
+    pub fn stop_loss(&self) -> Option<&ExchangeDataStatus> {
+        let index = self.layout.entry as usize + self.layout.take_profit as usize;
+        self.leg(self.layout.stop_loss, index)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BracketOrderResponse {
+    Ok(BracketStatuses),
+    Err(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientLimit, Tif};
+
+    fn entry() -> ClientOrderRequest {
+        ClientOrderRequest {
+            asset: "ETH".to_string(),
+            is_buy: true,
+            reduce_only: false,
+            limit_px: 2000.0.try_into().unwrap(),
+            sz: 3.5.try_into().unwrap(),
+            cloid: None,
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Gtc }),
+        }
+    }
+
+    #[test]
+    fn test_bracket_legs() -> Result<()> {
+        let bracket = BracketOrder::new(entry())
+            .take_profit(BracketLeg::limit(2100.0.try_into()?, 2099.0.try_into()?))
+            .stop_loss(BracketLeg::market(1900.0.try_into()?));
+        assert_eq!(bracket.grouping(), Grouping::NormalTpsl);
+
+        let orders = bracket.into_orders()?;
+        assert_eq!(orders.len(), 3);
+        let (tp, sl) = (&orders[1], &orders[2]);
+        assert!(!tp.is_buy && tp.reduce_only && tp.sz == 3.5.try_into()?);
+        assert_eq!(tp.limit_px, 2099.0.try_into()?);
+        assert!(matches!(
+            &sl.order_type,
+            ClientOrder::Trigger(ClientTrigger {
+                is_market: true,
+                tpsl: TpSl::Sl,
+                ..
+            })
+        ));
+        assert_eq!(sl.limit_px, 1900.0.try_into()?);
+
+        let bracket = BracketOrder::for_position("ETH", false, 1.0.try_into()?);
+        assert_eq!(bracket.grouping(), Grouping::PositionTpsl);
+        assert!(matches!(
+            bracket.into_orders(),
+            Err(Error::EmptyBracketOrder)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bracket_statuses() {
+        let response: ExchangeResponseStatus = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "order", "data": {"statuses": [
+                {"resting": {"oid": 1}}, "waitingForFill"
+            ]}}}"#,
+        )
+        .unwrap();
+        let layout = BracketOrder::new(entry())
+            .stop_loss(BracketLeg::market(1900.0.try_into().unwrap()))
+            .layout();
+
+        let BracketOrderResponse::Ok(statuses) = layout.statuses(&response) else {
+            panic!("unexpected response");
+        };
+        assert!(matches!(
+            statuses.entry(),
+            Some(ExchangeDataStatus::Resting(_))
+        ));
+        assert!(statuses.take_profit().is_none());
+        assert!(matches!(
+            statuses.stop_loss(),
+            Some(ExchangeDataStatus::WaitingForFill)
+        ));
+    }
+}
@@ -1,42 +1,63 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use alloy::{
     primitives::{keccak256, Address, Signature, B256},
     signers::local::PrivateKeySigner,
 };
-use log::debug;
+use log::{debug, warn};
 use reqwest::Client;
-use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde::{de::DeserializeOwned, ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use uuid::Uuid;
 
 use crate::{
     exchange::{
         actions::{
             ApproveAgent, ApproveBuilderFee, BulkCancel, BulkModify, BulkOrder, ClaimRewards,
-            EvmUserModify, ScheduleCancel, SendAsset, SetReferrer, UpdateIsolatedMargin,
-            UpdateLeverage, UsdSend,
+            CreateSubAccount, EvmUserModify, Noop, ReserveRequestWeight, ScheduleCancel, SendAsset,
+            SetReferrer, SubAccountModify, SubAccountSpotTransfer, SubAccountUsdTransfer,
+            TwapCancel, TwapOrder, UpdateIsolatedMargin, UpdateLeverage, UsdSend,
         },
         cancel::{CancelRequest, CancelRequestCloid, ClientCancelRequestCloid},
         modify::{ClientModifyRequest, ModifyRequest},
-        order::{MarketCloseParams, MarketOrderParams},
-        BuilderInfo, ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest,
+        order::{BookSlippage, MarketCloseParams, MarketOrderParams},
+        BracketOrder, BracketOrderResponse, BuilderInfo, ClientCancelRequest, ClientLimit,
+        ClientOrder, ClientOrderRequest, ClientTwapRequest, Grouping, RequestOptions, Tif,
     },
     helpers::{next_nonce, uuid_to_hex_string},
     info::info_client::InfoClient,
-    meta::Meta,
+    meta::{Meta, SpotMeta},
     prelude::*,
     req::HttpClient,
-    signature::{sign_l1_action, sign_typed_data},
-    BaseUrl, BulkCancelCloid, ClassTransfer, Error, ExchangeResponseStatus, SpotSend, SpotUser,
+    signature::{sign_l1_action, sign_typed_data, HyperliquidSigner},
+    ws::{PostRequest, WsPostTransport},
+    AssetRules, BaseUrl, BulkCancelCloid, ClassTransfer, CreateSubAccountResponseStatus, Error,
+    ExchangeDataStatus, ExchangeDataStatuses, ExchangeResponse, ExchangeResponseStatus, OrderBook,
+    OrderRejectReason, RequestWeight, RetryClass, RetryPolicy, RoundingPolicy, SpotSend, SpotUser,
+    Transport, TwapCancelResponse, TwapOrderResponse, UserRateLimitResponse, UserRoleResponse,
     VaultTransfer, Withdraw3,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExchangeClient {
     pub http_client: HttpClient,
-    pub wallet: PrivateKeySigner,
-    pub meta: Meta,
+    pub wallet: Arc<dyn HyperliquidSigner>,
+    pub meta: Arc<Meta>,
     pub vault_address: Option<Address>,
-    pub coin_to_asset: HashMap<String, u32>,
+    pub coin_to_asset: Arc<HashMap<String, u32>>,
+    /// Tick and lot sizes of every perp and spot pair, keyed like `coin_to_asset`.
+    pub asset_rules: Arc<HashMap<String, AssetRules>>,
+    /// How orders that don't match `asset_rules` are handled. Defaults to
+    /// [`RoundingPolicy::Strict`].
+    pub rounding_policy: RoundingPolicy,
+    /// When set, actions are sent as websocket `post` requests, falling back to HTTP while the
+    /// websocket is disconnected. See [`InfoClient::enable_ws_post`].
+    pub ws_post: Option<WsPostTransport>,
+    /// When set, L1 actions sent without [`RequestOptions::expires_after`] are rejected by the
+    /// exchange if they land more than this long after being signed.
+    pub expiry_window: Option<Duration>,
+    /// When set, actions that fail with a retryable error are re-sent with the same signature
+    /// and nonce. Orders whose outcome stays unknown are then looked up by cloid.
+    pub retry_policy: Option<RetryPolicy>,
 }
 
 fn serialize_sig<S>(sig: &Signature, s: S) -> std::result::Result<S::Ok, S::Error>
@@ -52,12 +73,23 @@ where
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct ExchangePayload {
-    action: serde_json::Value,
+pub(crate) struct ExchangePayload {
+    pub(crate) action: serde_json::Value,
     #[serde(serialize_with = "serialize_sig")]
-    signature: Signature,
-    nonce: u64,
+    pub(crate) signature: Signature,
+    pub(crate) nonce: u64,
+    pub(crate) vault_address: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) expires_after: Option<u64>,
+}
+
+/// [`RequestOptions`] with the client's defaults filled in.
+#[derive(Clone, Copy)]
+struct ResolvedOptions<'a> {
+    wallet: &'a dyn HyperliquidSigner,
     vault_address: Option<Address>,
+    expires_after: Option<u64>,
+    nonce: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -82,10 +114,23 @@ pub enum Actions {
     EvmUserModify(EvmUserModify),
     ScheduleCancel(ScheduleCancel),
     ClaimRewards(ClaimRewards),
+    TwapOrder(TwapOrder),
+    TwapCancel(TwapCancel),
+    CreateSubAccount(CreateSubAccount),
+    SubAccountModify(SubAccountModify),
+    SubAccountTransfer(SubAccountUsdTransfer),
+    SubAccountSpotTransfer(SubAccountSpotTransfer),
+    ReserveRequestWeight(ReserveRequestWeight),
+    Noop(Noop),
 }
 
 impl Actions {
-    fn hash(&self, timestamp: u64, vault_address: Option<Address>) -> Result<B256> {
+    pub(crate) fn hash(
+        &self,
+        timestamp: u64,
+        vault_address: Option<Address>,
+        expires_after: Option<u64>,
+    ) -> Result<B256> {
         let mut bytes =
             rmp_serde::to_vec_named(self).map_err(|e| Error::RmpParse(e.to_string()))?;
         bytes.extend(timestamp.to_be_bytes());
@@ -95,6 +140,10 @@ impl Actions {
         } else {
             bytes.push(0);
         }
+        if let Some(expires_after) = expires_after {
+            bytes.push(0);
+            bytes.extend(expires_after.to_be_bytes());
+        }
         Ok(keccak256(bytes))
     }
 }
@@ -102,49 +151,149 @@ impl Actions {
 impl ExchangeClient {
     pub async fn new(
         client: Option<Client>,
-        wallet: PrivateKeySigner,
+        wallet: impl HyperliquidSigner + 'static,
         base_url: Option<BaseUrl>,
         meta: Option<Meta>,
         vault_address: Option<Address>,
     ) -> Result<ExchangeClient> {
-        let client = client.unwrap_or_default();
-        let base_url = base_url.unwrap_or(BaseUrl::Mainnet);
+        let http_client = HttpClient::new(
+            client.unwrap_or_default(),
+            base_url.unwrap_or(BaseUrl::Mainnet).get_url(),
+        );
+        Self::with_http_client(http_client, wallet, meta, vault_address).await
+    }
 
-        let info = InfoClient::new(None, Some(base_url)).await?;
+    /// Client for an API served at any other url, e.g. a local mock server.
+    pub async fn with_url(
+        client: Option<Client>,
+        wallet: impl HyperliquidSigner + 'static,
+        url: String,
+        meta: Option<Meta>,
+        vault_address: Option<Address>,
+    ) -> Result<ExchangeClient> {
+        let http_client = HttpClient::new(client.unwrap_or_default(), url);
+        Self::with_http_client(http_client, wallet, meta, vault_address).await
+    }
+
+    /// Client sending its requests through `transport`, e.g. a [`crate::MockTransport`] in
+    /// tests.
+    pub async fn with_transport(
+        transport: Arc<dyn Transport>,
+        wallet: impl HyperliquidSigner + 'static,
+        base_url: Option<BaseUrl>,
+        meta: Option<Meta>,
+        vault_address: Option<Address>,
+    ) -> Result<ExchangeClient> {
+        let base_url = base_url.unwrap_or(BaseUrl::Mainnet).get_url();
+        let http_client = HttpClient::with_transport(transport, base_url);
+        Self::with_http_client(http_client, wallet, meta, vault_address).await
+    }
+
+    async fn with_http_client(
+        http_client: HttpClient,
+        wallet: impl HyperliquidSigner + 'static,
+        meta: Option<Meta>,
+        vault_address: Option<Address>,
+    ) -> Result<ExchangeClient> {
+        let info = InfoClient::with_http_client(http_client.clone());
         let meta = if let Some(meta) = meta {
             meta
         } else {
             info.meta().await?
         };
+        let spot_meta = info.spot_meta().await?;
 
+        Ok(Self::from_meta(
+            http_client,
+            Arc::new(wallet),
+            meta,
+            &spot_meta,
+            vault_address,
+        ))
+    }
+
+    fn from_meta(
+        http_client: HttpClient,
+        wallet: Arc<dyn HyperliquidSigner>,
+        meta: Meta,
+        spot_meta: &SpotMeta,
+        vault_address: Option<Address>,
+    ) -> ExchangeClient {
         let mut coin_to_asset = HashMap::new();
         for (asset_ind, asset) in meta.universe.iter().enumerate() {
             coin_to_asset.insert(asset.name.clone(), asset_ind as u32);
         }
+        coin_to_asset = spot_meta.add_pair_and_name_to_index_map(coin_to_asset);
 
-        coin_to_asset = info
-            .spot_meta()
-            .await?
-            .add_pair_and_name_to_index_map(coin_to_asset);
+        let mut asset_rules = AssetRules::from_meta(&meta);
+        asset_rules.extend(AssetRules::from_spot_meta(spot_meta));
 
-        Ok(ExchangeClient {
+        ExchangeClient {
             wallet,
-            meta,
+            meta: Arc::new(meta),
             vault_address,
-            http_client: HttpClient {
-                client,
-                base_url: base_url.get_url(),
-            },
-            coin_to_asset,
-        })
+            http_client,
+            coin_to_asset: Arc::new(coin_to_asset),
+            asset_rules: Arc::new(asset_rules),
+            rounding_policy: RoundingPolicy::default(),
+            ws_post: None,
+            expiry_window: None,
+            retry_policy: None,
+        }
+    }
+
+    fn resolve_options<'a>(&'a self, options: Option<RequestOptions<'a>>) -> ResolvedOptions<'a> {
+        let options = options.unwrap_or_default();
+        let nonce = options.nonce.unwrap_or_else(next_nonce);
+        let expires_after = options.expires_after.or_else(|| {
+            self.expiry_window
+                .map(|window| nonce + window.as_millis() as u64)
+        });
+        ResolvedOptions {
+            wallet: options.wallet.unwrap_or(&*self.wallet),
+            vault_address: options.vault_address.or(self.vault_address),
+            expires_after,
+            nonce,
+        }
+    }
+
+    /// User-signed actions carry their own timestamp in the typed data and can't expire.
+    fn resolve_user_signed_options<'a>(
+        &'a self,
+        options: Option<RequestOptions<'a>>,
+    ) -> ResolvedOptions<'a> {
+        ResolvedOptions {
+            expires_after: None,
+            ..self.resolve_options(options)
+        }
+    }
+
+    /// Signs and sends an L1 action on behalf of the signer itself, ignoring any vault address.
+    /// Used for actions only a master account may perform.
+    async fn post_master_action<T: DeserializeOwned>(
+        &self,
+        action: Actions,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<T> {
+        let options = ResolvedOptions {
+            vault_address: None,
+            ..self.resolve_options(options)
+        };
+
+        let connection_id = action.hash(options.nonce, None, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
+        let is_mainnet = self.http_client.is_mainnet();
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
+
+        self.post(action, signature, &options).await
     }
 
-    async fn post(
+    async fn post<T: DeserializeOwned>(
         &self,
         action: serde_json::Value,
         signature: Signature,
-        nonce: u64,
-    ) -> Result<ExchangeResponseStatus> {
+        options: &ResolvedOptions<'_>,
+    ) -> Result<T> {
         let perf_profile = std::env::var("HL_PERF_PROFILE").is_ok();
         let post_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
         if perf_profile {
@@ -156,8 +305,9 @@ impl ExchangeClient {
         let exchange_payload = ExchangePayload {
             action,
             signature,
-            nonce,
-            vault_address: self.vault_address,
+            nonce: options.nonce,
+            vault_address: options.vault_address,
+            expires_after: options.expires_after,
         };
         if let Some(start) = step1_start {
             let time = start.elapsed().as_secs_f64() * 1000.0;
@@ -166,30 +316,27 @@ impl ExchangeClient {
 
         // Step 2: Serialize payload
         let step2_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
-        let res = serde_json::to_string(&exchange_payload)
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let res = serde_json::to_string(&exchange_payload).map_err(Error::serialize)?;
         if let Some(start) = step2_start {
             let time = start.elapsed().as_secs_f64() * 1000.0;
             eprintln!("[PERF] Post Step 2 - Serialize payload: {:.2}ms", time);
         }
         debug!("Sending request {res:?}");
 
-        // Step 3: HTTP request (network + server processing)
+        // Step 3: Websocket post or HTTP request (network + server processing)
         let step3_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
-        let output = &self
-            .http_client
-            .post("/exchange", res)
-            .await
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let (output, is_ws) = self.send_with_retries(&exchange_payload, &res).await?;
+        let output = &output;
         if let Some(start) = step3_start {
             let step3_time = start.elapsed().as_secs_f64() * 1000.0;
-            eprintln!("[PERF] Post Step 3 - HTTP request (network + server): {:.2}ms", step3_time);
+            let transport = if is_ws { "WS post" } else { "HTTP request" };
+            eprintln!("[PERF] Post Step 3 - {transport} (network + server): {:.2}ms", step3_time);
         }
         debug!("Response: {output}");
 
         // Step 4: Parse response
         let step4_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
-        let result = serde_json::from_str(output).map_err(|e| Error::JsonParse(e.to_string()));
+        let result = serde_json::from_str(output).map_err(|e| Error::response_parse(e, output));
         if let Some(start) = step4_start {
             let time = start.elapsed().as_secs_f64() * 1000.0;
             eprintln!("[PERF] Post Step 4 - Parse response: {:.2}ms", time);
@@ -202,38 +349,156 @@ impl ExchangeClient {
         result
     }
 
+    /// Sends a signed payload, re-sending it unchanged while the retry policy allows. Returns the
+    /// response and whether it came over the websocket.
+    async fn send_with_retries(
+        &self,
+        payload: &ExchangePayload,
+        serialized: &str,
+    ) -> Result<(String, bool)> {
+        let weight = action_weight(&payload.action);
+        let mut attempts = 1;
+        // Failure of an earlier attempt that may have reached the exchange
+        let mut ambiguous: Option<Error> = None;
+        loop {
+            self.http_client.throttle(weight).await?;
+            let error = match self.send_payload(payload, serialized).await {
+                Ok(sent) => {
+                    // The nonce was already used, so an earlier attempt did land and this
+                    // response says nothing about its outcome
+                    return match ambiguous {
+                        Some(earlier) if is_nonce_rejection(&sent.0) => Err(earlier),
+                        _ => Ok(sent),
+                    };
+                }
+                Err(e) => e,
+            };
+            match &self.retry_policy {
+                Some(policy) if policy.should_retry(&error, attempts) => {
+                    warn!("Retrying exchange request after attempt {attempts} failed: {error}");
+                    tokio::time::sleep(policy.backoff(attempts)).await;
+                    if RetryClass::of(&error).is_some_and(|class| class.is_ambiguous()) {
+                        ambiguous = Some(error);
+                    }
+                    attempts += 1;
+                }
+                _ => return Err(ambiguous.unwrap_or(error)),
+            }
+        }
+    }
+
+    async fn send_payload(
+        &self,
+        payload: &ExchangePayload,
+        serialized: &str,
+    ) -> Result<(String, bool)> {
+        if let Some(ws_post) = &self.ws_post {
+            let payload = serde_json::to_value(payload).map_err(Error::serialize)?;
+            if let Some(output) = ws_post.post(PostRequest::Action(&payload)).await? {
+                return Ok((output, true));
+            }
+        }
+        let output = self
+            .http_client
+            .post("/exchange", serialized.to_string())
+            .await?;
+        Ok((output, false))
+    }
+
+    /// Turns a failed order request whose outcome is unknown into the orders' current statuses,
+    /// looked up by cloid. Only done with a retry policy and when every order has a cloid;
+    /// otherwise, or if none of the orders is found, the original result is returned.
+    async fn resolve_lost_orders(
+        &self,
+        cloids: &[Option<Uuid>],
+        result: Result<ExchangeResponseStatus>,
+        options: &ResolvedOptions<'_>,
+    ) -> Result<ExchangeResponseStatus> {
+        let error = match result {
+            Err(error) if self.retry_policy.is_some() && outcome_unknown(&error) => error,
+            result => return result,
+        };
+        let Some(cloids) = cloids.iter().copied().collect::<Option<Vec<Uuid>>>() else {
+            return Err(error);
+        };
+
+        let user = match options.vault_address {
+            Some(vault_address) => vault_address,
+            None => match self.account_of(options.wallet.address()).await {
+                Ok(user) => user,
+                Err(_) => return Err(error),
+            },
+        };
+        let info_client = self.info_client();
+        let mut statuses = Vec::with_capacity(cloids.len());
+        for cloid in cloids {
+            let Ok(response) = info_client.query_order_by_cloid(user, cloid).await else {
+                return Err(error);
+            };
+            statuses.push(match response.order {
+                Some(order) => ExchangeDataStatus::Resolved(Box::new(order)),
+                None => ExchangeDataStatus::Error(format!("Order {cloid} not found")),
+            });
+        }
+        if !statuses
+            .iter()
+            .any(|status| matches!(status, ExchangeDataStatus::Resolved(_)))
+        {
+            return Err(error);
+        }
+        Ok(ExchangeResponseStatus::Ok(ExchangeResponse {
+            response_type: "order".to_string(),
+            data: Some(ExchangeDataStatuses { statuses }),
+        }))
+    }
+
+    /// Account whose orders `signer` places: the master account for agent wallets.
+    async fn account_of(&self, signer: Address) -> Result<Address> {
+        match self.info_client().user_role(signer).await? {
+            UserRoleResponse::Agent { user } => Ok(user),
+            _ => Ok(signer),
+        }
+    }
+
+    /// Syncs the client's rate limiter with the address-based budget of the account it acts
+    /// for.
+    pub async fn sync_rate_limit(&self) -> Result<UserRateLimitResponse> {
+        let user = self.vault_address.unwrap_or_else(|| self.wallet.address());
+        self.http_client.sync_rate_limit(user).await
+    }
+
     pub async fn enable_big_blocks(
         &self,
         using_big_blocks: bool,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
+        let options = self.resolve_options(options);
 
-        let timestamp = next_nonce();
+        let timestamp = options.nonce;
 
         let action = Actions::EvmUserModify(EvmUserModify { using_big_blocks });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn usdc_transfer(
         &self,
         amount: &str,
         destination: &str,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
+        let options = self.resolve_user_signed_options(options);
         let hyperliquid_chain = if self.http_client.is_mainnet() {
             "Mainnet".to_string()
         } else {
             "Testnet".to_string()
         };
 
-        let timestamp = next_nonce();
+        let timestamp = options.nonce;
         let usd_send = UsdSend {
             signature_chain_id: 421614,
             hyperliquid_chain,
@@ -241,34 +506,33 @@ impl ExchangeClient {
             amount: amount.to_string(),
             time: timestamp,
         };
-        let signature = sign_typed_data(&usd_send, wallet)?;
-        let action = serde_json::to_value(Actions::UsdSend(usd_send))
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let signature = sign_typed_data(&usd_send, options.wallet).await?;
+        let action = serde_json::to_value(Actions::UsdSend(usd_send)).map_err(Error::serialize)?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn class_transfer(
         &self,
         usdc: f64,
         to_perp: bool,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
         // payload expects usdc without decimals
         let usdc = (usdc * 1e6).round() as u64;
-        let wallet = wallet.unwrap_or(&self.wallet);
+        let options = self.resolve_options(options);
 
-        let timestamp = next_nonce();
+        let timestamp = options.nonce;
 
         let action = Actions::SpotUser(SpotUser {
             class_transfer: ClassTransfer { usdc, to_perp },
         });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn send_asset(
@@ -278,9 +542,9 @@ impl ExchangeClient {
         destination_dex: &str,
         token: &str,
         amount: f64,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
+        let options = self.resolve_user_signed_options(options);
 
         let hyperliquid_chain = if self.http_client.is_mainnet() {
             "Mainnet".to_string()
@@ -288,10 +552,10 @@ impl ExchangeClient {
             "Testnet".to_string()
         };
 
-        let timestamp = next_nonce();
+        let timestamp = options.nonce;
 
         // Build fromSubAccount string (similar to Python SDK)
-        let from_sub_account = self
+        let from_sub_account = options
             .vault_address
             .map_or_else(String::new, |vault_addr| format!("{vault_addr:?}"));
 
@@ -307,11 +571,11 @@ impl ExchangeClient {
             nonce: timestamp,
         };
 
-        let signature = sign_typed_data(&send_asset, wallet)?;
-        let action = serde_json::to_value(Actions::SendAsset(send_asset))
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let signature = sign_typed_data(&send_asset, options.wallet).await?;
+        let action =
+            serde_json::to_value(Actions::SendAsset(send_asset)).map_err(Error::serialize)?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn vault_transfer(
@@ -319,27 +583,27 @@ impl ExchangeClient {
         is_deposit: bool,
         usd: u64,
         vault_address: Option<Address>,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let vault_address = self
+        let options = self.resolve_options(options);
+        let vault_address = options
             .vault_address
             .or(vault_address)
             .ok_or(Error::VaultAddressNotFound)?;
-        let wallet = wallet.unwrap_or(&self.wallet);
 
-        let timestamp = next_nonce();
+        let timestamp = options.nonce;
 
         let action = Actions::VaultTransfer(VaultTransfer {
             vault_address,
             is_deposit,
             usd,
         });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn market_open(
@@ -348,22 +612,27 @@ impl ExchangeClient {
     ) -> Result<ExchangeResponseStatus> {
         let slippage = params.slippage.unwrap_or(0.05); // Default 5% slippage
         let (px, sz_decimals) = self
-            .calculate_slippage_price(params.asset, params.is_buy, slippage, params.px)
+            .calculate_slippage_price(
+                params.asset,
+                params.is_buy,
+                params.sz,
+                slippage,
+                params.px,
+                params.book_slippage,
+            )
             .await?;
 
         let order = ClientOrderRequest {
             asset: params.asset.to_string(),
             is_buy: params.is_buy,
             reduce_only: false,
-            limit_px: px,
-            sz: round_to_decimals(params.sz, sz_decimals),
+            limit_px: px.try_into()?,
+            sz: round_to_decimals(params.sz, sz_decimals).try_into()?,
             cloid: params.cloid,
-            order_type: ClientOrder::Limit(ClientLimit {
-                tif: "Ioc".to_string(),
-            }),
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Ioc }),
         };
 
-        self.order(order, params.wallet).await
+        self.order(order, params.options).await
     }
 
     pub async fn market_open_with_builder(
@@ -373,22 +642,28 @@ impl ExchangeClient {
     ) -> Result<ExchangeResponseStatus> {
         let slippage = params.slippage.unwrap_or(0.05); // Default 5% slippage
         let (px, sz_decimals) = self
-            .calculate_slippage_price(params.asset, params.is_buy, slippage, params.px)
+            .calculate_slippage_price(
+                params.asset,
+                params.is_buy,
+                params.sz,
+                slippage,
+                params.px,
+                params.book_slippage,
+            )
             .await?;
 
         let order = ClientOrderRequest {
             asset: params.asset.to_string(),
             is_buy: params.is_buy,
             reduce_only: false,
-            limit_px: px,
-            sz: round_to_decimals(params.sz, sz_decimals),
+            limit_px: px.try_into()?,
+            sz: round_to_decimals(params.sz, sz_decimals).try_into()?,
             cloid: params.cloid,
-            order_type: ClientOrder::Limit(ClientLimit {
-                tif: "Ioc".to_string(),
-            }),
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Ioc }),
         };
 
-        self.order_with_builder(order, params.wallet, builder).await
+        self.order_with_builder(order, params.options, builder)
+            .await
     }
 
     pub async fn market_close(
@@ -396,15 +671,14 @@ impl ExchangeClient {
         params: MarketCloseParams<'_>,
     ) -> Result<ExchangeResponseStatus> {
         let slippage = params.slippage.unwrap_or(0.05); // Default 5% slippage
-        let wallet = params.wallet.unwrap_or(&self.wallet);
-
-        let base_url = match self.http_client.base_url.as_str() {
-            "https://api.hyperliquid.xyz" => BaseUrl::Mainnet,
-            "https://api.hyperliquid-testnet.xyz" => BaseUrl::Testnet,
-            _ => return Err(Error::GenericRequest("Invalid base URL".to_string())),
+        let options = params.options.unwrap_or_default();
+        // The position belongs to the vault or sub-account when acting for one
+        let account = match options.vault_address.or(self.vault_address) {
+            Some(vault_address) => vault_address,
+            None => options.wallet.unwrap_or(&*self.wallet).address(),
         };
-        let info_client = InfoClient::new(None, Some(base_url)).await?;
-        let user_state = info_client.user_state(wallet.address()).await?;
+
+        let user_state = self.info_client().user_state(account).await?;
 
         let position = user_state
             .asset_positions
@@ -412,72 +686,83 @@ impl ExchangeClient {
             .find(|p| p.position.coin == params.asset)
             .ok_or(Error::AssetNotFound)?;
 
-        let szi = position
-            .position
-            .szi
-            .parse::<f64>()
-            .map_err(|_| Error::FloatStringParse)?;
+        let szi = position.position.szi.to_f64();
 
+        let sz = params.sz.unwrap_or_else(|| szi.abs());
         let (px, sz_decimals) = self
-            .calculate_slippage_price(params.asset, szi < 0.0, slippage, params.px)
+            .calculate_slippage_price(
+                params.asset,
+                szi < 0.0,
+                sz,
+                slippage,
+                params.px,
+                params.book_slippage,
+            )
             .await?;
 
-        let sz = round_to_decimals(params.sz.unwrap_or_else(|| szi.abs()), sz_decimals);
+        let sz = round_to_decimals(sz, sz_decimals);
 
         let order = ClientOrderRequest {
             asset: params.asset.to_string(),
             is_buy: szi < 0.0,
             reduce_only: true,
-            limit_px: px,
-            sz,
+            limit_px: px.try_into()?,
+            sz: sz.try_into()?,
             cloid: params.cloid,
-            order_type: ClientOrder::Limit(ClientLimit {
-                tif: "Ioc".to_string(),
-            }),
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Ioc }),
         };
 
-        self.order(order, Some(wallet)).await
+        self.order(order, params.options).await
+    }
+
+    /// Info client sharing this client's HTTP connection pool and base URL.
+    fn info_client(&self) -> InfoClient {
+        InfoClient::with_http_client(self.http_client.clone())
+    }
+
+    /// Name of `asset` in `allMids` and `l2Book` data. Spot pairs other than PURR/USDC are
+    /// listed by their `@index` name rather than `BASE/QUOTE`.
+    fn market_coin(&self, asset: &str) -> Result<String> {
+        let &index = self.coin_to_asset.get(asset).ok_or(Error::AssetNotFound)?;
+        if index >= 10000 {
+            let spot_name = format!("@{}", index - 10000);
+            if self.coin_to_asset.contains_key(&spot_name) {
+                return Ok(spot_name);
+            }
+        }
+        Ok(asset.to_string())
     }
 
     async fn calculate_slippage_price(
         &self,
         asset: &str,
         is_buy: bool,
+        sz: f64,
         slippage: f64,
         px: Option<f64>,
+        book_slippage: Option<BookSlippage>,
     ) -> Result<(f64, u32)> {
-        // Use cached meta instead of fetching it again - this avoids ~800ms HTTP request
-        let asset_meta = self
-            .meta
-            .universe
-            .iter()
-            .find(|a| a.name == asset)
-            .ok_or(Error::AssetNotFound)?;
-
-        let sz_decimals = asset_meta.sz_decimals;
-        let max_decimals: u32 = if self.coin_to_asset[asset] < 10000 {
-            6
-        } else {
-            8
-        };
-        let price_decimals = max_decimals.saturating_sub(sz_decimals);
+        let rules = self.asset_rules.get(asset).ok_or(Error::AssetNotFound)?;
+        let sz_decimals = rules.sz_decimals;
+
+        if let Some(book_slippage) = book_slippage {
+            let snapshot = self
+                .info_client()
+                .l2_snapshot(self.market_coin(asset)?)
+                .await?;
+            let px = book_slippage.limit_px(&OrderBook::from_snapshot(&snapshot), is_buy, sz)?;
+            debug!("px from book: {px:?}");
+            return Ok((rules.round_px(px.try_into()?).to_f64(), sz_decimals));
+        }
 
         let px = if let Some(px) = px {
             px
         } else {
-            // Only create InfoClient if we need to fetch the price
-            let base_url = match self.http_client.base_url.as_str() {
-                "https://api.hyperliquid.xyz" => BaseUrl::Mainnet,
-                "https://api.hyperliquid-testnet.xyz" => BaseUrl::Testnet,
-                _ => return Err(Error::GenericRequest("Invalid base URL".to_string())),
-            };
-            let info_client = InfoClient::new(None, Some(base_url)).await?;
-            let all_mids = info_client.all_mids().await?;
+            let all_mids = self.info_client().all_mids().await?;
             all_mids
-                .get(asset)
+                .get(&self.market_coin(asset)?)
                 .ok_or(Error::AssetNotFound)?
-                .parse::<f64>()
-                .map_err(|_| Error::FloatStringParse)?
+                .to_f64()
         };
 
         debug!("px before slippage: {px:?}");
@@ -489,7 +774,7 @@ impl ExchangeClient {
         let px = px * slippage_factor;
 
         // Round to the correct number of decimal places and significant figures
-        let px = round_to_significant_and_decimal(px, 5, price_decimals);
+        let px = rules.round_px(px.try_into()?).to_f64();
 
         debug!("px after slippage: {px:?}");
         Ok((px, sz_decimals))
@@ -498,25 +783,25 @@ impl ExchangeClient {
     pub async fn order(
         &self,
         order: ClientOrderRequest,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        self.bulk_order(vec![order], wallet).await
+        self.bulk_order(vec![order], options).await
     }
 
     pub async fn order_with_builder(
         &self,
         order: ClientOrderRequest,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
         builder: BuilderInfo,
     ) -> Result<ExchangeResponseStatus> {
-        self.bulk_order_with_builder(vec![order], wallet, builder)
+        self.bulk_order_with_builder(vec![order], options, builder)
             .await
     }
 
     pub async fn bulk_order(
         &self,
         orders: Vec<ClientOrderRequest>,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
         // Performance profiling (controlled by HL_PERF_PROFILE env var)
         let perf_profile = std::env::var("HL_PERF_PROFILE").is_ok();
@@ -525,11 +810,12 @@ impl ExchangeClient {
             eprintln!("[PERF] ========== bulk_order 开始 ==========");
         }
         
-        let wallet = wallet.unwrap_or(&self.wallet);
+        let options = self.resolve_options(options);
+        let cloids: Vec<_> = orders.iter().map(|order| order.cloid).collect();
         
         // Step 1: Generate nonce
         let step1_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
-        let timestamp = next_nonce();
+        let timestamp = options.nonce;
         if let Some(start) = step1_start {
             let time = start.elapsed().as_secs_f64() * 1000.0;
             eprintln!("[PERF] Step 1 - Generate nonce: {:.2}ms", time);
@@ -539,7 +825,11 @@ impl ExchangeClient {
         let step2_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
         let mut transformed_orders = Vec::new();
         for order in orders {
-            transformed_orders.push(order.convert(&self.coin_to_asset)?);
+            transformed_orders.push(order.convert(
+                &self.coin_to_asset,
+                &self.asset_rules,
+                self.rounding_policy,
+            )?);
         }
         if let Some(start) = step2_start {
             let time = start.elapsed().as_secs_f64() * 1000.0;
@@ -550,7 +840,7 @@ impl ExchangeClient {
         let step3_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
         let action = Actions::Order(BulkOrder {
             orders: transformed_orders,
-            grouping: "na".to_string(),
+            grouping: Grouping::Na,
             builder: None,
         });
         if let Some(start) = step3_start {
@@ -560,7 +850,7 @@ impl ExchangeClient {
 
         // Step 4: Calculate connection_id (hash)
         let step4_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
-        let connection_id = action.hash(timestamp, self.vault_address)?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
         if let Some(start) = step4_start {
             let time = start.elapsed().as_secs_f64() * 1000.0;
             eprintln!("[PERF] Step 4 - Calculate hash: {:.2}ms", time);
@@ -568,7 +858,7 @@ impl ExchangeClient {
 
         // Step 5: Serialize action to JSON
         let step5_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
         if let Some(start) = step5_start {
             let time = start.elapsed().as_secs_f64() * 1000.0;
             eprintln!("[PERF] Step 5 - Serialize to JSON: {:.2}ms", time);
@@ -577,7 +867,7 @@ impl ExchangeClient {
         // Step 6: Sign
         let step6_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
         if let Some(start) = step6_start {
             let time = start.elapsed().as_secs_f64() * 1000.0;
             eprintln!("[PERF] Step 6 - Sign: {:.2}ms", time);
@@ -585,7 +875,8 @@ impl ExchangeClient {
 
         // Step 7: Post (includes HTTP request and server processing)
         let step7_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
-        let result = self.post(action, signature, timestamp).await;
+        let result = self.post(action, signature, &options).await;
+        let result = self.resolve_lost_orders(&cloids, result, &options).await;
         if let (Some(start), Some(total)) = (step7_start, total_start) {
             let step7_time = start.elapsed().as_secs_f64() * 1000.0;
             let total_time = total.elapsed().as_secs_f64() * 1000.0;
@@ -599,48 +890,78 @@ impl ExchangeClient {
     pub async fn bulk_order_with_builder(
         &self,
         orders: Vec<ClientOrderRequest>,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
         mut builder: BuilderInfo,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
-
         builder.builder = builder.builder.to_lowercase();
+        self.grouped_order(orders, Grouping::Na, Some(builder), options)
+            .await
+    }
+
+    /// Sends the entry and trigger legs of `bracket` as one grouped order.
+    pub async fn bracket_order(
+        &self,
+        bracket: BracketOrder,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<BracketOrderResponse> {
+        let layout = bracket.layout();
+        let grouping = bracket.grouping();
+        let response = self
+            .grouped_order(bracket.into_orders()?, grouping, None, options)
+            .await?;
+        Ok(layout.statuses(&response))
+    }
+
+    async fn grouped_order(
+        &self,
+        orders: Vec<ClientOrderRequest>,
+        grouping: Grouping,
+        builder: Option<BuilderInfo>,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<ExchangeResponseStatus> {
+        let options = self.resolve_options(options);
+        let timestamp = options.nonce;
+        let cloids: Vec<_> = orders.iter().map(|order| order.cloid).collect();
 
         let mut transformed_orders = Vec::new();
 
         for order in orders {
-            transformed_orders.push(order.convert(&self.coin_to_asset)?);
+            transformed_orders.push(order.convert(
+                &self.coin_to_asset,
+                &self.asset_rules,
+                self.rounding_policy,
+            )?);
         }
 
         let action = Actions::Order(BulkOrder {
             orders: transformed_orders,
-            grouping: "na".to_string(),
-            builder: Some(builder),
+            grouping,
+            builder,
         });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
 
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
-        self.post(action, signature, timestamp).await
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
+        let result = self.post(action, signature, &options).await;
+        self.resolve_lost_orders(&cloids, result, &options).await
     }
 
     pub async fn cancel(
         &self,
         cancel: ClientCancelRequest,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        self.bulk_cancel(vec![cancel], wallet).await
+        self.bulk_cancel(vec![cancel], options).await
     }
 
     pub async fn bulk_cancel(
         &self,
         cancels: Vec<ClientCancelRequest>,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        let options = self.resolve_options(options);
+        let timestamp = options.nonce;
 
         let mut transformed_cancels = Vec::new();
         for cancel in cancels.into_iter() {
@@ -657,66 +978,70 @@ impl ExchangeClient {
         let action = Actions::Cancel(BulkCancel {
             cancels: transformed_cancels,
         });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
 
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn modify(
         &self,
         modify: ClientModifyRequest,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        self.bulk_modify(vec![modify], wallet).await
+        self.bulk_modify(vec![modify], options).await
     }
 
     pub async fn bulk_modify(
         &self,
         modifies: Vec<ClientModifyRequest>,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        let options = self.resolve_options(options);
+        let timestamp = options.nonce;
 
         let mut transformed_modifies = Vec::new();
         for modify in modifies.into_iter() {
             transformed_modifies.push(ModifyRequest {
                 oid: modify.oid,
-                order: modify.order.convert(&self.coin_to_asset)?,
+                order: modify.order.convert(
+                    &self.coin_to_asset,
+                    &self.asset_rules,
+                    self.rounding_policy,
+                )?,
             });
         }
 
         let action = Actions::BatchModify(BulkModify {
             modifies: transformed_modifies,
         });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
 
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn cancel_by_cloid(
         &self,
         cancel: ClientCancelRequestCloid,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        self.bulk_cancel_by_cloid(vec![cancel], wallet).await
+        self.bulk_cancel_by_cloid(vec![cancel], options).await
     }
 
     pub async fn bulk_cancel_by_cloid(
         &self,
         cancels: Vec<ClientCancelRequestCloid>,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        let options = self.resolve_options(options);
+        let timestamp = options.nonce;
 
         let mut transformed_cancels: Vec<CancelRequestCloid> = Vec::new();
         for cancel in cancels.into_iter() {
@@ -734,12 +1059,12 @@ impl ExchangeClient {
             cancels: transformed_cancels,
         });
 
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn update_leverage(
@@ -747,11 +1072,11 @@ impl ExchangeClient {
         leverage: u32,
         coin: &str,
         is_cross: bool,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
+        let options = self.resolve_options(options);
 
-        let timestamp = next_nonce();
+        let timestamp = options.nonce;
 
         let &asset_index = self.coin_to_asset.get(coin).ok_or(Error::AssetNotFound)?;
         let action = Actions::UpdateLeverage(UpdateLeverage {
@@ -759,24 +1084,24 @@ impl ExchangeClient {
             is_cross,
             leverage,
         });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn update_isolated_margin(
         &self,
         amount: f64,
         coin: &str,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
+        let options = self.resolve_options(options);
 
         let amount = (amount * 1_000_000.0).round() as i64;
-        let timestamp = next_nonce();
+        let timestamp = options.nonce;
 
         let &asset_index = self.coin_to_asset.get(coin).ok_or(Error::AssetNotFound)?;
         let action = Actions::UpdateIsolatedMargin(UpdateIsolatedMargin {
@@ -784,19 +1109,19 @@ impl ExchangeClient {
             is_buy: true,
             ntli: amount,
         });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn approve_agent(
         &self,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<(B256, ExchangeResponseStatus)> {
-        let wallet = wallet.unwrap_or(&self.wallet);
+        let options = self.resolve_user_signed_options(options);
         let agent = PrivateKeySigner::random();
 
         let hyperliquid_chain = if self.http_client.is_mainnet() {
@@ -805,7 +1130,7 @@ impl ExchangeClient {
             "Testnet".to_string()
         };
 
-        let nonce = next_nonce();
+        let nonce = options.nonce;
         let approve_agent = ApproveAgent {
             signature_chain_id: 421614,
             hyperliquid_chain,
@@ -813,26 +1138,29 @@ impl ExchangeClient {
             agent_name: None,
             nonce,
         };
-        let signature = sign_typed_data(&approve_agent, wallet)?;
-        let action = serde_json::to_value(Actions::ApproveAgent(approve_agent))
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
-        Ok((agent.to_bytes(), self.post(action, signature, nonce).await?))
+        let signature = sign_typed_data(&approve_agent, options.wallet).await?;
+        let action =
+            serde_json::to_value(Actions::ApproveAgent(approve_agent)).map_err(Error::serialize)?;
+        Ok((
+            agent.to_bytes(),
+            self.post(action, signature, &options).await?,
+        ))
     }
 
     pub async fn withdraw_from_bridge(
         &self,
         amount: &str,
         destination: &str,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
+        let options = self.resolve_user_signed_options(options);
         let hyperliquid_chain = if self.http_client.is_mainnet() {
             "Mainnet".to_string()
         } else {
             "Testnet".to_string()
         };
 
-        let timestamp = next_nonce();
+        let timestamp = options.nonce;
         let withdraw = Withdraw3 {
             signature_chain_id: 421614,
             hyperliquid_chain,
@@ -840,11 +1168,11 @@ impl ExchangeClient {
             amount: amount.to_string(),
             time: timestamp,
         };
-        let signature = sign_typed_data(&withdraw, wallet)?;
-        let action = serde_json::to_value(Actions::Withdraw3(withdraw))
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let signature = sign_typed_data(&withdraw, options.wallet).await?;
+        let action =
+            serde_json::to_value(Actions::Withdraw3(withdraw)).map_err(Error::serialize)?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn spot_transfer(
@@ -852,16 +1180,16 @@ impl ExchangeClient {
         amount: &str,
         destination: &str,
         token: &str,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
+        let options = self.resolve_user_signed_options(options);
         let hyperliquid_chain = if self.http_client.is_mainnet() {
             "Mainnet".to_string()
         } else {
             "Testnet".to_string()
         };
 
-        let timestamp = next_nonce();
+        let timestamp = options.nonce;
         let spot_send = SpotSend {
             signature_chain_id: 421614,
             hyperliquid_chain,
@@ -870,39 +1198,110 @@ impl ExchangeClient {
             time: timestamp,
             token: token.to_string(),
         };
-        let signature = sign_typed_data(&spot_send, wallet)?;
-        let action = serde_json::to_value(Actions::SpotSend(spot_send))
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
+        let signature = sign_typed_data(&spot_send, options.wallet).await?;
+        let action =
+            serde_json::to_value(Actions::SpotSend(spot_send)).map_err(Error::serialize)?;
+
+        self.post(action, signature, &options).await
+    }
+
+    /// Creates a sub-account of the signer, returning its address.
+    pub async fn create_sub_account(
+        &self,
+        name: &str,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<CreateSubAccountResponseStatus> {
+        let action = Actions::CreateSubAccount(CreateSubAccount {
+            name: name.to_string(),
+        });
+        self.post_master_action(action, options).await
+    }
 
-        self.post(action, signature, timestamp).await
+    pub async fn rename_sub_account(
+        &self,
+        sub_account: Address,
+        name: &str,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::SubAccountModify(SubAccountModify {
+            sub_account_user: format!("{sub_account:?}"),
+            name: name.to_string(),
+        });
+        self.post_master_action(action, options).await
+    }
+
+    /// Moves USDC between the signer's perp account and a sub-account. `usd` is in millionths
+    /// of a dollar, i.e. 1_000_000 = $1; `is_deposit` moves funds into the sub-account.
+    pub async fn sub_account_transfer(
+        &self,
+        sub_account: Address,
+        is_deposit: bool,
+        usd: u64,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::SubAccountTransfer(SubAccountUsdTransfer {
+            sub_account_user: format!("{sub_account:?}"),
+            is_deposit,
+            usd,
+        });
+        self.post_master_action(action, options).await
+    }
+
+    /// Moves a spot token between the signer and a sub-account. `token` is `NAME:tokenId`, e.g.
+    /// `USDC:0xeb62eee3685fc4c43992febcd9e75443`.
+    pub async fn sub_account_spot_transfer(
+        &self,
+        sub_account: Address,
+        is_deposit: bool,
+        token: &str,
+        amount: &str,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<ExchangeResponseStatus> {
+        let action = Actions::SubAccountSpotTransfer(SubAccountSpotTransfer {
+            sub_account_user: format!("{sub_account:?}"),
+            is_deposit,
+            token: token.to_string(),
+            amount: amount.to_string(),
+        });
+        self.post_master_action(action, options).await
+    }
+
+    /// Cheap handle that trades on behalf of `sub_account` (or a vault), signing with the same
+    /// wallet and sharing the HTTP client and meta. For a single call, setting
+    /// [`RequestOptions::vault_address`] does the same.
+    pub fn as_sub_account(&self, sub_account: Address) -> ExchangeClient {
+        ExchangeClient {
+            vault_address: Some(sub_account),
+            ..self.clone()
+        }
     }
 
     pub async fn set_referrer(
         &self,
         code: String,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        let options = self.resolve_options(options);
+        let timestamp = options.nonce;
 
         let action = Actions::SetReferrer(SetReferrer { code });
 
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
 
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
-        self.post(action, signature, timestamp).await
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
+        self.post(action, signature, &options).await
     }
 
     pub async fn approve_builder_fee(
         &self,
         builder: Address,
         max_fee_rate: String,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        let options = self.resolve_user_signed_options(options);
+        let timestamp = options.nonce;
 
         let hyperliquid_chain = if self.http_client.is_mainnet() {
             "Mainnet".to_string()
@@ -917,44 +1316,118 @@ impl ExchangeClient {
             max_fee_rate,
             nonce: timestamp,
         };
-        let signature = sign_typed_data(&approve_builder_fee, wallet)?;
+        let signature = sign_typed_data(&approve_builder_fee, options.wallet).await?;
         let action = serde_json::to_value(Actions::ApproveBuilderFee(approve_builder_fee))
-            .map_err(|e| Error::JsonParse(e.to_string()))?;
+            .map_err(Error::serialize)?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 
     pub async fn schedule_cancel(
         &self,
         time: Option<u64>,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        let options = self.resolve_options(options);
+        let timestamp = options.nonce;
 
         let action = Actions::ScheduleCancel(ScheduleCancel { time });
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
+    }
+
+    pub async fn twap_order(
+        &self,
+        twap: ClientTwapRequest,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<TwapOrderResponse> {
+        let options = self.resolve_options(options);
+        let timestamp = options.nonce;
+
+        let twap = twap.convert(&self.coin_to_asset, &self.asset_rules, self.rounding_policy)?;
+        let action = Actions::TwapOrder(TwapOrder { twap });
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
+        let is_mainnet = self.http_client.is_mainnet();
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
+
+        self.post(action, signature, &options).await
+    }
+
+    pub async fn twap_cancel(
+        &self,
+        asset: &str,
+        twap_id: u64,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<TwapCancelResponse> {
+        let options = self.resolve_options(options);
+        let timestamp = options.nonce;
+
+        let &asset = self.coin_to_asset.get(asset).ok_or(Error::AssetNotFound)?;
+        let action = Actions::TwapCancel(TwapCancel { asset, twap_id });
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
+        let is_mainnet = self.http_client.is_mainnet();
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
+
+        self.post(action, signature, &options).await
     }
 
     pub async fn claim_rewards(
         &self,
-        wallet: Option<&PrivateKeySigner>,
+        options: Option<RequestOptions<'_>>,
     ) -> Result<ExchangeResponseStatus> {
-        let wallet = wallet.unwrap_or(&self.wallet);
-        let timestamp = next_nonce();
+        let options = self.resolve_options(options);
+        let timestamp = options.nonce;
 
         let action = Actions::ClaimRewards(ClaimRewards {});
-        let connection_id = action.hash(timestamp, self.vault_address)?;
-        let action = serde_json::to_value(&action).map_err(|e| Error::JsonParse(e.to_string()))?;
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
+        let is_mainnet = self.http_client.is_mainnet();
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
+
+        self.post(action, signature, &options).await
+    }
+
+    /// Buys `weight` additional requests of address-based budget, paid from the perp balance.
+    /// Call [`ExchangeClient::sync_rate_limit`] afterwards to update the rate limiter.
+    pub async fn reserve_request_weight(
+        &self,
+        weight: u64,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<ExchangeResponseStatus> {
+        let options = self.resolve_options(options);
+        let timestamp = options.nonce;
+
+        let action = Actions::ReserveRequestWeight(ReserveRequestWeight { weight });
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
+        let is_mainnet = self.http_client.is_mainnet();
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
+
+        self.post(action, signature, &options).await
+    }
+
+    /// Does nothing but use up a nonce. Sent with [`RequestOptions::nonce`] set to the nonce of
+    /// an action that was signed but not sent, it makes sure that action can never land.
+    pub async fn noop(
+        &self,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<ExchangeResponseStatus> {
+        let options = self.resolve_options(options);
+        let timestamp = options.nonce;
+
+        let action = Actions::Noop(Noop {});
+        let connection_id = action.hash(timestamp, options.vault_address, options.expires_after)?;
+        let action = serde_json::to_value(&action).map_err(Error::serialize)?;
         let is_mainnet = self.http_client.is_mainnet();
-        let signature = sign_l1_action(wallet, connection_id, is_mainnet)?;
+        let signature = sign_l1_action(options.wallet, connection_id, is_mainnet).await?;
 
-        self.post(action, signature, timestamp).await
+        self.post(action, signature, &options).await
     }
 }
 
@@ -963,12 +1436,25 @@ fn round_to_decimals(value: f64, decimals: u32) -> f64 {
     (value * factor).round() / factor
 }
 
-fn round_to_significant_and_decimal(value: f64, sig_figs: u32, max_decimals: u32) -> f64 {
-    let abs_value = value.abs();
-    let magnitude = abs_value.log10().floor() as i32;
-    let scale = 10f64.powi(sig_figs as i32 - magnitude - 1);
-    let rounded = (abs_value * scale).round() / scale;
-    round_to_decimals(rounded.copysign(value), max_decimals)
+/// Rate limit weight of a serialized action: batches count once per order, cancel or modify.
+fn action_weight(action: &serde_json::Value) -> RequestWeight {
+    let batch_len = ["orders", "cancels", "modifies"]
+        .iter()
+        .find_map(|key| action.get(key)?.as_array().map(Vec::len))
+        .unwrap_or(1);
+    RequestWeight::action(batch_len)
+}
+
+/// Whether the exchange may have processed a request that failed with `error`, including
+/// connections dropped after the request was sent, which are not retried.
+fn outcome_unknown(error: &Error) -> bool {
+    RetryClass::of(error).is_some_and(|class| class.is_ambiguous())
+        || matches!(error, Error::Http(e) if e.is_request() && !e.is_connect())
+}
+
+fn is_nonce_rejection(output: &str) -> bool {
+    serde_json::from_str::<ExchangeResponseStatus>(output)
+        .is_ok_and(|response| response.reject_reason() == Some(OrderRejectReason::InvalidNonce))
 }
 
 #[cfg(test)]
@@ -976,11 +1462,19 @@ mod tests {
     use std::str::FromStr;
 
     use alloy::primitives::address;
+    use tokio::{
+        io::BufReader,
+        net::TcpListener,
+        sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    };
 
     use super::*;
     use crate::{
-        exchange::order::{Limit, OrderRequest, Trigger},
-        Order,
+        exchange::order::{BookPrice, Limit, OrderRequest, Trigger},
+        helpers::float_to_string_for_hashing,
+        http_server::{read_request, write_response},
+        BookLevel, BracketLeg, ClientTrigger, L2BookData, OidOrCloid, Order, TpSl,
+        TwapCancelStatus, TwapData, TwapOrderStatus, TwapResponse, TwapResponseStatus, TwapRunning,
     };
 
     fn get_wallet() -> Result<PrivateKeySigner> {
@@ -990,8 +1484,8 @@ mod tests {
             .map_err(|e| Error::Wallet(e.to_string()))
     }
 
-    #[test]
-    fn test_limit_order_action_hashing() -> Result<()> {
+    #[tokio::test]
+    async fn test_limit_order_action_hashing() -> Result<()> {
         let wallet = get_wallet()?;
         let action = Actions::Order(BulkOrder {
             orders: vec![OrderRequest {
@@ -1000,27 +1494,25 @@ mod tests {
                 limit_px: "2000.0".to_string(),
                 sz: "3.5".to_string(),
                 reduce_only: false,
-                order_type: Order::Limit(Limit {
-                    tif: "Ioc".to_string(),
-                }),
+                order_type: Order::Limit(Limit { tif: Tif::Ioc }),
                 cloid: None,
             }],
-            grouping: "na".to_string(),
+            grouping: Grouping::Na,
             builder: None,
         });
-        let connection_id = action.hash(1583838, None)?;
+        let connection_id = action.hash(1583838, None, None)?;
 
-        let signature = sign_l1_action(&wallet, connection_id, true)?;
+        let signature = sign_l1_action(&wallet, connection_id, true).await?;
         assert_eq!(signature.to_string(), "0x77957e58e70f43b6b68581f2dc42011fc384538a2e5b7bf42d5b936f19fbb67360721a8598727230f67080efee48c812a6a4442013fd3b0eed509171bef9f23f1c");
 
-        let signature = sign_l1_action(&wallet, connection_id, false)?;
+        let signature = sign_l1_action(&wallet, connection_id, false).await?;
         assert_eq!(signature.to_string(), "0xcd0925372ff1ed499e54883e9a6205ecfadec748f80ec463fe2f84f1209648776377961965cb7b12414186b1ea291e95fd512722427efcbcfb3b0b2bcd4d79d01c");
 
         Ok(())
     }
 
-    #[test]
-    fn test_limit_order_action_hashing_with_cloid() -> Result<()> {
+    #[tokio::test]
+    async fn test_limit_order_action_hashing_with_cloid() -> Result<()> {
         let cloid = uuid::Uuid::from_str("1e60610f-0b3d-4205-97c8-8c1fed2ad5ee")
             .map_err(|_e| uuid::Uuid::new_v4());
         let wallet = get_wallet()?;
@@ -1031,35 +1523,126 @@ mod tests {
                 limit_px: "2000.0".to_string(),
                 sz: "3.5".to_string(),
                 reduce_only: false,
-                order_type: Order::Limit(Limit {
-                    tif: "Ioc".to_string(),
-                }),
+                order_type: Order::Limit(Limit { tif: Tif::Ioc }),
                 cloid: Some(uuid_to_hex_string(cloid.unwrap())),
             }],
-            grouping: "na".to_string(),
+            grouping: Grouping::Na,
             builder: None,
         });
-        let connection_id = action.hash(1583838, None)?;
+        let connection_id = action.hash(1583838, None, None)?;
 
-        let signature = sign_l1_action(&wallet, connection_id, true)?;
+        let signature = sign_l1_action(&wallet, connection_id, true).await?;
         assert_eq!(signature.to_string(), "0xd3e894092eb27098077145714630a77bbe3836120ee29df7d935d8510b03a08f456de5ec1be82aa65fc6ecda9ef928b0445e212517a98858cfaa251c4cd7552b1c");
 
-        let signature = sign_l1_action(&wallet, connection_id, false)?;
+        let signature = sign_l1_action(&wallet, connection_id, false).await?;
         assert_eq!(signature.to_string(), "0x3768349dbb22a7fd770fc9fc50c7b5124a7da342ea579b309f58002ceae49b4357badc7909770919c45d850aabb08474ff2b7b3204ae5b66d9f7375582981f111c");
 
         Ok(())
     }
 
-    #[test]
-    fn test_tpsl_order_action_hashing() -> Result<()> {
+    #[tokio::test]
+    async fn test_limit_order_action_hashing_with_expires_after() -> Result<()> {
+        let wallet = get_wallet()?;
+        let action = Actions::Order(BulkOrder {
+            orders: vec![OrderRequest {
+                asset: 1,
+                is_buy: true,
+                limit_px: "2000.0".to_string(),
+                sz: "3.5".to_string(),
+                reduce_only: false,
+                order_type: Order::Limit(Limit { tif: Tif::Ioc }),
+                cloid: None,
+            }],
+            grouping: Grouping::Na,
+            builder: None,
+        });
+
+        // msgpack(action) ++ nonce ++ vault flag [++ vault] ++ 0x00 ++ expiresAfter
+        let vault_address = address!("0x1719884eb866cb12b2287399b15f7db5e7d775ea");
+        let mut bytes = rmp_serde::to_vec_named(&action).unwrap();
+        bytes.extend(1583838u64.to_be_bytes());
+        bytes.push(1);
+        bytes.extend(vault_address);
+        bytes.push(0);
+        bytes.extend(1583848u64.to_be_bytes());
+        assert_eq!(
+            action.hash(1583838, Some(vault_address), Some(1583848))?,
+            keccak256(bytes)
+        );
+
+        let connection_id = action.hash(1583838, None, Some(1583848))?;
+
+        let signature = sign_l1_action(&wallet, connection_id, true).await?;
+        assert_eq!(signature.to_string(), "0x0eff4a1ca6d5a3b504690c1ea8fa1a908b655134f51066af4d098220d49ce6e523ce1cdb49fd365bffd7bd3a19f4ad6bccefded5d29ac6cd5e8db8d67e2fe1181c");
+
+        let signature = sign_l1_action(&wallet, connection_id, false).await?;
+        assert_eq!(signature.to_string(), "0xd2e146cacb24a5db4544e20759bde669967d319194ff840c7d87f160b41b9e064c863800a9c387391df01a65fcc9a8112468cb6e68ff326f7573ccfba3fb6a971b");
+
+        let connection_id = action.hash(1583838, Some(vault_address), Some(1583848))?;
+
+        let signature = sign_l1_action(&wallet, connection_id, true).await?;
+        assert_eq!(signature.to_string(), "0xc078fe94f4a59afea9dbe8a4f57e82addbdbd3b359b61da90965b01db011ccfd6449ee70ae51904aec1fda38e59d478f4bb05c7faf2822b920f226715a87e7ea1b");
+
+        let signature = sign_l1_action(&wallet, connection_id, false).await?;
+        assert_eq!(signature.to_string(), "0xef71e8ddacd2c876a86dae62b0c1319b7b4060a54540be0fe933fe548fb0e6a2486509475abddd522dc5a2bf2a9dfa974aaa398ae9d9ab37ca29e905d2849b1e1c");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_modify_action_hashing() -> Result<()> {
+        let cloid = uuid::Uuid::from_str("1e60610f-0b3d-4205-97c8-8c1fed2ad5ee").unwrap();
+        let wallet = get_wallet()?;
+        for (oid, mainnet_signature, testnet_signature) in [
+            (
+                OidOrCloid::Oid(82),
+                "0x41636508f072cbf23ba0201d0ff2c6be0d50b44295e6fd80baf34feaea73267a65c1813d82b1e25072ad809e79843298b9a17914d102a050370ef5275abe0c841b",
+                "0x50f6a757f0209f4a57b8d41f7c392b9cab2bd45bb753b4dd692bf80713995b09163535128179f2aefbad52695b1c31ee99de06fa163cbbc38e1e203a2c8ae8bc1c",
+            ),
+            (
+                OidOrCloid::Cloid(cloid),
+                "0x20dd9a504586472dcfdae357341c1c3873914afe67578d165cff40a7ddda74fa340e1ec6b86614278a7593502c080fa8dd542a8a3f38cb93819ee49f278a58951b",
+                "0xdbb6c5f8f479259bf7599bcdfa2be3f666fa46c08d7af98d98b9ff7e1788fbd170ee83dac10023a1d4b6d5b82e902b4cecc0b2129b741678aead506063ef8d3d1c",
+            ),
+        ] {
+            let action = Actions::BatchModify(BulkModify {
+                modifies: vec![ModifyRequest {
+                    oid,
+                    order: OrderRequest {
+                        asset: 1,
+                        is_buy: true,
+                        limit_px: "2000.0".to_string(),
+                        sz: "3.5".to_string(),
+                        reduce_only: false,
+                        order_type: Order::Limit(Limit {
+                            tif: Tif::Gtc,
+                        }),
+                        cloid: Some(uuid_to_hex_string(cloid)),
+                    },
+                }],
+            });
+            let connection_id = action.hash(1583838, None, None)?;
+
+            let signature = sign_l1_action(&wallet, connection_id, true).await?;
+            assert_eq!(signature.to_string(), mainnet_signature);
+
+            let signature = sign_l1_action(&wallet, connection_id, false).await?;
+            assert_eq!(signature.to_string(), testnet_signature);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tpsl_order_action_hashing() -> Result<()> {
         for (tpsl, mainnet_signature, testnet_signature) in [
             (
-                "tp",
+                TpSl::Tp,
                 "0xb91e5011dff15e4b4a40753730bda44972132e7b75641f3cac58b66159534a170d422ee1ac3c7a7a2e11e298108a2d6b8da8612caceaeeb3e571de3b2dfda9e41b",
                 "0x6df38b609904d0d4439884756b8f366f22b3a081801dbdd23f279094a2299fac6424cb0cdc48c3706aeaa368f81959e91059205403d3afd23a55983f710aee871b"
             ),
             (
-                "sl",
+                TpSl::Sl,
                 "0x8456d2ace666fce1bee1084b00e9620fb20e810368841e9d4dd80eb29014611a0843416e51b1529c22dd2fc28f7ff8f6443875635c72011f60b62cbb8ce90e2d1c",
                 "0xeb5bdb52297c1d19da45458758bd569dcb24c07e5c7bd52cf76600fd92fdd8213e661e21899c985421ec018a9ee7f3790e7b7d723a9932b7b5adcd7def5354601c"
             )
@@ -1076,27 +1659,133 @@ mod tests {
                         order_type: Order::Trigger(Trigger {
                             trigger_px: "2000.0".to_string(),
                             is_market: true,
-                            tpsl: tpsl.to_string(),
+                            tpsl,
                         }),
                         cloid: None,
                     }
                 ],
-                grouping: "na".to_string(),
+                grouping: Grouping::Na,
                 builder: None,
             });
-            let connection_id = action.hash(1583838, None)?;
+            let connection_id = action.hash(1583838, None, None)?;
 
-            let signature = sign_l1_action(&wallet, connection_id, true)?;
+            let signature = sign_l1_action(&wallet, connection_id, true).await?;
             assert_eq!(signature.to_string(), mainnet_signature);
 
-            let signature = sign_l1_action(&wallet, connection_id, false)?;
+            let signature = sign_l1_action(&wallet, connection_id, false).await?;
             assert_eq!(signature.to_string(), testnet_signature);
         }
         Ok(())
     }
 
     #[test]
-    fn test_cancel_action_hashing() -> Result<()> {
+    fn test_client_order_wire_format() -> Result<()> {
+        let coin_to_asset = HashMap::from([("ETH".to_string(), 1)]);
+        for (px, sz, trigger_px) in [
+            (2000.0, 3.5, 1999.9),
+            (0.00002378, 1000000.0, 0.1 + 0.2),
+            (87654321.12345678, 0.00076, -0.0),
+        ] {
+            let order = ClientOrderRequest {
+                asset: "ETH".to_string(),
+                is_buy: true,
+                reduce_only: false,
+                limit_px: px.try_into()?,
+                sz: sz.try_into()?,
+                cloid: None,
+                order_type: ClientOrder::Trigger(ClientTrigger {
+                    is_market: true,
+                    trigger_px: trigger_px.try_into()?,
+                    tpsl: TpSl::Tp,
+                }),
+            }
+            .convert(&coin_to_asset, &HashMap::new(), RoundingPolicy::Strict)?;
+
+            let expected = OrderRequest {
+                asset: 1,
+                is_buy: true,
+                limit_px: float_to_string_for_hashing(px),
+                sz: float_to_string_for_hashing(sz),
+                reduce_only: false,
+                order_type: Order::Trigger(Trigger {
+                    trigger_px: float_to_string_for_hashing(trigger_px),
+                    is_market: true,
+                    tpsl: TpSl::Tp,
+                }),
+                cloid: None,
+            };
+
+            let hash = |order: OrderRequest| {
+                Actions::Order(BulkOrder {
+                    orders: vec![order],
+                    grouping: Grouping::Na,
+                    builder: None,
+                })
+                .hash(1583838, None, None)
+            };
+            assert_eq!(hash(order)?, hash(expected)?);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bracket_order_action_hashing() -> Result<()> {
+        let client = fixture_client()?;
+        let wallet = get_wallet()?;
+        for (bracket, mainnet_signature, testnet_signature) in [
+            (
+                BracketOrder::new(ClientOrderRequest {
+                    asset: "ETH".to_string(),
+                    is_buy: true,
+                    reduce_only: false,
+                    limit_px: 2000.0.try_into()?,
+                    sz: 3.5.try_into()?,
+                    cloid: None,
+                    order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Gtc }),
+                }),
+                "0xe060bbeacfd2f7c7f52c029ed9d02a22ed3873684eea9e4418a87ffa4f18e4e81f2f665ba090d35561e66b222a7743cb744dd7c6cee83f48542e5804deef7c2a1b",
+                "0x6e816d545bcdffc76420b4f7bb95d23ad4f62b1cdc7147d47d4a18b9fe30ced048392a3b1243f9e9abb074127a6bb55b894d8415c6f8bab04b69b9b522528f651c",
+            ),
+            (
+                BracketOrder::for_position("ETH", true, 3.5.try_into()?),
+                "0x875495a5af092812d2899d1423abe89fa4b1bfe0ca2fc7f5275cee616c35012e3478235eac2542a70c75e5ef8399fd54bffb3012faf5040a50e024e33a40c0861c",
+                "0xed598305c1ccba65a3c29ebed325f0f4c420c6d851ed1fa0e1f27add794e7cc81e164b0ba656871a4766bba0e543fed287f7e439b74e2f67e2b9728ed015d3b21c",
+            ),
+        ] {
+            let bracket = bracket
+                .take_profit(BracketLeg::limit(2200.0.try_into()?, 2190.0.try_into()?))
+                .stop_loss(BracketLeg::market(1900.0.try_into()?));
+            let grouping = bracket.grouping();
+            let orders = bracket
+                .into_orders()?
+                .into_iter()
+                .map(|order| {
+                    order.convert(
+                        &client.coin_to_asset,
+                        &client.asset_rules,
+                        client.rounding_policy,
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let action = Actions::Order(BulkOrder {
+                orders,
+                grouping,
+                builder: None,
+            });
+            let connection_id = action.hash(1583838, None, None)?;
+
+            let signature = sign_l1_action(&wallet, connection_id, true).await?;
+            assert_eq!(signature.to_string(), mainnet_signature);
+
+            let signature = sign_l1_action(&wallet, connection_id, false).await?;
+            assert_eq!(signature.to_string(), testnet_signature);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel_action_hashing() -> Result<()> {
         let wallet = get_wallet()?;
         let action = Actions::Cancel(BulkCancel {
             cancels: vec![CancelRequest {
@@ -1104,19 +1793,19 @@ mod tests {
                 oid: 82382,
             }],
         });
-        let connection_id = action.hash(1583838, None)?;
+        let connection_id = action.hash(1583838, None, None)?;
 
-        let signature = sign_l1_action(&wallet, connection_id, true)?;
+        let signature = sign_l1_action(&wallet, connection_id, true).await?;
         assert_eq!(signature.to_string(), "0x02f76cc5b16e0810152fa0e14e7b219f49c361e3325f771544c6f54e157bf9fa17ed0afc11a98596be85d5cd9f86600aad515337318f7ab346e5ccc1b03425d51b");
 
-        let signature = sign_l1_action(&wallet, connection_id, false)?;
+        let signature = sign_l1_action(&wallet, connection_id, false).await?;
         assert_eq!(signature.to_string(), "0x6ffebadfd48067663390962539fbde76cfa36f53be65abe2ab72c9db6d0db44457720db9d7c4860f142a484f070c84eb4b9694c3a617c83f0d698a27e55fd5e01c");
 
         Ok(())
     }
 
-    #[test]
-    fn test_approve_builder_fee_signing() -> Result<()> {
+    #[tokio::test]
+    async fn test_approve_builder_fee_signing() -> Result<()> {
         let wallet = get_wallet()?;
 
         // Test mainnet
@@ -1128,7 +1817,7 @@ mod tests {
             nonce: 1583838,
         };
 
-        let mainnet_signature = sign_typed_data(&mainnet_fee, &wallet)?;
+        let mainnet_signature = sign_typed_data(&mainnet_fee, &wallet).await?;
         assert_eq!(
             mainnet_signature.to_string(),
             "0x343c9078af7c3d6683abefd0ca3b2960de5b669b716863e6dc49090853a4a3cd6c016301239461091a8ca3ea5ac783362526c4d9e9e624ffc563aea93d6ac2391b"
@@ -1143,7 +1832,7 @@ mod tests {
             nonce: 1583838,
         };
 
-        let testnet_signature = sign_typed_data(&testnet_fee, &wallet)?;
+        let testnet_signature = sign_typed_data(&testnet_fee, &wallet).await?;
         assert_eq!(
             testnet_signature.to_string(),
             "0x2ada43eeebeba9cfe13faf95aa84e5b8c4885c3a07cbf4536f2df5edd340d4eb1ed0e24f60a80d199a842258d5fa737a18d486f7d4e656268b434d226f2811d71c"
@@ -1165,7 +1854,7 @@ mod tests {
             nonce: 1583838,
         });
 
-        let connection_id = action.hash(1583838, None)?;
+        let connection_id = action.hash(1583838, None, None)?;
         assert_eq!(
             connection_id.to_string(),
             "0xbe889a23135fce39a37315424cc4ae910edea7b42a075457b15bf4a9f0a8cfa4"
@@ -1174,21 +1863,21 @@ mod tests {
         Ok(())
     }
 
-    #[test]
-    fn test_claim_rewards_action_hashing() -> Result<()> {
+    #[tokio::test]
+    async fn test_claim_rewards_action_hashing() -> Result<()> {
         let wallet = get_wallet()?;
         let action = Actions::ClaimRewards(ClaimRewards {});
-        let connection_id = action.hash(1583838, None)?;
+        let connection_id = action.hash(1583838, None, None)?;
 
         // Test mainnet signature
-        let signature = sign_l1_action(&wallet, connection_id, true)?;
+        let signature = sign_l1_action(&wallet, connection_id, true).await?;
         assert_eq!(
             signature.to_string(),
             "0xe13542800ba5ec821153401e1cafac484d1f861adbbb86c00b580ec2560c153248b8d9f0e004ecc86959c07d44b591861ebab2167b54651a81367e2c3d472d4e1c"
         );
 
         // Test testnet signature
-        let signature = sign_l1_action(&wallet, connection_id, false)?;
+        let signature = sign_l1_action(&wallet, connection_id, false).await?;
         assert_eq!(
             signature.to_string(),
             "0x16de9b346ddd8e200492a2db45ec9104dcdfc7fbfdbcd85890a6063bdd56df2c44846714c261a431de7095ad52e07143346eb26d9e66c6aed4674f120a1048131c"
@@ -1197,8 +1886,241 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rate_limit_actions() -> Result<()> {
+        let action = Actions::ReserveRequestWeight(ReserveRequestWeight { weight: 1000 });
+        assert_eq!(
+            serde_json::to_string(&action).unwrap(),
+            r#"{"type":"reserveRequestWeight","weight":1000}"#
+        );
+        let noop = Actions::Noop(Noop {});
+        assert_eq!(serde_json::to_string(&noop).unwrap(), r#"{"type":"noop"}"#);
+
+        let wallet = get_wallet()?;
+        for (action, expected) in [
+            (action, "0x189d1cf585e440fb2d9fbc2760d702729df5346ccafad8fbc18d483697a4e70c156c0990f1e3fe96dd8be216ee39926648b37c33436f296be41a111845632b791c"),
+            (noop, "0xcb8188dfd28e5fc98e47a11546f17ef71150d023b341a48a88ee14dbb795cdb03da7d7d664449be1b349a9d2a66e6431769c3d04786adba826cd4f96972474c01b"),
+        ] {
+            let connection_id = action.hash(1583838, None, None)?;
+            let signature = sign_l1_action(&wallet, connection_id, true).await?;
+            assert_eq!(signature.to_string(), expected);
+        }
+        Ok(())
+    }
+
     #[test]
-    fn test_send_asset_signing() -> Result<()> {
+    fn test_twap_actions_msgpack() -> Result<()> {
+        let coin_to_asset = HashMap::from([("BTC".to_string(), 0)]);
+        let twap = ClientTwapRequest {
+            asset: "BTC".to_string(),
+            is_buy: true,
+            sz: "0.1".parse()?,
+            reduce_only: false,
+            minutes: 30,
+            randomize: false,
+        }
+        .convert(&coin_to_asset, &HashMap::new(), RoundingPolicy::Strict)?;
+
+        let action = Actions::TwapOrder(TwapOrder { twap });
+        assert_eq!(
+            alloy::hex::encode(rmp_serde::to_vec_named(&action).unwrap()),
+            "82a474797065a9747761704f72646572a47477617086a16100a162c3a173a3302e31a172c2a16d1ea174c2"
+        );
+
+        let action = Actions::TwapCancel(TwapCancel {
+            asset: 0,
+            twap_id: 5,
+        });
+        assert_eq!(
+            alloy::hex::encode(rmp_serde::to_vec_named(&action).unwrap()),
+            "83a474797065aa7477617043616e63656ca16100a17405"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sub_account_actions() -> Result<()> {
+        let sub_account = address!("0x1d9470d4b963f552e6f671a81619d395877bf409");
+        let sub_account_user = format!("{sub_account:?}");
+        let transfer = Actions::SubAccountTransfer(SubAccountUsdTransfer {
+            sub_account_user: sub_account_user.clone(),
+            is_deposit: true,
+            usd: 1_000_000,
+        });
+        assert_eq!(
+            serde_json::to_string(&transfer).unwrap(),
+            r#"{"type":"subAccountTransfer","subAccountUser":"0x1d9470d4b963f552e6f671a81619d395877bf409","isDeposit":true,"usd":1000000}"#
+        );
+
+        // The address is hashed as a string, like every other address in L1 actions
+        let wallet = get_wallet()?;
+        for (action, msgpack, signature) in [
+            (
+                Actions::SubAccountModify(SubAccountModify {
+                    sub_account_user: sub_account_user.clone(),
+                    name: "Test".to_string(),
+                }),
+                "83a474797065b07375624163636f756e744d6f64696679ae7375624163636f756e7455736572d92a307831643934373064346239363366353532653666363731613831363139643339353837376266343039a46e616d65a454657374",
+                "0x4fe383068bd188ba8ff0f12a090acfb853a9a4b90a5b53e1886c64158c2ea9995796308871209fb467e275e9f55b82c77a0ad79c3bcc70e1dff7c22361b78a961c",
+            ),
+            (
+                transfer,
+                "84a474797065b27375624163636f756e745472616e73666572ae7375624163636f756e7455736572d92a307831643934373064346239363366353532653666363731613831363139643339353837376266343039a969734465706f736974c3a3757364ce000f4240",
+                "0xd766ff2eb59b1ab6d33c474fa22830e302c5c116aadb9b7c3cdc08ea3e4f08ba0ba874106de56d86501e1eaefce781413880775c3a632940149788e688ba00201c",
+            ),
+            (
+                Actions::SubAccountSpotTransfer(SubAccountSpotTransfer {
+                    sub_account_user,
+                    is_deposit: false,
+                    token: "USDC:0xeb62eee3685fc4c43992febcd9e75443".to_string(),
+                    amount: "1.5".to_string(),
+                }),
+                "85a474797065b67375624163636f756e7453706f745472616e73666572ae7375624163636f756e7455736572d92a307831643934373064346239363366353532653666363731613831363139643339353837376266343039a969734465706f736974c2a5746f6b656ed927555344433a30786562363265656533363835666334633433393932666562636439653735343433a6616d6f756e74a3312e35",
+                "0xfdc262c1ed6e4617a19bd822f52745fc402c0f02626791c337b6f8337c2f6eb72c7dadc3afb2ac2112957c000ff534c4b817929fe5d8aa96cc931b76da61d4b41c",
+            ),
+        ] {
+            assert_eq!(
+                alloy::hex::encode(rmp_serde::to_vec_named(&action).unwrap()),
+                msgpack
+            );
+            let connection_id = action.hash(1583838, None, None)?;
+            let signature_got = sign_l1_action(&wallet, connection_id, true).await?;
+            assert_eq!(signature_got.to_string(), signature);
+        }
+
+        let response: CreateSubAccountResponseStatus = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "createSubAccount", "data": "0x1d9470d4b963f552e6f671a81619d395877bf409"}}"#,
+        )
+        .unwrap();
+        let CreateSubAccountResponseStatus::Ok(response) = response else {
+            panic!("expected ok response");
+        };
+        assert_eq!(response.data, Some(sub_account));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_as_sub_account() -> Result<()> {
+        let client = fixture_client()?;
+        let sub_account = address!("0x1d9470d4b963f552e6f671a81619d395877bf409");
+
+        let acting = client.as_sub_account(sub_account);
+        assert_eq!(acting.vault_address, Some(sub_account));
+        assert_eq!(client.vault_address, None);
+        assert!(Arc::ptr_eq(&acting.wallet, &client.wallet));
+        assert!(Arc::ptr_eq(&acting.meta, &client.meta));
+        assert!(Arc::ptr_eq(&acting.coin_to_asset, &client.coin_to_asset));
+        Ok(())
+    }
+
+    #[test]
+    fn test_request_options() -> Result<()> {
+        let client = fixture_client()?.as_sub_account(Address::repeat_byte(1));
+        let other_wallet = PrivateKeySigner::random();
+
+        let defaults = client.resolve_options(None);
+        assert_eq!(defaults.wallet.address(), client.wallet.address());
+        assert_eq!(defaults.vault_address, Some(Address::repeat_byte(1)));
+        assert_eq!(defaults.expires_after, None);
+
+        let options = client.resolve_options(Some(RequestOptions {
+            wallet: Some(&other_wallet),
+            vault_address: Some(Address::repeat_byte(2)),
+            expires_after: Some(1583848),
+            nonce: Some(1583838),
+        }));
+        assert_eq!(options.wallet.address(), other_wallet.address());
+        assert_eq!(options.vault_address, Some(Address::repeat_byte(2)));
+        assert_eq!(options.nonce, 1583838);
+
+        let action = Actions::ClaimRewards(ClaimRewards {});
+        assert_ne!(
+            action.hash(1583838, None, Some(1583848))?,
+            action.hash(1583838, None, None)?
+        );
+
+        let payload = ExchangePayload {
+            action: serde_json::to_value(&action).unwrap(),
+            signature: Signature::test_signature(),
+            nonce: options.nonce,
+            vault_address: options.vault_address,
+            expires_after: options.expires_after,
+        };
+        let payload = serde_json::to_value(&payload).unwrap();
+        assert_eq!(
+            payload["vaultAddress"],
+            format!("{:?}", Address::repeat_byte(2))
+        );
+        assert_eq!(payload["expiresAfter"], 1583848);
+        Ok(())
+    }
+
+    #[test]
+    fn test_expiry_window() -> Result<()> {
+        let mut client = fixture_client()?;
+        client.expiry_window = Some(Duration::from_secs(10));
+
+        let options = client.resolve_options(Some(RequestOptions {
+            nonce: Some(1583838),
+            ..Default::default()
+        }));
+        assert_eq!(options.expires_after, Some(1593838));
+
+        let options = client.resolve_options(Some(RequestOptions {
+            nonce: Some(1583838),
+            expires_after: Some(1583848),
+            ..Default::default()
+        }));
+        assert_eq!(options.expires_after, Some(1583848));
+
+        let options = client.resolve_user_signed_options(None);
+        assert_eq!(options.expires_after, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_twap_responses() {
+        let response: TwapOrderResponse = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "twapOrder", "data": {"status": {"running": {"twapId": 77738308}}}}}"#,
+        )
+        .unwrap();
+        let TwapResponseStatus::Ok(response) = response else {
+            panic!("expected ok response");
+        };
+        assert!(matches!(
+            response.data.unwrap().status,
+            TwapOrderStatus::Running(TwapRunning { twap_id: 77738308 })
+        ));
+
+        let response: TwapCancelResponse = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "twapCancel", "data": {"status": {"error": "TWAP was never placed, already canceled, or filled."}}}}"#,
+        )
+        .unwrap();
+        let TwapResponseStatus::Ok(response) = response else {
+            panic!("expected ok response");
+        };
+        assert!(matches!(
+            response.data.unwrap().status,
+            TwapCancelStatus::Error(_)
+        ));
+
+        let response: TwapCancelResponse = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "twapCancel", "data": {"status": "success"}}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            response,
+            TwapResponseStatus::Ok(TwapResponse {
+                data: Some(TwapData {
+                    status: TwapCancelStatus::Success
+                }),
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_send_asset_signing() -> Result<()> {
         let wallet = get_wallet()?;
 
         // Test mainnet - send asset to another address
@@ -1214,7 +2136,7 @@ mod tests {
             nonce: 1583838,
         };
 
-        let mainnet_signature = sign_typed_data(&mainnet_send, &wallet)?;
+        let mainnet_signature = sign_typed_data(&mainnet_send, &wallet).await?;
         // Signature generated successfully - just verify it's a valid signature object
 
         // Test testnet - send different token
@@ -1230,7 +2152,7 @@ mod tests {
             nonce: 1583838,
         };
 
-        let testnet_signature = sign_typed_data(&testnet_send, &wallet)?;
+        let testnet_signature = sign_typed_data(&testnet_send, &wallet).await?;
         // Verify signatures are different for mainnet vs testnet
         assert_ne!(mainnet_signature, testnet_signature);
 
@@ -1247,10 +2169,256 @@ mod tests {
             nonce: 1583838,
         };
 
-        let vault_signature = sign_typed_data(&vault_send, &wallet)?;
+        let vault_signature = sign_typed_data(&vault_send, &wallet).await?;
         // Verify vault signature is different from non-vault signature
         assert_ne!(mainnet_signature, vault_signature);
 
         Ok(())
     }
+
+    #[test]
+    fn test_book_slippage_limit_px() -> Result<()> {
+        let level = |px: &str, sz: &str| BookLevel {
+            px: px.parse().unwrap(),
+            sz: sz.parse().unwrap(),
+            n: 1,
+        };
+        let book = OrderBook::from_ws(&L2BookData {
+            coin: "ETH".to_string(),
+            time: 0,
+            levels: vec![
+                vec![level("1999", "1"), level("1998", "1")],
+                vec![level("2001", "1"), level("2003", "1")],
+            ],
+        });
+
+        let worst = BookSlippage {
+            price: BookPrice::Worst,
+            buffer: 0.01,
+        };
+        assert_eq!(worst.limit_px(&book, true, 1.5)?, 2003.0 * 1.01);
+        assert_eq!(worst.limit_px(&book, false, 1.0)?, 1999.0 * 0.99);
+
+        let vwap = BookSlippage {
+            price: BookPrice::Vwap,
+            buffer: 0.0,
+        };
+        assert_eq!(vwap.limit_px(&book, true, 2.0)?, 2002.0);
+        assert!(matches!(
+            vwap.limit_px(&book, false, 3.0),
+            Err(Error::InsufficientBookDepth { available, .. }) if available == 2.0
+        ));
+        Ok(())
+    }
+
+    fn fixture_client() -> Result<ExchangeClient> {
+        let meta: Meta = serde_json::from_str(
+            r#"{"universe": [
+                {"name": "BTC", "szDecimals": 5, "maxLeverage": 50},
+                {"name": "ETH", "szDecimals": 4, "maxLeverage": 50}
+            ]}"#,
+        )
+        .map_err(Error::json_parse)?;
+        let spot_meta: SpotMeta = serde_json::from_str(
+            r#"{
+                "universe": [
+                    {"tokens": [1, 0], "name": "PURR/USDC", "index": 0, "isCanonical": true},
+                    {"tokens": [2, 0], "name": "@1", "index": 1, "isCanonical": false}
+                ],
+                "tokens": [
+                    {"name": "USDC", "szDecimals": 8, "weiDecimals": 8, "index": 0, "tokenId": "0x6d1e7cde53ba9467b783cb7c530ce054", "isCanonical": true},
+                    {"name": "PURR", "szDecimals": 0, "weiDecimals": 5, "index": 1, "tokenId": "0xc1fb593aeffbeb02f85e0308e9956a90", "isCanonical": true},
+                    {"name": "HFUN", "szDecimals": 2, "weiDecimals": 8, "index": 2, "tokenId": "0xbaf265ef389da684513d98d68edf4eae", "isCanonical": false}
+                ]
+            }"#,
+        )
+        .map_err(Error::json_parse)?;
+
+        Ok(ExchangeClient::from_meta(
+            HttpClient::new(Client::new(), BaseUrl::Localhost.get_url()),
+            Arc::new(get_wallet()?),
+            meta,
+            &spot_meta,
+            None,
+        ))
+    }
+
+    #[test]
+    fn test_market_coin() -> Result<()> {
+        let client = fixture_client()?;
+        assert_eq!(client.market_coin("ETH")?, "ETH");
+        assert_eq!(client.market_coin("PURR/USDC")?, "PURR/USDC");
+        assert_eq!(client.market_coin("HFUN/USDC")?, "@1");
+        assert_eq!(client.market_coin("@1")?, "@1");
+        assert!(client.market_coin("FOO/USDC").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_slippage_price_perp_and_spot() -> Result<()> {
+        let client = fixture_client()?;
+
+        // Perps: 6 - 4 = 2 price decimals and 5 significant figures
+        assert_eq!(
+            client
+                .calculate_slippage_price("ETH", true, 1.0, 0.05, Some(1834.52), None)
+                .await?,
+            (1926.2, 4)
+        );
+        // Spot: 8 - sz_decimals of the base token price decimals
+        assert_eq!(
+            client
+                .calculate_slippage_price("PURR/USDC", false, 10.0, 0.05, Some(0.1834523), None)
+                .await?,
+            (0.17428, 0)
+        );
+        assert_eq!(
+            client
+                .calculate_slippage_price("HFUN/USDC", true, 1.0, 0.01, Some(0.00002378), None)
+                .await?,
+            (0.000024, 2)
+        );
+        assert!(matches!(
+            client
+                .calculate_slippage_price("FOO/USDC", true, 1.0, 0.01, Some(1.0), None)
+                .await,
+            Err(Error::AssetNotFound)
+        ));
+        Ok(())
+    }
+
+    /// Answers HTTP requests in order with `(status code, body)` responses, dropping the
+    /// connection without an answer for `None`. Returns the base url and a channel of the
+    /// received `(path, body)` pairs.
+    async fn spawn_scripted_server(
+        responses: Vec<Option<(u16, &'static str)>>,
+    ) -> (String, UnboundedReceiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = unbounded_channel();
+
+        tokio::spawn(async move {
+            for response in responses {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut reader = BufReader::new(stream);
+                let request = read_request(&mut reader).await.unwrap().unwrap();
+                sender.send(request).unwrap();
+
+                if let Some((status_code, body)) = response {
+                    let status = format!("{status_code} Scripted");
+                    write_response(reader.get_mut(), &status, body, false)
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+
+        (url, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_retry_resolves_lost_order() -> Result<()> {
+        let (url, mut requests) = spawn_scripted_server(vec![
+            Some((502, "")),
+            Some((
+                200,
+                r#"{"status": "err", "response": "Invalid nonce: duplicate nonce"}"#,
+            )),
+            Some((200, r#"{"role": "user"}"#)),
+            Some((
+                200,
+                r#"{"status": "order", "order": {"order": {
+                    "coin": "ETH", "side": "B", "limitPx": "1800.0", "sz": "0.01", "oid": 42,
+                    "timestamp": 1, "triggerCondition": "N/A", "isTrigger": false,
+                    "triggerPx": "0.0", "isPositionTpsl": false, "reduceOnly": false,
+                    "orderType": "Limit", "origSz": "0.01", "tif": "Gtc",
+                    "cloid": "0x1e60610f0b3d420597c88c1fed2ad5ee"
+                }, "status": "open", "statusTimestamp": 1}}"#,
+            )),
+        ])
+        .await;
+        let mut client = fixture_client()?;
+        client.http_client.base_url = url;
+        client.retry_policy = Some(RetryPolicy {
+            initial_backoff: Duration::ZERO,
+            ..RetryPolicy::default()
+        });
+
+        let cloid = Uuid::from_str("1e60610f-0b3d-4205-97c8-8c1fed2ad5ee").unwrap();
+        let order = ClientOrderRequest {
+            asset: "ETH".to_string(),
+            is_buy: true,
+            reduce_only: false,
+            limit_px: 1800.0.try_into()?,
+            sz: 0.01.try_into()?,
+            cloid: Some(cloid),
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Gtc }),
+        };
+        let response = client.order(order, None).await?;
+
+        let (first_path, first) = requests.recv().await.unwrap();
+        let (_, second) = requests.recv().await.unwrap();
+        let (_, role) = requests.recv().await.unwrap();
+        let (info_path, info) = requests.recv().await.unwrap();
+        assert_eq!(first_path, "/exchange");
+        assert_eq!(first, second);
+        assert!(role.contains(r#""type":"userRole""#));
+        assert_eq!(info_path, "/info");
+        assert!(info.contains(r#""oid":"0x1e60610f0b3d420597c88c1fed2ad5ee""#));
+
+        let ExchangeResponseStatus::Ok(response) = response else {
+            panic!("expected ok response");
+        };
+        let statuses = response.data.unwrap().statuses;
+        let [ExchangeDataStatus::Resolved(info)] = statuses.as_slice() else {
+            panic!("expected resolved status, got {statuses:?}");
+        };
+        assert_eq!(info.order.oid, 42);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry_resolves_lost_order_of_agent() -> Result<()> {
+        let (url, mut requests) = spawn_scripted_server(vec![
+            None,
+            Some((
+                200,
+                r#"{"role": "agent", "data": {"user": "0x1d9470d4b963f552e6f671a81619d395877bf409"}}"#,
+            )),
+            Some((200, r#"{"status": "unknownOid"}"#)),
+        ])
+        .await;
+        let mut client = fixture_client()?;
+        client.http_client.base_url = url;
+        client.retry_policy = Some(RetryPolicy {
+            initial_backoff: Duration::ZERO,
+            ..RetryPolicy::default()
+        });
+
+        let order = ClientOrderRequest {
+            asset: "ETH".to_string(),
+            is_buy: true,
+            reduce_only: false,
+            limit_px: 1800.0.try_into()?,
+            sz: 0.01.try_into()?,
+            cloid: Some(Uuid::from_str("1e60610f-0b3d-4205-97c8-8c1fed2ad5ee").unwrap()),
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Gtc }),
+        };
+        // Not found either, so the original error is returned
+        assert!(client.order(order, None).await.is_err());
+
+        // A connection dropped after sending is looked up rather than re-sent
+        let (exchange_path, _) = requests.recv().await.unwrap();
+        let (role_path, role) = requests.recv().await.unwrap();
+        let (_, info) = requests.recv().await.unwrap();
+        assert_eq!(
+            (exchange_path.as_str(), role_path.as_str()),
+            ("/exchange", "/info")
+        );
+        let agent = format!("{:?}", client.wallet.address());
+        assert!(role.contains(&format!(r#""user":"{agent}""#)));
+        // Orders placed by an agent belong to the account it trades for
+        assert!(info.contains(r#""user":"0x1d9470d4b963f552e6f671a81619d395877bf409""#));
+        Ok(())
+    }
 }
@@ -1,5 +1,8 @@
+use alloy::primitives::Address;
 use serde::Deserialize;
 
+use crate::{OrderInfo, Px, Sz};
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct RestingOrder {
     pub oid: u64,
@@ -8,8 +11,8 @@ pub struct RestingOrder {
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct FilledOrder {
-    pub total_sz: String,
-    pub avg_px: String,
+    pub total_sz: Sz,
+    pub avg_px: Px,
     pub oid: u64,
 }
 
@@ -22,6 +25,10 @@ pub enum ExchangeDataStatus {
     Error(String),
     Resting(RestingOrder),
     Filled(FilledOrder),
+    /// Never sent by the exchange. Reported when the response to an order was lost and its
+    /// status had to be looked up by cloid instead, see [`crate::RetryPolicy`].
+    #[serde(skip)]
+    Resolved(Box<OrderInfo>),
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -43,3 +50,63 @@ pub enum ExchangeResponseStatus {
     Ok(ExchangeResponse),
     Err(String),
 }
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TwapRunning {
+    pub twap_id: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum TwapOrderStatus {
+    Running(TwapRunning),
+    Error(String),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum TwapCancelStatus {
+    Success,
+    Error(String),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TwapData<S> {
+    pub status: S,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TwapResponse<S> {
+    #[serde(rename = "type")]
+    pub response_type: String,
+    pub data: Option<TwapData<S>>,
+}
+
+/// Response to `twapOrder` and `twapCancel`, which report a single status instead of a list.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "status", content = "response")]
+pub enum TwapResponseStatus<S> {
+    Ok(TwapResponse<S>),
+    Err(String),
+}
+
+pub type TwapOrderResponse = TwapResponseStatus<TwapOrderStatus>;
+pub type TwapCancelResponse = TwapResponseStatus<TwapCancelStatus>;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CreateSubAccountResponse {
+    #[serde(rename = "type")]
+    pub response_type: String,
+    /// Address of the new sub-account.
+    pub data: Option<Address>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "status", content = "response")]
+pub enum CreateSubAccountResponseStatus {
+    Ok(CreateSubAccountResponse),
+    Err(String),
+}
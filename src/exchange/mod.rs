@@ -1,18 +1,28 @@
 mod actions;
+mod bracket;
 mod builder;
 mod cancel;
 mod exchange_client;
 mod exchange_responses;
 mod modify;
+mod options;
 mod order;
+mod reject_reason;
+mod twap;
 
 pub use actions::*;
+pub use bracket::{BracketLayout, BracketLeg, BracketOrder, BracketOrderResponse, BracketStatuses};
 pub use builder::*;
 pub use cancel::{ClientCancelRequest, ClientCancelRequestCloid};
 pub use exchange_client::*;
 pub use exchange_responses::*;
-pub use modify::{ClientModifyRequest, ModifyRequest};
+pub use modify::{ClientModifyRequest, ModifyRequest, OidOrCloid};
+pub use options::RequestOptions;
 pub use order::{
-    ClientLimit, ClientOrder, ClientOrderRequest, ClientTrigger, MarketCloseParams,
-    MarketOrderParams, Order,
+    BookPrice, BookSlippage, ClientLimit, ClientOrder, ClientOrderRequest, ClientTrigger, Grouping,
+    MarketCloseParams, MarketOrderParams, Order, OrderStatus, Side, Tif, TpSl,
 };
+#[cfg(feature = "mock")]
+pub(crate) use order::{Limit, OrderRequest};
+pub use reject_reason::OrderRejectReason;
+pub use twap::{ClientTwapRequest, TwapRequest};
@@ -1,15 +1,80 @@
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use super::{order::OrderRequest, ClientOrderRequest};
 
+/// Identifies an order either by its exchange-assigned oid or by the cloid it was placed with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum OidOrCloid {
+    Oid(u64),
+    Cloid(#[serde(with = "cloid_hex")] Uuid),
+}
+
+impl From<u64> for OidOrCloid {
+    fn from(oid: u64) -> Self {
+        OidOrCloid::Oid(oid)
+    }
+}
+
+impl From<Uuid> for OidOrCloid {
+    fn from(cloid: Uuid) -> Self {
+        OidOrCloid::Cloid(cloid)
+    }
+}
+
+/// Cloids go over the wire as `0x`-prefixed 32 character hex strings.
+mod cloid_hex {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+    use uuid::Uuid;
+
+    use crate::helpers::uuid_to_hex_string;
+
+    pub(super) fn serialize<S: Serializer>(cloid: &Uuid, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&uuid_to_hex_string(*cloid))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Uuid, D::Error> {
+        let cloid = String::deserialize(d)?;
+        let hex = cloid.strip_prefix("0x").unwrap_or(&cloid);
+        Uuid::try_parse(hex).map_err(D::Error::custom)
+    }
+}
+
 #[derive(Debug)]
 pub struct ClientModifyRequest {
-    pub oid: u64,
+    pub oid: OidOrCloid,
     pub order: ClientOrderRequest,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ModifyRequest {
-    pub oid: u64,
+    pub oid: OidOrCloid,
     pub order: OrderRequest,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_oid_or_cloid_serde() {
+        let cloid = Uuid::from_str("1e60610f-0b3d-4205-97c8-8c1fed2ad5ee").unwrap();
+
+        let oid = serde_json::to_value(OidOrCloid::Oid(82)).unwrap();
+        assert_eq!(oid, serde_json::json!(82));
+        let hex = serde_json::to_value(OidOrCloid::Cloid(cloid)).unwrap();
+        assert_eq!(hex, serde_json::json!("0x1e60610f0b3d420597c88c1fed2ad5ee"));
+
+        assert_eq!(
+            serde_json::from_value::<OidOrCloid>(oid).unwrap(),
+            OidOrCloid::Oid(82)
+        );
+        assert_eq!(
+            serde_json::from_value::<OidOrCloid>(hex).unwrap(),
+            OidOrCloid::Cloid(cloid)
+        );
+    }
+}
@@ -0,0 +1,18 @@
+use alloy::primitives::Address;
+
+use crate::signature::HyperliquidSigner;
+
+/// Per-call overrides for exchange actions. Fields left as `None` fall back to the client's
+/// settings, so `None` can be passed wherever options are accepted.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestOptions<'a> {
+    /// Signer to use instead of the client's wallet.
+    pub wallet: Option<&'a dyn HyperliquidSigner>,
+    /// Vault or sub-account to act for instead of the client's `vault_address`.
+    pub vault_address: Option<Address>,
+    /// Timestamp in milliseconds after which the exchange rejects the action, overriding the
+    /// client's `expiry_window`. Ignored by user-signed actions such as transfers and withdrawals.
+    pub expires_after: Option<u64>,
+    /// Nonce to sign with instead of the next one from the shared nonce counter.
+    pub nonce: Option<u64>,
+}
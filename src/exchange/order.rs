@@ -1,18 +1,151 @@
 use std::collections::HashMap;
 
-use alloy::signers::local::PrivateKeySigner;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    errors::Error,
-    helpers::{float_to_string_for_hashing, uuid_to_hex_string},
-    prelude::*,
+    errors::Error, exchange::RequestOptions, helpers::uuid_to_hex_string, prelude::*, AssetRules,
+    OrderBook, Px, RoundingPolicy, Sz,
 };
 
+/// Time in force of a limit order.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tif {
+    /// Add liquidity only (post only).
+    Alo,
+    /// Immediate or cancel.
+    Ioc,
+    /// Good til canceled.
+    Gtc,
+    /// Ioc sent by the frontend for market orders.
+    FrontendMarket,
+    /// Only reported for liquidation orders.
+    LiquidationMarket,
+}
+
+/// Whether a trigger order takes profit or stops a loss.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum TpSl {
+    Tp,
+    Sl,
+}
+
+/// How the orders of a bulk order relate to each other.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Grouping {
+    /// Independent orders.
+    #[default]
+    Na,
+    /// Tp/sl orders sized to the parent order, placed once it fills.
+    NormalTpsl,
+    /// Tp/sl orders attached to the whole position, resized as it changes.
+    PositionTpsl,
+}
+
+/// Side of an order or fill, `B` (bid) or `A` (ask) on the wire.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    #[serde(rename = "B")]
+    Buy,
+    #[serde(rename = "A")]
+    Sell,
+}
+
+impl Side {
+    pub fn from_is_buy(is_buy: bool) -> Side {
+        if is_buy {
+            Side::Buy
+        } else {
+            Side::Sell
+        }
+    }
+
+    pub fn is_buy(self) -> bool {
+        self == Side::Buy
+    }
+}
+
+/// Order status as reported by `orderUpdates` and `orderStatus`. Statuses added by the exchange
+/// after this list was written deserialize as `Unknown`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderStatus {
+    Open,
+    Filled,
+    Canceled,
+    Triggered,
+    Rejected,
+    MarginCanceled,
+    VaultWithdrawalCanceled,
+    OpenInterestCapCanceled,
+    SelfTradeCanceled,
+    ReduceOnlyCanceled,
+    SiblingFilledCanceled,
+    DelistedCanceled,
+    LiquidatedCanceled,
+    ScheduledCancel,
+    TickRejected,
+    MinTradeNtlRejected,
+    PerpMarginRejected,
+    ReduceOnlyRejected,
+    BadAloPxRejected,
+    IocCancelRejected,
+    BadTriggerPxRejected,
+    MarketOrderNoLiquidityRejected,
+    PositionIncreaseAtOpenInterestCapRejected,
+    PositionFlipAtOpenInterestCapRejected,
+    TooAggressiveAtOpenInterestCapRejected,
+    OpenInterestIncreaseRejected,
+    InsufficientSpotBalanceRejected,
+    OracleRejected,
+    PerpMaxPositionRejected,
+    #[serde(other)]
+    Unknown,
+}
+
+impl OrderStatus {
+    /// Resting on the book, including trigger orders that have triggered.
+    pub fn is_open(self) -> bool {
+        matches!(self, OrderStatus::Open | OrderStatus::Triggered)
+    }
+
+    pub fn is_rejected(self) -> bool {
+        use OrderStatus::*;
+        matches!(
+            self,
+            Rejected
+                | TickRejected
+                | MinTradeNtlRejected
+                | PerpMarginRejected
+                | ReduceOnlyRejected
+                | BadAloPxRejected
+                | IocCancelRejected
+                | BadTriggerPxRejected
+                | MarketOrderNoLiquidityRejected
+                | PositionIncreaseAtOpenInterestCapRejected
+                | PositionFlipAtOpenInterestCapRejected
+                | TooAggressiveAtOpenInterestCapRejected
+                | OpenInterestIncreaseRejected
+                | InsufficientSpotBalanceRejected
+                | OracleRejected
+                | PerpMaxPositionRejected
+        )
+    }
+
+    /// Removed from the book without filling completely, by the user or the exchange. Unknown
+    /// statuses are not counted since their outcome can't be told.
+    pub fn is_canceled(self) -> bool {
+        !self.is_open()
+            && !self.is_rejected()
+            && !matches!(self, OrderStatus::Filled | OrderStatus::Unknown)
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Limit {
-    pub tif: String,
+    pub tif: Tif,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -20,7 +153,7 @@ pub struct Limit {
 pub struct Trigger {
     pub is_market: bool,
     pub trigger_px: String,
-    pub tpsl: String,
+    pub tpsl: TpSl,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -51,14 +184,52 @@ pub struct OrderRequest {
 
 #[derive(Debug)]
 pub struct ClientLimit {
-    pub tif: String,
+    pub tif: Tif,
 }
 
 #[derive(Debug)]
 pub struct ClientTrigger {
     pub is_market: bool,
-    pub trigger_px: f64,
-    pub tpsl: String,
+    pub trigger_px: Px,
+    pub tpsl: TpSl,
+}
+
+/// Reference price taken from the L2 book when pricing a market order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookPrice {
+    /// Size-weighted average price needed to fill the order.
+    Vwap,
+    /// Price of the deepest level the order reaches.
+    Worst,
+}
+
+/// Derives the limit price of a market order from the current L2 snapshot instead of a flat
+/// slippage on the mid. `buffer` is a fraction (0.001 = 10 bps) added on top of the reference
+/// price. Orders are rejected with [`Error::InsufficientBookDepth`] when the book cannot fill them.
+#[derive(Debug, Clone, Copy)]
+pub struct BookSlippage {
+    pub price: BookPrice,
+    pub buffer: f64,
+}
+
+impl BookSlippage {
+    pub fn limit_px(&self, book: &OrderBook, is_buy: bool, sz: f64) -> Result<f64> {
+        let px = match self.price {
+            BookPrice::Vwap => book.vwap(is_buy, sz),
+            BookPrice::Worst => book.worst_price(is_buy, sz),
+        }
+        .ok_or_else(|| Error::InsufficientBookDepth {
+            coin: book.coin.clone(),
+            sz,
+            available: book.taker_levels(is_buy).iter().map(|level| level.sz).sum(),
+        })?;
+
+        Ok(if is_buy {
+            px * (1.0 + self.buffer)
+        } else {
+            px * (1.0 - self.buffer)
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -68,8 +239,10 @@ pub struct MarketOrderParams<'a> {
     pub sz: f64,
     pub px: Option<f64>,
     pub slippage: Option<f64>,
+    /// When set, takes precedence over `px` and `slippage`.
+    pub book_slippage: Option<BookSlippage>,
     pub cloid: Option<Uuid>,
-    pub wallet: Option<&'a PrivateKeySigner>,
+    pub options: Option<RequestOptions<'a>>,
 }
 
 #[derive(Debug)]
@@ -78,8 +251,10 @@ pub struct MarketCloseParams<'a> {
     pub sz: Option<f64>,
     pub px: Option<f64>,
     pub slippage: Option<f64>,
+    /// When set, takes precedence over `px` and `slippage`.
+    pub book_slippage: Option<BookSlippage>,
     pub cloid: Option<Uuid>,
-    pub wallet: Option<&'a PrivateKeySigner>,
+    pub options: Option<RequestOptions<'a>>,
 }
 
 #[derive(Debug)]
@@ -93,18 +268,31 @@ pub struct ClientOrderRequest {
     pub asset: String,
     pub is_buy: bool,
     pub reduce_only: bool,
-    pub limit_px: f64,
-    pub sz: f64,
+    pub limit_px: Px,
+    pub sz: Sz,
     pub cloid: Option<Uuid>,
     pub order_type: ClientOrder,
 }
 
 impl ClientOrderRequest {
-    pub(crate) fn convert(self, coin_to_asset: &HashMap<String, u32>) -> Result<OrderRequest> {
+    /// Converts to the wire format, checking or rounding prices and sizes against the asset's
+    /// entry in `asset_rules` according to `policy`. Assets without rules are sent unchanged.
+    pub fn convert(
+        self,
+        coin_to_asset: &HashMap<String, u32>,
+        asset_rules: &HashMap<String, AssetRules>,
+        policy: RoundingPolicy,
+    ) -> Result<OrderRequest> {
+        let rules = asset_rules.get(&self.asset);
+        let px = |px: Px| match rules {
+            Some(rules) => rules.apply_px(&self.asset, px, policy),
+            None => Ok(px),
+        };
+
         let order_type = match self.order_type {
             ClientOrder::Limit(limit) => Order::Limit(Limit { tif: limit.tif }),
             ClientOrder::Trigger(trigger) => Order::Trigger(Trigger {
-                trigger_px: float_to_string_for_hashing(trigger.trigger_px),
+                trigger_px: px(trigger.trigger_px)?.to_string(),
                 is_market: trigger.is_market,
                 tpsl: trigger.tpsl,
             }),
@@ -117,10 +305,48 @@ impl ClientOrderRequest {
             asset,
             is_buy: self.is_buy,
             reduce_only: self.reduce_only,
-            limit_px: float_to_string_for_hashing(self.limit_px),
-            sz: float_to_string_for_hashing(self.sz),
+            limit_px: px(self.limit_px)?.to_string(),
+            sz: match rules {
+                Some(rules) => rules.apply_sz(&self.asset, self.sz, policy)?,
+                None => self.sz,
+            }
+            .to_string(),
             order_type,
             cloid,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(value: impl Serialize) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_wire_strings() {
+        assert_eq!(
+            json(Order::Limit(Limit { tif: Tif::Alo }))["limit"]["tif"],
+            "Alo"
+        );
+        assert_eq!(json(Tif::FrontendMarket), "FrontendMarket");
+        assert_eq!(json(TpSl::Sl), "sl");
+        assert_eq!(json(Grouping::Na), "na");
+        assert_eq!(json(Grouping::PositionTpsl), "positionTpsl");
+        assert_eq!(json(Side::Sell), "A");
+
+        let status = |s: &str| serde_json::from_value::<OrderStatus>(s.into()).unwrap();
+        assert_eq!(status("open"), OrderStatus::Open);
+        assert_eq!(
+            status("reduceOnlyCanceled"),
+            OrderStatus::ReduceOnlyCanceled
+        );
+        assert!(status("badAloPxRejected").is_rejected());
+        assert!(status("siblingFilledCanceled").is_canceled());
+        assert!(status("triggered").is_open());
+        assert_eq!(status("someNewStatus"), OrderStatus::Unknown);
+        assert!(!OrderStatus::Unknown.is_canceled());
+    }
+}
@@ -0,0 +1,227 @@
+use thiserror::Error;
+
+use crate::{ExchangeDataStatus, ExchangeResponseStatus};
+
+/// Why the exchange rejected an order or action, classified from its error message. Messages
+/// the SDK doesn't know end up in [`OrderRejectReason::Other`] unchanged.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum OrderRejectReason {
+    #[error("Price must be divisible by tick size")]
+    TickSize,
+    #[error("Order is below the minimum notional")]
+    MinNotional,
+    #[error("Insufficient margin")]
+    InsufficientMargin,
+    #[error("Insufficient spot balance")]
+    InsufficientSpotBalance,
+    #[error("Reduce only order would increase position")]
+    ReduceOnly,
+    #[error("Post only order would have immediately matched")]
+    PostOnlyWouldCross,
+    #[error("Ioc order could not immediately match")]
+    IocCouldNotMatch,
+    #[error("No liquidity available for market order")]
+    NoLiquidity,
+    #[error("Invalid trigger price")]
+    InvalidTriggerPx,
+    #[error("Price too far from the reference price")]
+    PriceOutOfRange,
+    #[error("Open interest cap reached")]
+    OpenInterestCap,
+    #[error("Maximum position size exceeded")]
+    MaxPosition,
+    #[error("Rate limited")]
+    RateLimited,
+    #[error("Order was never placed, already canceled, or filled")]
+    UnknownOrder,
+    /// The nonce was already used or is outside the accepted time window. The same signed
+    /// request can't succeed, it has to be signed again with a fresh nonce.
+    #[error("Invalid nonce")]
+    InvalidNonce,
+    #[error("Unknown user or API wallet")]
+    UnknownSigner,
+    #[error("{0}")]
+    Other(String),
+}
+
+impl OrderRejectReason {
+    pub fn from_message(message: &str) -> OrderRejectReason {
+        let lower = message.to_lowercase();
+        let has = |pattern: &str| lower.contains(pattern);
+
+        if has("tick size") {
+            OrderRejectReason::TickSize
+        } else if has("minimum value") {
+            OrderRejectReason::MinNotional
+        } else if has("insufficient margin") {
+            OrderRejectReason::InsufficientMargin
+        } else if has("insufficient spot balance") {
+            OrderRejectReason::InsufficientSpotBalance
+        } else if has("reduce only") {
+            OrderRejectReason::ReduceOnly
+        } else if has("post only") {
+            OrderRejectReason::PostOnlyWouldCross
+        } else if has("could not immediately match") {
+            OrderRejectReason::IocCouldNotMatch
+        } else if has("no liquidity") {
+            OrderRejectReason::NoLiquidity
+        } else if has("tp/sl price") || has("trigger price") {
+            OrderRejectReason::InvalidTriggerPx
+        } else if has("away from the reference price") || has("away from the oracle") {
+            OrderRejectReason::PriceOutOfRange
+        } else if has("open interest") {
+            OrderRejectReason::OpenInterestCap
+        } else if has("maximum position") {
+            OrderRejectReason::MaxPosition
+        } else if has("too many") || has("rate limit") {
+            OrderRejectReason::RateLimited
+        } else if has("never placed") || has("canceled or filled") {
+            OrderRejectReason::UnknownOrder
+        } else if has("invalid nonce") {
+            OrderRejectReason::InvalidNonce
+        } else if has("does not exist") {
+            OrderRejectReason::UnknownSigner
+        } else {
+            OrderRejectReason::Other(message.to_string())
+        }
+    }
+
+    /// Whether sending the same request again later may succeed. Everything else needs a change
+    /// to the order, the account or the client before retrying.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            OrderRejectReason::RateLimited
+                | OrderRejectReason::NoLiquidity
+                | OrderRejectReason::OpenInterestCap
+        )
+    }
+}
+
+impl From<&str> for OrderRejectReason {
+    fn from(message: &str) -> Self {
+        OrderRejectReason::from_message(message)
+    }
+}
+
+impl ExchangeDataStatus {
+    /// Classified error of a rejected order, `None` if the order was accepted.
+    pub fn reject_reason(&self) -> Option<OrderRejectReason> {
+        match self {
+            ExchangeDataStatus::Error(e) => Some(OrderRejectReason::from_message(e)),
+            _ => None,
+        }
+    }
+}
+
+impl ExchangeResponseStatus {
+    /// Classified error of a rejected request. Rejections of individual orders in an accepted
+    /// request are reported per status, see [`ExchangeDataStatus::reject_reason`].
+    pub fn reject_reason(&self) -> Option<OrderRejectReason> {
+        match self {
+            ExchangeResponseStatus::Err(e) => Some(OrderRejectReason::from_message(e)),
+            ExchangeResponseStatus::Ok(_) => None,
+        }
+    }
+
+    /// Reject reasons of every status in the response, in order. Accepted orders are `None`.
+    pub fn reject_reasons(&self) -> Vec<Option<OrderRejectReason>> {
+        match self {
+            ExchangeResponseStatus::Ok(response) => response
+                .data
+                .iter()
+                .flat_map(|data| &data.statuses)
+                .map(ExchangeDataStatus::reject_reason)
+                .collect(),
+            ExchangeResponseStatus::Err(_) => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn test_from_message() {
+        let cases = [
+            (
+                "Price must be divisible by tick size. asset=4",
+                OrderRejectReason::TickSize,
+            ),
+            (
+                "Order must have minimum value of $10. asset=0",
+                OrderRejectReason::MinNotional,
+            ),
+            (
+                "Insufficient margin to place order. asset=0",
+                OrderRejectReason::InsufficientMargin,
+            ),
+            (
+                "Reduce only order would increase position. asset=0",
+                OrderRejectReason::ReduceOnly,
+            ),
+            (
+                "Post only order would have immediately matched, bbo was 1800.1@1800.2. asset=4",
+                OrderRejectReason::PostOnlyWouldCross,
+            ),
+            (
+                "Too many cumulative requests sent (10240) for cumulative volume traded ($10000).",
+                OrderRejectReason::RateLimited,
+            ),
+            (
+                "Order was never placed, already canceled, or filled. asset=4",
+                OrderRejectReason::UnknownOrder,
+            ),
+            (
+                "User or API Wallet 0x0000000000000000000000000000000000000001 does not exist.",
+                OrderRejectReason::UnknownSigner,
+            ),
+            (
+                "Invalid nonce: duplicate nonce",
+                OrderRejectReason::InvalidNonce,
+            ),
+        ];
+        for (message, reason) in cases {
+            assert_eq!(OrderRejectReason::from_message(message), reason);
+        }
+
+        let other = OrderRejectReason::from("Something new");
+        assert_eq!(other, OrderRejectReason::Other("Something new".to_string()));
+        assert_eq!(other.to_string(), "Something new");
+        assert!(!other.is_retryable());
+        assert!(OrderRejectReason::RateLimited.is_retryable());
+        assert!(!OrderRejectReason::InvalidNonce.is_retryable());
+        assert!(matches!(
+            OrderRejectReason::from("Nonce field must be an integer"),
+            OrderRejectReason::Other(_)
+        ));
+        assert!(matches!(
+            Error::from(OrderRejectReason::ReduceOnly),
+            Error::OrderRejected(OrderRejectReason::ReduceOnly)
+        ));
+    }
+
+    #[test]
+    fn test_response_reject_reasons() {
+        let response: ExchangeResponseStatus = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "order", "data": {"statuses": [
+                {"resting": {"oid": 1}},
+                {"error": "Insufficient margin to place order. asset=0"}
+            ]}}}"#,
+        )
+        .unwrap();
+        assert!(response.reject_reason().is_none());
+        assert_eq!(
+            response.reject_reasons(),
+            vec![None, Some(OrderRejectReason::InsufficientMargin)]
+        );
+
+        let response = ExchangeResponseStatus::Err("Too many requests".to_string());
+        assert_eq!(
+            response.reject_reason(),
+            Some(OrderRejectReason::RateLimited)
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::Error, prelude::*, AssetRules, RoundingPolicy, Sz};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TwapRequest {
+    #[serde(rename = "a", alias = "asset")]
+    pub asset: u32,
+    #[serde(rename = "b", alias = "isBuy")]
+    pub is_buy: bool,
+    #[serde(rename = "s", alias = "sz")]
+    pub sz: String,
+    #[serde(rename = "r", alias = "reduceOnly")]
+    pub reduce_only: bool,
+    #[serde(rename = "m", alias = "minutes")]
+    pub minutes: u64,
+    #[serde(rename = "t", alias = "randomize")]
+    pub randomize: bool,
+}
+
+/// Exchange-native TWAP: `sz` is worked over `minutes` in slices, optionally with randomized
+/// slice timing.
+#[derive(Debug)]
+pub struct ClientTwapRequest {
+    pub asset: String,
+    pub is_buy: bool,
+    pub sz: Sz,
+    pub reduce_only: bool,
+    pub minutes: u64,
+    pub randomize: bool,
+}
+
+impl ClientTwapRequest {
+    pub fn convert(
+        self,
+        coin_to_asset: &HashMap<String, u32>,
+        asset_rules: &HashMap<String, AssetRules>,
+        policy: RoundingPolicy,
+    ) -> Result<TwapRequest> {
+        let &asset = coin_to_asset.get(&self.asset).ok_or(Error::AssetNotFound)?;
+        let sz = match asset_rules.get(&self.asset) {
+            Some(rules) => rules.apply_sz(&self.asset, self.sz, policy)?,
+            None => self.sz,
+        };
+
+        Ok(TwapRequest {
+            asset,
+            is_buy: self.is_buy,
+            sz: sz.to_string(),
+            reduce_only: self.reduce_only,
+            minutes: self.minutes,
+            randomize: self.randomize,
+        })
+    }
+}
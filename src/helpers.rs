@@ -5,9 +5,9 @@ use lazy_static::lazy_static;
 use log::info;
 use uuid::Uuid;
 
-use crate::consts::*;
+use crate::{consts::*, decimal::MAX_SCALE, Decimal};
 
-fn now_timestamp_ms() -> u64 {
+pub(crate) fn now_timestamp_ms() -> u64 {
     let now = Utc::now();
     now.timestamp_millis() as u64
 }
@@ -53,13 +53,37 @@ pub(crate) fn uuid_to_hex_string(uuid: Uuid) -> String {
     format!("0x{hex_string}")
 }
 
+/// Truncates `float` to `decimals` places towards zero, then moves one step away from zero when
+/// `round_up` is set.
 pub fn truncate_float(float: f64, decimals: u32, round_up: bool) -> f64 {
-    let pow10 = 10i64.pow(decimals) as f64;
-    let mut float = (float * pow10) as u64;
-    if round_up {
-        float += 1;
-    }
-    float as f64 / pow10
+    let decimals = decimals.min(MAX_SCALE);
+    // Cut the exact digits of `float` rather than the 8 decimal wire formatting, which rounds
+    let digits = float.to_string();
+    let digits = match digits.split_once('.') {
+        Some((int_part, frac_part)) if frac_part.len() > decimals as usize => {
+            &digits[..int_part.len() + 1 + decimals as usize]
+        }
+        _ => &digits,
+    };
+    // NaN, infinities and values beyond the range of Decimal have nothing to truncate
+    let Ok(truncated) = digits.parse::<Decimal>() else {
+        return float;
+    };
+    let truncated = if round_up {
+        let step = Decimal::new(1, decimals);
+        let stepped = if float < 0.0 {
+            truncated.checked_sub(step)
+        } else {
+            truncated.checked_add(step)
+        };
+        match stepped {
+            Ok(stepped) => stepped,
+            Err(_) => return float,
+        }
+    } else {
+        truncated
+    };
+    truncated.to_f64()
 }
 
 pub fn bps_diff(x: f64, y: f64) -> u16 {
@@ -135,4 +159,20 @@ mod tests {
             "987654321".to_string()
         );
     }
+
+    #[test]
+    fn truncate_float_test() {
+        assert_eq!(truncate_float(1.23456, 2, false), 1.23);
+        assert_eq!(truncate_float(1.23456, 2, true), 1.24);
+        assert_eq!(truncate_float(0.29, 2, false), 0.29);
+        assert_eq!(truncate_float(-1.23456, 2, false), -1.23);
+        assert_eq!(truncate_float(-1.23456, 2, true), -1.24);
+        assert_eq!(truncate_float(-0.001, 2, false), 0.0);
+        assert_eq!(truncate_float(0.999999999, 2, false), 0.99);
+        assert_eq!(truncate_float(1.239999999, 2, false), 1.23);
+        assert_eq!(truncate_float(1.239999999, 2, true), 1.24);
+        assert_eq!(truncate_float(0.1 + 0.2, 20, false), 0.30000000000000004);
+        assert_eq!(truncate_float(1.5, 30, false), 1.5);
+        assert!(truncate_float(f64::NAN, 2, false).is_nan());
+    }
 }
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use tokio::io::{self, AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Request line and headers of an HTTP/1.1 request read by the local servers of the mock
+/// exchange and the tests.
+#[derive(Debug)]
+pub(crate) struct RequestHead {
+    pub(crate) path: String,
+    /// Header values keyed by lowercase name.
+    pub(crate) headers: HashMap<String, String>,
+}
+
+impl RequestHead {
+    fn content_length(&self) -> usize {
+        self.headers
+            .get("content-length")
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0)
+    }
+}
+
+/// Reads the head of the next request, `None` once the client closed the connection.
+pub(crate) async fn read_head<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> io::Result<Option<RequestHead>> {
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).await? == 0 {
+        return Ok(None);
+    }
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        match line.trim_end().split_once(':') {
+            Some((name, value)) => {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+            None => break,
+        }
+    }
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+    Ok(Some(RequestHead {
+        path: path.to_string(),
+        headers,
+    }))
+}
+
+/// Reads the `Content-Length` bytes of body following `head`.
+pub(crate) async fn read_body<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    head: &RequestHead,
+) -> io::Result<Vec<u8>> {
+    let mut body = vec![0; head.content_length()];
+    reader.read_exact(&mut body).await?;
+    Ok(body)
+}
+
+/// Reads the next request and returns its path and body.
+#[cfg(test)]
+pub(crate) async fn read_request<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> io::Result<Option<(String, String)>> {
+    let Some(head) = read_head(reader).await? else {
+        return Ok(None);
+    };
+    let body = read_body(reader, &head).await?;
+    Ok(Some((
+        head.path,
+        String::from_utf8_lossy(&body).into_owned(),
+    )))
+}
+
+/// Writes a JSON response, e.g. with `status` "200 OK". Without `keep_alive` the client is told
+/// the connection closes after it.
+pub(crate) async fn write_response<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    status: &str,
+    body: &str,
+    keep_alive: bool,
+) -> io::Result<()> {
+    let connection = if keep_alive { "keep-alive" } else { "close" };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: {connection}\r\n\r\n{body}",
+        body.len()
+    );
+    writer.write_all(response.as_bytes()).await
+}
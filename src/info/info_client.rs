@@ -1,22 +1,24 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use alloy::primitives::Address;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use uuid::Uuid;
 
 use crate::{
     info::{
         ActiveAssetDataResponse, CandlesSnapshotResponse, FundingHistoryResponse,
-        L2SnapshotResponse, OpenOrdersResponse, OrderInfo, RecentTradesResponse, UserFillsResponse,
-        UserStateResponse,
+        L2SnapshotResponse, OpenOrdersResponse, OrderInfo, RecentTradesResponse,
+        SubAccountResponse, UserFillsResponse, UserStateResponse,
     },
     meta::{AssetContext, Meta, SpotMeta, SpotMetaAndAssetCtxs},
     prelude::*,
     req::HttpClient,
-    ws::{Subscription, WsManager},
-    BaseUrl, Error, Message, OrderStatusResponse, ReferralResponse, UserFeesResponse,
-    UserFundingResponse, UserTokenBalanceResponse,
+    ws::{PostRequest, Subscription, WsManager, WsPostTransport},
+    BaseUrl, Error, Message, OidOrCloid, OrderBook, OrderBookHandle, OrderStatusResponse, Px,
+    ReferralResponse, RequestWeight, Transport, TwapHistory, TwapSliceFill, UserFeesResponse,
+    UserFundingResponse, UserRateLimitResponse, UserRoleResponse, UserTokenBalanceResponse,
 };
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -52,7 +54,7 @@ pub enum InfoRequest {
     },
     OrderStatus {
         user: Address,
-        oid: u64,
+        oid: OidOrCloid,
     },
     Meta,
     MetaAndAssetCtxs,
@@ -94,17 +96,69 @@ pub enum InfoRequest {
         user: Address,
         coin: String,
     },
+    UserTwapSliceFills {
+        user: Address,
+    },
+    TwapHistory {
+        user: Address,
+    },
+    SubAccounts {
+        user: Address,
+    },
+    UserRateLimit {
+        user: Address,
+    },
+    UserRole {
+        user: Address,
+    },
+}
+
+impl InfoRequest {
+    /// Weight of the request against the per-IP rate limit. Requests returning long lists may
+    /// cost the exchange more than this.
+    pub fn weight(&self) -> RequestWeight {
+        let ip = match self {
+            InfoRequest::UserState { .. }
+            | InfoRequest::UserTokenBalances { .. }
+            | InfoRequest::OrderStatus { .. }
+            | InfoRequest::AllMids
+            | InfoRequest::L2Book { .. } => 2,
+            InfoRequest::UserStates { .. }
+            | InfoRequest::UserFees { .. }
+            | InfoRequest::OpenOrders { .. }
+            | InfoRequest::Meta
+            | InfoRequest::MetaAndAssetCtxs
+            | InfoRequest::SpotMeta
+            | InfoRequest::SpotMetaAndAssetCtxs
+            | InfoRequest::UserFills { .. }
+            | InfoRequest::FundingHistory { .. }
+            | InfoRequest::UserFunding { .. }
+            | InfoRequest::RecentTrades { .. }
+            | InfoRequest::CandleSnapshot { .. }
+            | InfoRequest::Referral { .. }
+            | InfoRequest::HistoricalOrders { .. }
+            | InfoRequest::ActiveAssetData { .. }
+            | InfoRequest::UserTwapSliceFills { .. }
+            | InfoRequest::TwapHistory { .. }
+            | InfoRequest::SubAccounts { .. }
+            | InfoRequest::UserRateLimit { .. } => 20,
+            InfoRequest::UserRole { .. } => 60,
+        };
+        RequestWeight::info(ip)
+    }
 }
 
 #[derive(Debug)]
 pub struct InfoClient {
     pub http_client: HttpClient,
     pub(crate) ws_manager: Option<WsManager>,
+    ws_post: Option<WsPostTransport>,
     reconnect: bool,
 }
 
 impl InfoClient {
     pub async fn new(client: Option<Client>, base_url: Option<BaseUrl>) -> Result<InfoClient> {
+        let base_url = base_url.unwrap_or(BaseUrl::Mainnet).get_url();
         Self::new_internal(client, base_url, false).await
     }
 
@@ -112,31 +166,50 @@ impl InfoClient {
         client: Option<Client>,
         base_url: Option<BaseUrl>,
     ) -> Result<InfoClient> {
+        let base_url = base_url.unwrap_or(BaseUrl::Mainnet).get_url();
         Self::new_internal(client, base_url, true).await
     }
 
+    /// Client for an API served at any other url, e.g. a local mock server.
+    pub async fn with_url(client: Option<Client>, url: String) -> Result<InfoClient> {
+        Self::new_internal(client, url, false).await
+    }
+
     async fn new_internal(
         client: Option<Client>,
-        base_url: Option<BaseUrl>,
+        base_url: String,
         reconnect: bool,
     ) -> Result<InfoClient> {
         let client = client.unwrap_or_default();
-        let base_url = base_url.unwrap_or(BaseUrl::Mainnet).get_url();
 
         Ok(InfoClient {
-            http_client: HttpClient { client, base_url },
+            http_client: HttpClient::new(client, base_url),
             ws_manager: None,
+            ws_post: None,
             reconnect,
         })
     }
 
-    pub async fn subscribe(
-        &mut self,
-        subscription: Subscription,
-        sender_channel: UnboundedSender<Message>,
-    ) -> Result<u32> {
+    /// Client sending its requests and websocket connections through `transport`, e.g. a
+    /// [`crate::MockTransport`] in tests.
+    pub fn with_transport(transport: Arc<dyn Transport>, base_url: Option<BaseUrl>) -> InfoClient {
+        let base_url = base_url.unwrap_or(BaseUrl::Mainnet).get_url();
+        InfoClient::with_http_client(HttpClient::with_transport(transport, base_url))
+    }
+
+    pub(crate) fn with_http_client(http_client: HttpClient) -> InfoClient {
+        InfoClient {
+            http_client,
+            ws_manager: None,
+            ws_post: None,
+            reconnect: false,
+        }
+    }
+
+    async fn ws_manager(&mut self) -> Result<&mut WsManager> {
         if self.ws_manager.is_none() {
             let ws_manager = WsManager::new(
+                self.http_client.transport.clone(),
                 format!("ws{}/ws", &self.http_client.base_url[4..]),
                 self.reconnect,
             )
@@ -144,42 +217,71 @@ impl InfoClient {
             self.ws_manager = Some(ws_manager);
         }
 
-        let identifier =
-            serde_json::to_string(&subscription).map_err(|e| Error::JsonParse(e.to_string()))?;
+        self.ws_manager.as_mut().ok_or(Error::WsManagerNotFound)
+    }
+
+    pub async fn subscribe(
+        &mut self,
+        subscription: Subscription,
+        sender_channel: UnboundedSender<Message>,
+    ) -> Result<u32> {
+        let identifier = serde_json::to_string(&subscription).map_err(Error::serialize)?;
 
-        self.ws_manager
-            .as_mut()
-            .ok_or(Error::WsManagerNotFound)?
+        self.ws_manager()
+            .await?
             .add_subscription(identifier, sender_channel)
             .await
     }
 
     pub async fn unsubscribe(&mut self, subscription_id: u32) -> Result<()> {
-        if self.ws_manager.is_none() {
-            let ws_manager = WsManager::new(
-                format!("ws{}/ws", &self.http_client.base_url[4..]),
-                self.reconnect,
-            )
-            .await?;
-            self.ws_manager = Some(ws_manager);
-        }
-
-        self.ws_manager
-            .as_mut()
-            .ok_or(Error::WsManagerNotFound)?
+        self.ws_manager()
+            .await?
             .remove_subscription(subscription_id)
             .await
     }
 
+    /// Removes the subscriptions made before a later step of a setup failed. Failures are only
+    /// logged, so the caller can return the error of the failed step.
+    pub(crate) async fn unsubscribe_all(&mut self, subscription_ids: &[u32]) {
+        for &subscription_id in subscription_ids {
+            if let Err(err) = self.unsubscribe(subscription_id).await {
+                log::warn!("Failed to unsubscribe {subscription_id}: {err}");
+            }
+        }
+    }
+
+    /// Sends info requests as websocket `post` messages instead of HTTP requests, falling back
+    /// to HTTP while the websocket is disconnected.
+    ///
+    /// The returned transport shares this client's connection and can be handed to
+    /// [`ExchangeClient::ws_post`](crate::ExchangeClient::ws_post) as well.
+    pub async fn enable_ws_post(&mut self, timeout: Duration) -> Result<WsPostTransport> {
+        let transport = self.ws_manager().await?.post_transport(timeout);
+        self.ws_post = Some(transport.clone());
+        Ok(transport)
+    }
+
+    pub fn disable_ws_post(&mut self) {
+        self.ws_post = None;
+    }
+
     async fn send_info_request<T: for<'a> Deserialize<'a>>(
         &self,
         info_request: InfoRequest,
     ) -> Result<T> {
-        let data =
-            serde_json::to_string(&info_request).map_err(|e| Error::JsonParse(e.to_string()))?;
+        self.http_client.throttle(info_request.weight()).await?;
+        if let Some(ws_post) = &self.ws_post {
+            let payload = serde_json::to_value(&info_request).map_err(Error::serialize)?;
+            if let Some(return_data) = ws_post.post(PostRequest::Info(&payload)).await? {
+                return serde_json::from_str(&return_data)
+                    .map_err(|e| Error::response_parse(e, &return_data));
+            }
+        }
+
+        let data = serde_json::to_string(&info_request).map_err(Error::serialize)?;
 
         let return_data = self.http_client.post("/info", data).await?;
-        serde_json::from_str(&return_data).map_err(|e| Error::JsonParse(e.to_string()))
+        serde_json::from_str(&return_data).map_err(|e| Error::response_parse(e, &return_data))
     }
 
     pub async fn open_orders(&self, address: Address) -> Result<Vec<OpenOrdersResponse>> {
@@ -227,7 +329,7 @@ impl InfoClient {
         self.send_info_request(input).await
     }
 
-    pub async fn all_mids(&self) -> Result<HashMap<String, String>> {
+    pub async fn all_mids(&self) -> Result<HashMap<String, Px>> {
         let input = InfoRequest::AllMids;
         self.send_info_request(input).await
     }
@@ -275,6 +377,25 @@ impl InfoClient {
         self.send_info_request(input).await
     }
 
+    /// Seeds an [`OrderBook`] from [`InfoClient::l2_snapshot`] and keeps it up to date from an
+    /// `L2Book` subscription. Unsubscribing `subscription_id` stops the updates.
+    pub async fn order_book(&mut self, coin: String) -> Result<OrderBookHandle> {
+        let (sender, receiver) = unbounded_channel();
+        let subscription_id = self
+            .subscribe(Subscription::L2Book { coin: coin.clone() }, sender)
+            .await?;
+        let snapshot = match self.l2_snapshot(coin).await {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                // Nothing would read the updates of the subscription
+                self.unsubscribe_all(&[subscription_id]).await;
+                return Err(err);
+            }
+        };
+        let book = OrderBook::from_snapshot(&snapshot);
+        Ok(OrderBookHandle::spawn(book, subscription_id, receiver))
+    }
+
     pub async fn candles_snapshot(
         &self,
         coin: String,
@@ -298,7 +419,34 @@ impl InfoClient {
         address: Address,
         oid: u64,
     ) -> Result<OrderStatusResponse> {
-        let input = InfoRequest::OrderStatus { user: address, oid };
+        let input = InfoRequest::OrderStatus {
+            user: address,
+            oid: oid.into(),
+        };
+        self.send_info_request(input).await
+    }
+
+    pub async fn query_order_by_cloid(
+        &self,
+        address: Address,
+        cloid: Uuid,
+    ) -> Result<OrderStatusResponse> {
+        let input = InfoRequest::OrderStatus {
+            user: address,
+            oid: cloid.into(),
+        };
+        self.send_info_request(input).await
+    }
+
+    /// Address-based request budget of `user`.
+    pub async fn user_rate_limit(&self, user: Address) -> Result<UserRateLimitResponse> {
+        let input = InfoRequest::UserRateLimit { user };
+        self.send_info_request(input).await
+    }
+
+    /// Role of `user`, e.g. the account an agent wallet trades for.
+    pub async fn user_role(&self, user: Address) -> Result<UserRoleResponse> {
+        let input = InfoRequest::UserRole { user };
         self.send_info_request(input).await
     }
 
@@ -312,6 +460,24 @@ impl InfoClient {
         self.send_info_request(input).await
     }
 
+    /// Sub-accounts of `address`, including their perp and spot state.
+    pub async fn sub_accounts(&self, address: Address) -> Result<Vec<SubAccountResponse>> {
+        let input = InfoRequest::SubAccounts { user: address };
+        // Users without sub-accounts get `null`
+        let sub_accounts: Option<Vec<SubAccountResponse>> = self.send_info_request(input).await?;
+        Ok(sub_accounts.unwrap_or_default())
+    }
+
+    pub async fn user_twap_slice_fills(&self, address: Address) -> Result<Vec<TwapSliceFill>> {
+        let input = InfoRequest::UserTwapSliceFills { user: address };
+        self.send_info_request(input).await
+    }
+
+    pub async fn user_twap_history(&self, address: Address) -> Result<Vec<TwapHistory>> {
+        let input = InfoRequest::TwapHistory { user: address };
+        self.send_info_request(input).await
+    }
+
     pub async fn active_asset_data(
         &self,
         user: Address,
@@ -4,7 +4,7 @@ use alloy::primitives::Address;
 
 use crate::{
     info::{AssetPosition, Level, MarginSummary},
-    DailyUserVlm, Delta, FeeSchedule, Leverage, OrderInfo, Referrer, ReferrerState,
+    DailyUserVlm, Delta, FeeSchedule, Leverage, OrderInfo, Px, Referrer, ReferrerState, Side, Sz,
     UserTokenBalance,
 };
 
@@ -22,6 +22,16 @@ pub struct UserTokenBalanceResponse {
     pub balances: Vec<UserTokenBalance>,
 }
 
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubAccountResponse {
+    pub name: String,
+    pub sub_account_user: Address,
+    pub master: Address,
+    pub clearinghouse_state: UserStateResponse,
+    pub spot_state: UserTokenBalanceResponse,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UserFeesResponse {
@@ -36,10 +46,10 @@ pub struct UserFeesResponse {
 #[serde(rename_all = "camelCase")]
 pub struct OpenOrdersResponse {
     pub coin: String,
-    pub limit_px: String,
+    pub limit_px: Px,
     pub oid: u64,
-    pub side: String,
-    pub sz: String,
+    pub side: Side,
+    pub sz: Sz,
     pub timestamp: u64,
     pub cloid: Option<String>,
 }
@@ -53,10 +63,10 @@ pub struct UserFillsResponse {
     pub dir: String,
     pub hash: String,
     pub oid: u64,
-    pub px: String,
-    pub side: String,
-    pub start_position: String,
-    pub sz: String,
+    pub px: Px,
+    pub side: Side,
+    pub start_position: Sz,
+    pub sz: Sz,
     pub time: u64,
     pub fee: String,
     pub tid: u64,
@@ -92,9 +102,9 @@ pub struct L2SnapshotResponse {
 #[serde(rename_all = "camelCase")]
 pub struct RecentTradesResponse {
     pub coin: String,
-    pub side: String,
-    pub px: String,
-    pub sz: String,
+    pub side: Side,
+    pub px: Px,
+    pub sz: Sz,
     pub time: u64,
     pub hash: String,
 }
@@ -147,7 +157,28 @@ pub struct ActiveAssetDataResponse {
     pub user: Address,
     pub coin: String,
     pub leverage: Leverage,
-    pub max_trade_szs: Vec<String>,
+    pub max_trade_szs: Vec<Sz>,
     pub available_to_trade: Vec<String>,
-    pub mark_px: String,
+    pub mark_px: Px,
+}
+
+/// Address-based request budget: one request per USDC of cumulative volume, on top of an
+/// initial allowance.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserRateLimitResponse {
+    pub cum_vlm: String,
+    pub n_requests_used: u64,
+    pub n_requests_cap: u64,
+}
+
+/// What kind of account an address is. Agents (API wallets) report the account they trade for.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "role", content = "data", rename_all = "camelCase")]
+pub enum UserRoleResponse {
+    User,
+    Agent { user: Address },
+    Vault,
+    SubAccount { master: Address },
+    Missing,
 }
@@ -1,11 +1,20 @@
 use alloy::primitives::Address;
 use serde::{Deserialize, Serialize};
 
+use crate::{OrderStatus, Px, Side, Sz, Tif};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LeverageType {
+    Cross,
+    Isolated,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Leverage {
     #[serde(rename = "type")]
-    pub type_string: String,
+    pub leverage_type: LeverageType,
     pub value: u32,
     pub raw_usd: Option<String>,
 }
@@ -22,13 +31,13 @@ pub struct CumulativeFunding {
 #[serde(rename_all = "camelCase")]
 pub struct PositionData {
     pub coin: String,
-    pub entry_px: Option<String>,
+    pub entry_px: Option<Px>,
     pub leverage: Leverage,
-    pub liquidation_px: Option<String>,
+    pub liquidation_px: Option<Px>,
     pub margin_used: String,
     pub position_value: String,
     pub return_on_equity: String,
-    pub szi: String,
+    pub szi: Sz,
     pub unrealized_pnl: String,
     pub max_leverage: u32,
     pub cum_funding: CumulativeFunding,
@@ -54,8 +63,8 @@ pub struct MarginSummary {
 #[serde(rename_all = "camelCase")]
 pub struct Level {
     pub n: u64,
-    pub px: String,
-    pub sz: String,
+    pub px: Px,
+    pub sz: Sz,
 }
 
 #[derive(Deserialize, Debug)]
@@ -65,7 +74,7 @@ pub struct Delta {
     pub type_string: String,
     pub coin: String,
     pub usdc: String,
-    pub szi: String,
+    pub szi: Sz,
     pub funding_rate: String,
 }
 
@@ -121,7 +130,7 @@ pub struct UserTokenBalance {
 #[serde(rename_all = "camelCase")]
 pub struct OrderInfo {
     pub order: BasicOrderInfo,
-    pub status: String,
+    pub status: OrderStatus,
     pub status_timestamp: u64,
 }
 
@@ -129,19 +138,19 @@ pub struct OrderInfo {
 #[serde(rename_all = "camelCase")]
 pub struct BasicOrderInfo {
     pub coin: String,
-    pub side: String,
-    pub limit_px: String,
-    pub sz: String,
+    pub side: Side,
+    pub limit_px: Px,
+    pub sz: Sz,
     pub oid: u64,
     pub timestamp: u64,
     pub trigger_condition: String,
     pub is_trigger: bool,
-    pub trigger_px: String,
+    pub trigger_px: Px,
     pub is_position_tpsl: bool,
     pub reduce_only: bool,
     pub order_type: String,
-    pub orig_sz: String,
-    pub tif: Option<String>,
+    pub orig_sz: Sz,
+    pub tif: Option<Tif>,
     pub cloid: Option<String>,
 }
 
@@ -1,17 +1,43 @@
 #![deny(unreachable_pub)]
+mod asset_rules;
+mod conditional_orders;
 mod consts;
+mod dead_man_switch;
+mod decimal;
 mod eip712;
 mod errors;
 mod exchange;
 mod helpers;
+#[cfg(any(test, feature = "mock"))]
+mod http_server;
 mod info;
 mod market_maker;
 mod meta;
+#[cfg(feature = "mock")]
+mod mock;
+mod order_book;
+mod order_manager;
+mod portfolio;
 mod prelude;
+mod rate_limit;
 mod req;
+mod retry;
 mod signature;
+mod transport;
 mod ws;
+pub use asset_rules::{
+    AssetRules, RoundingPolicy, MAX_SIG_FIGS, PERP_MAX_DECIMALS, SPOT_MAX_DECIMALS,
+};
+pub use conditional_orders::{
+    ConditionalOrder, ConditionalOrderEngine, ConditionalOrders, ExpiringOrder, OcoLeg, OcoPair,
+    TrailingDistance, TrailingStop,
+};
 pub use consts::{EPSILON, LOCAL_API_URL, MAINNET_API_URL, TESTNET_API_URL};
+pub use dead_man_switch::{
+    DeadManSwitch, DeadManSwitchConfig, DeadManSwitchState, DeadManSwitchStatus, TriggerReason,
+    MAX_DAILY_SCHEDULE_CANCEL_TRIGGERS, MIN_SCHEDULE_CANCEL_DELAY,
+};
+pub use decimal::{Decimal, Px, Sz};
 pub use eip712::Eip712;
 pub use errors::Error;
 pub use exchange::*;
@@ -19,4 +45,13 @@ pub use helpers::{bps_diff, truncate_float, BaseUrl};
 pub use info::{info_client::*, *};
 pub use market_maker::{MarketMaker, MarketMakerInput, MarketMakerRestingOrder};
 pub use meta::{AssetContext, AssetMeta, Meta, MetaAndAssetCtxs, SpotAssetMeta, SpotMeta};
+#[cfg(feature = "mock")]
+pub use mock::{MockAsset, MockExchange};
+pub use order_book::{OrderBook, OrderBookHandle, PriceLevel};
+pub use order_manager::{OrderEvent, OrderManager, OrderState, TrackedOrder};
+pub use portfolio::{Portfolio, Position, PositionDrift};
+pub use rate_limit::{RateLimitMode, RateLimiter, RequestWeight, IP_WEIGHT_PER_MINUTE};
+pub use retry::{RetryClass, RetryPolicy};
+pub use signature::{sign_l1_action, sign_typed_data, FnSigner, HyperliquidSigner, RemoteSigner};
+pub use transport::{DefaultTransport, MockTransport, Transport, WsConnection, WsSink, WsStream};
 pub use ws::*;
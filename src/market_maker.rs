@@ -1,11 +1,11 @@
 use alloy::{primitives::Address, signers::local::PrivateKeySigner};
-use log::{error, info};
+use log::{debug, error, info, warn};
 use tokio::sync::mpsc::unbounded_channel;
 
 use crate::{
     bps_diff, truncate_float, BaseUrl, ClientCancelRequest, ClientLimit, ClientOrder,
     ClientOrderRequest, ExchangeClient, ExchangeDataStatus, ExchangeResponseStatus, InfoClient,
-    Message, Subscription, UserData, EPSILON,
+    Message, Px, Subscription, Sz, Tif, UserData, EPSILON,
 };
 #[derive(Debug)]
 pub struct MarketMakerRestingOrder {
@@ -104,7 +104,7 @@ impl MarketMaker {
                     let all_mids = all_mids.data.mids;
                     let mid = all_mids.get(&self.asset);
                     if let Some(mid) = mid {
-                        let mid: f64 = mid.parse().unwrap();
+                        let mid = mid.to_f64();
                         self.latest_mid_price = mid;
                         // Check to see if we need to cancel or place any new orders
                         self.potentially_update().await;
@@ -123,9 +123,9 @@ impl MarketMaker {
                     let user_events = user_events.data;
                     if let UserData::Fills(fills) = user_events {
                         for fill in fills {
-                            let amount: f64 = fill.sz.parse().unwrap();
+                            let amount = fill.sz.to_f64();
                             // Update our resting positions whenever we see a fill
-                            if fill.side.eq("B") {
+                            if fill.side.is_buy() {
                                 self.cur_position += amount;
                                 self.lower_resting.position -= amount;
                                 info!("Fill: bought {amount} {}", self.asset.clone());
@@ -139,8 +139,11 @@ impl MarketMaker {
                     // Check to see if we need to cancel or place any new orders
                     self.potentially_update().await;
                 }
-                _ => {
-                    panic!("Unsupported message type");
+                Message::NoData => {
+                    warn!("Websocket disconnected, waiting for reconnect");
+                }
+                message => {
+                    debug!("Ignoring unexpected message: {message:?}");
                 }
             }
         }
@@ -187,23 +190,25 @@ impl MarketMaker {
         price: f64,
         is_buy: bool,
     ) -> (f64, u64) {
-        let order = self
-            .exchange_client
-            .order(
-                ClientOrderRequest {
-                    asset,
-                    is_buy,
-                    reduce_only: false,
-                    limit_px: price,
-                    sz: amount,
-                    cloid: None,
-                    order_type: ClientOrder::Limit(ClientLimit {
-                        tif: "Gtc".to_string(),
-                    }),
-                },
-                None,
-            )
-            .await;
+        let order = match (Px::try_from(price), Sz::try_from(amount)) {
+            (Ok(limit_px), Ok(sz)) => {
+                self.exchange_client
+                    .order(
+                        ClientOrderRequest {
+                            asset,
+                            is_buy,
+                            reduce_only: false,
+                            limit_px,
+                            sz,
+                            cloid: None,
+                            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Gtc }),
+                        },
+                        None,
+                    )
+                    .await
+            }
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        };
         match order {
             Ok(order) => match order {
                 ExchangeResponseStatus::Ok(order) => {
@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use alloy::primitives::B128;
 use serde::Deserialize;
 
+use crate::Px;
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Meta {
     pub universe: Vec<AssetMeta>,
@@ -63,9 +65,9 @@ pub enum MetaAndAssetCtxs {
 #[serde(rename_all = "camelCase")]
 pub struct SpotAssetContext {
     pub day_ntl_vlm: String,
-    pub mark_px: String,
-    pub mid_px: Option<String>,
-    pub prev_day_px: String,
+    pub mark_px: Px,
+    pub mid_px: Option<Px>,
+    pub prev_day_px: Px,
     pub circulating_supply: String,
     pub coin: String,
 }
@@ -75,13 +77,13 @@ pub struct SpotAssetContext {
 pub struct AssetContext {
     pub day_ntl_vlm: String,
     pub funding: String,
-    pub impact_pxs: Option<Vec<String>>,
-    pub mark_px: String,
-    pub mid_px: Option<String>,
+    pub impact_pxs: Option<Vec<Px>>,
+    pub mark_px: Px,
+    pub mid_px: Option<Px>,
     pub open_interest: String,
-    pub oracle_px: String,
+    pub oracle_px: Px,
     pub premium: Option<String>,
-    pub prev_day_px: String,
+    pub prev_day_px: Px,
 }
 
 #[derive(Deserialize, Debug, Clone)]
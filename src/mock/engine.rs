@@ -0,0 +1,739 @@
+use std::collections::HashMap;
+
+use alloy::primitives::Address;
+use serde_json::{json, Value};
+
+use crate::{
+    exchange::{Limit, Order, OrderRequest},
+    helpers::{now_timestamp_ms, uuid_to_hex_string},
+    AssetRules, Decimal, ModifyRequest, OidOrCloid, OrderStatus, Px, Side, Sz, Tif,
+};
+
+const MIN_ORDER_VALUE: u32 = 10;
+const L2_BOOK_DEPTH: usize = 20;
+
+/// A perp listed on the [`crate::MockExchange`].
+#[derive(Debug, Clone)]
+pub struct MockAsset {
+    pub name: String,
+    pub sz_decimals: u32,
+    pub max_leverage: u32,
+    /// Reported as the mid price while one side of the book is empty.
+    pub mark_px: Px,
+}
+
+impl MockAsset {
+    pub fn new(name: &str, sz_decimals: u32, mark_px: impl Into<Px>) -> MockAsset {
+        MockAsset {
+            name: name.to_string(),
+            sz_decimals,
+            max_leverage: 50,
+            mark_px: mark_px.into(),
+        }
+    }
+}
+
+/// Something a websocket subscriber of `user` is told about.
+#[derive(Debug)]
+pub(super) enum Event {
+    OrderUpdate(Address, Value),
+    Fill(Address, Value),
+}
+
+#[derive(Debug, Clone)]
+struct RestingOrder {
+    oid: u64,
+    user: Address,
+    side: Side,
+    px: Px,
+    /// Size left to fill.
+    sz: Sz,
+    orig_sz: Sz,
+    timestamp: u64,
+    cloid: Option<String>,
+}
+
+impl RestingOrder {
+    fn crosses(&self, px: Px) -> bool {
+        match self.side {
+            Side::Buy => px <= self.px,
+            Side::Sell => px >= self.px,
+        }
+    }
+}
+
+/// Resting orders of one asset, best price first and oldest first within a price.
+#[derive(Debug, Default)]
+struct Book {
+    bids: Vec<RestingOrder>,
+    asks: Vec<RestingOrder>,
+}
+
+impl Book {
+    fn side_mut(&mut self, side: Side) -> &mut Vec<RestingOrder> {
+        match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        }
+    }
+
+    fn insert(&mut self, order: RestingOrder) {
+        let side = order.side;
+        let orders = self.side_mut(side);
+        let index = orders
+            .iter()
+            .position(|resting| match side {
+                Side::Buy => resting.px < order.px,
+                Side::Sell => resting.px > order.px,
+            })
+            .unwrap_or(orders.len());
+        orders.insert(index, order);
+    }
+
+    fn remove(&mut self, user: Address, oid: &OidOrCloid) -> Option<RestingOrder> {
+        for orders in [&mut self.bids, &mut self.asks] {
+            let index = orders.iter().position(|order| {
+                order.user == user
+                    && match oid {
+                        OidOrCloid::Oid(oid) => order.oid == *oid,
+                        OidOrCloid::Cloid(cloid) => {
+                            order.cloid.as_deref() == Some(&uuid_to_hex_string(*cloid))
+                        }
+                    }
+            });
+            if let Some(index) = index {
+                return Some(orders.remove(index));
+            }
+        }
+        None
+    }
+
+    fn levels(orders: &[RestingOrder]) -> Vec<Value> {
+        let mut levels: Vec<(Px, Sz, u64)> = Vec::new();
+        for order in orders {
+            match levels.last_mut() {
+                Some((px, sz, n)) if *px == order.px => {
+                    *sz = sz.checked_add(order.sz).unwrap_or(*sz);
+                    *n += 1;
+                }
+                _ => {
+                    if levels.len() == L2_BOOK_DEPTH {
+                        break;
+                    }
+                    levels.push((order.px, order.sz, 1));
+                }
+            }
+        }
+        levels
+            .into_iter()
+            .map(|(px, sz, n)| json!({"px": px, "sz": sz, "n": n}))
+            .collect()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Position {
+    szi: Sz,
+    entry_px: f64,
+}
+
+#[derive(Debug, Default)]
+struct Account {
+    /// Deposits plus realized pnl.
+    balance: f64,
+    positions: HashMap<usize, Position>,
+    /// Newest first, like the `userFills` info request.
+    fills: Vec<Value>,
+}
+
+/// Order books and accounts of the mock exchange. Orders match at the resting order's price,
+/// in price-time priority, without fees or margin checks.
+#[derive(Debug)]
+pub(super) struct Engine {
+    assets: Vec<MockAsset>,
+    books: Vec<Book>,
+    accounts: HashMap<Address, Account>,
+    next_oid: u64,
+    next_tid: u64,
+    events: Vec<Event>,
+}
+
+/// Amounts derived from the engine's own finite prices and sizes always convert.
+fn decimal(x: f64) -> Decimal {
+    Decimal::try_from(x).unwrap_or_default()
+}
+
+fn usd(x: f64) -> Decimal {
+    decimal(x).round_dp(6)
+}
+
+fn order_update(coin: &str, order: &RestingOrder, status: OrderStatus) -> Value {
+    json!({
+        "order": {
+            "coin": coin,
+            "side": order.side,
+            "limitPx": order.px,
+            "sz": order.sz,
+            "oid": order.oid,
+            "timestamp": order.timestamp,
+            "origSz": order.orig_sz,
+            "cloid": order.cloid,
+        },
+        "status": status,
+        "statusTimestamp": now_timestamp_ms(),
+    })
+}
+
+impl Engine {
+    pub(super) fn new(assets: Vec<MockAsset>) -> Engine {
+        Engine {
+            books: assets.iter().map(|_| Book::default()).collect(),
+            assets,
+            accounts: HashMap::new(),
+            next_oid: 1,
+            next_tid: 1,
+            events: Vec::new(),
+        }
+    }
+
+    pub(super) fn asset(&self, coin: &str) -> Option<usize> {
+        self.assets.iter().position(|asset| asset.name == coin)
+    }
+
+    pub(super) fn has_account(&self, user: Address) -> bool {
+        self.accounts.contains_key(&user)
+    }
+
+    pub(super) fn deposit(&mut self, user: Address, usd: f64) {
+        self.accounts.entry(user).or_default().balance += usd;
+    }
+
+    /// Events produced since the last call, oldest first.
+    pub(super) fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub(super) fn mid(&self, asset: usize) -> Px {
+        let book = &self.books[asset];
+        match (book.bids.first(), book.asks.first()) {
+            (Some(bid), Some(ask)) => bid
+                .px
+                .checked_add(ask.px)
+                .and_then(|sum| sum.checked_mul(Decimal::new(5, 1)))
+                .unwrap_or(self.assets[asset].mark_px),
+            _ => self.assets[asset].mark_px,
+        }
+    }
+
+    /// Places `order` for `user` and returns its status as sent in an `order` response.
+    pub(super) fn place(&mut self, user: Address, order: &OrderRequest) -> Value {
+        match self.try_place(user, order) {
+            Ok(status) => status,
+            Err(e) => json!({ "error": e }),
+        }
+    }
+
+    fn try_place(&mut self, user: Address, order: &OrderRequest) -> Result<Value, String> {
+        let asset = order.asset as usize;
+        let meta = self
+            .assets
+            .get(asset)
+            .ok_or_else(|| format!("Unknown asset {asset}"))?;
+        let Order::Limit(Limit { tif }) = order.order_type else {
+            return Err("Trigger orders are not supported by the mock exchange".to_string());
+        };
+        let px: Px = order
+            .limit_px
+            .parse()
+            .map_err(|_| format!("Invalid price {}", order.limit_px))?;
+        let mut sz: Sz = order
+            .sz
+            .parse()
+            .map_err(|_| format!("Invalid size {}", order.sz))?;
+
+        let rules = AssetRules::perp(meta.sz_decimals);
+        if !rules.is_valid_px(px) {
+            return Err(format!(
+                "Price must be divisible by tick size. asset={asset}"
+            ));
+        }
+        if !rules.is_valid_sz(sz) || sz <= Decimal::ZERO {
+            return Err(format!("Order has invalid size. asset={asset}"));
+        }
+        match px.checked_mul(sz) {
+            Ok(value) if value >= MIN_ORDER_VALUE.into() => {}
+            Ok(_) => {
+                return Err(format!(
+                    "Order must have minimum value of ${MIN_ORDER_VALUE}. asset={asset}"
+                ))
+            }
+            Err(_) => return Err(format!("Order has invalid size. asset={asset}")),
+        }
+        if order.reduce_only {
+            let szi = self.position(user, asset);
+            if szi.is_zero() || szi.is_sign_negative() != order.is_buy {
+                return Err(format!(
+                    "Reduce only order would increase position. asset={asset}"
+                ));
+            }
+            sz = sz.min(szi.abs());
+        }
+
+        let side = Side::from_is_buy(order.is_buy);
+        let book = &self.books[asset];
+        let best_opposite = match side {
+            Side::Buy => book.asks.first(),
+            Side::Sell => book.bids.first(),
+        };
+        let mut taker = RestingOrder {
+            oid: self.next_oid,
+            user,
+            side,
+            px,
+            sz,
+            orig_sz: sz,
+            timestamp: now_timestamp_ms(),
+            cloid: order.cloid.clone(),
+        };
+        if tif == Tif::Alo && best_opposite.is_some_and(|best| taker.crosses(best.px)) {
+            let bbo = |orders: &[RestingOrder]| orders.first().map(|o| o.px.to_string());
+            return Err(format!(
+                "Post only order would have immediately matched, bbo was {}@{}. asset={asset}",
+                bbo(&book.bids).unwrap_or_default(),
+                bbo(&book.asks).unwrap_or_default(),
+            ));
+        }
+        self.next_oid += 1;
+
+        let notional = self.match_order(asset, &mut taker);
+        let filled = taker.orig_sz.checked_sub(taker.sz).unwrap_or_default();
+        let coin = self.assets[asset].name.clone();
+        let filled_status = || {
+            json!({"filled": {
+                "totalSz": filled,
+                "avgPx": decimal(notional / filled.to_f64()),
+                "oid": taker.oid,
+            }})
+        };
+
+        if taker.sz.is_zero() {
+            let update = order_update(&coin, &taker, OrderStatus::Filled);
+            self.events.push(Event::OrderUpdate(user, update));
+            return Ok(filled_status());
+        }
+        match tif {
+            Tif::Ioc | Tif::FrontendMarket | Tif::LiquidationMarket => {
+                if filled.is_zero() {
+                    return Err(if tif == Tif::Ioc {
+                        format!("Order could not immediately match against any resting orders. asset={asset}")
+                    } else {
+                        format!("No liquidity available for market order. asset={asset}")
+                    });
+                }
+                let update = order_update(&coin, &taker, OrderStatus::Canceled);
+                self.events.push(Event::OrderUpdate(user, update));
+                Ok(filled_status())
+            }
+            Tif::Gtc | Tif::Alo => {
+                let update = order_update(&coin, &taker, OrderStatus::Open);
+                self.events.push(Event::OrderUpdate(user, update));
+                let status = json!({"resting": {"oid": taker.oid, "cloid": taker.cloid}});
+                self.books[asset].insert(taker);
+                Ok(status)
+            }
+        }
+    }
+
+    /// Fills `taker` against the book as far as its price allows and returns the filled
+    /// notional. Resting orders of the taker's own user are canceled instead.
+    fn match_order(&mut self, asset: usize, taker: &mut RestingOrder) -> f64 {
+        let coin = self.assets[asset].name.clone();
+        let opposite = match taker.side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+        let mut notional = 0.0;
+        while !taker.sz.is_zero() {
+            let orders = self.books[asset].side_mut(opposite);
+            let Some(maker) = orders.first_mut() else {
+                break;
+            };
+            if !taker.crosses(maker.px) {
+                break;
+            }
+            if maker.user == taker.user {
+                let maker = orders.remove(0);
+                let update = order_update(&coin, &maker, OrderStatus::SelfTradeCanceled);
+                self.events.push(Event::OrderUpdate(maker.user, update));
+                continue;
+            }
+
+            // Neither can overflow, `sz` is at most either size
+            let sz = maker.sz.min(taker.sz);
+            maker.sz = maker.sz.checked_sub(sz).unwrap_or_default();
+            taker.sz = taker.sz.checked_sub(sz).unwrap_or_default();
+            let maker = if maker.sz.is_zero() {
+                let maker = orders.remove(0);
+                let update = order_update(&coin, &maker, OrderStatus::Filled);
+                self.events.push(Event::OrderUpdate(maker.user, update));
+                maker
+            } else {
+                maker.clone()
+            };
+
+            let tid = self.next_tid;
+            self.next_tid += 1;
+            notional += maker.px.to_f64() * sz.to_f64();
+            self.fill(asset, &maker, maker.px, sz, false, tid);
+            self.fill(asset, taker, maker.px, sz, true, tid);
+        }
+        notional
+    }
+
+    fn position(&self, user: Address, asset: usize) -> Sz {
+        self.accounts
+            .get(&user)
+            .and_then(|account| account.positions.get(&asset))
+            .map(|position| position.szi)
+            .unwrap_or_default()
+    }
+
+    /// Books a fill of `sz` at `px` for the owner of `order`.
+    fn fill(
+        &mut self,
+        asset: usize,
+        order: &RestingOrder,
+        px: Px,
+        sz: Sz,
+        crossed: bool,
+        tid: u64,
+    ) {
+        let account = self.accounts.entry(order.user).or_default();
+        let position = account.positions.entry(asset).or_default();
+        let start = position.szi;
+        let end = match order.side {
+            Side::Buy => start.checked_add(sz),
+            Side::Sell => start.checked_sub(sz),
+        };
+        let Ok(end) = end else {
+            log::warn!("Position of {} overflows, dropping fill {tid}", order.user);
+            return;
+        };
+        let opening = start.is_zero() || start.is_sign_negative() != order.side.is_buy();
+        let flipped = !end.is_zero()
+            && !start.is_zero()
+            && end.is_sign_negative() != start.is_sign_negative();
+
+        let mut closed_pnl = 0.0;
+        if opening {
+            let (start_sz, sz) = (start.abs().to_f64(), sz.to_f64());
+            position.entry_px = (start_sz * position.entry_px + sz * px.to_f64()) / (start_sz + sz);
+        } else {
+            let closed = sz.min(start.abs()).to_f64();
+            let direction = if start.is_sign_negative() { -1.0 } else { 1.0 };
+            closed_pnl = closed * (px.to_f64() - position.entry_px) * direction;
+            if flipped {
+                position.entry_px = px.to_f64();
+            }
+        }
+        position.szi = end;
+        account.balance += closed_pnl;
+
+        let long = |sz: Sz| {
+            if sz.is_sign_negative() {
+                "Short"
+            } else {
+                "Long"
+            }
+        };
+        let dir = if flipped {
+            format!("{} > {}", long(start), long(end))
+        } else if opening {
+            format!("Open {}", long(end))
+        } else {
+            format!("Close {}", long(start))
+        };
+        let fill = json!({
+            "coin": self.assets[asset].name,
+            "side": order.side,
+            "px": px,
+            "sz": sz,
+            "time": now_timestamp_ms(),
+            "hash": format!("0x{tid:064x}"),
+            "startPosition": start,
+            "dir": dir,
+            "closedPnl": usd(closed_pnl),
+            "oid": order.oid,
+            "cloid": order.cloid,
+            "crossed": crossed,
+            "fee": "0.0",
+            "feeToken": "USDC",
+            "tid": tid,
+        });
+        account.fills.insert(0, fill.clone());
+        self.events.push(Event::Fill(order.user, fill));
+    }
+
+    /// Cancels a resting order of `user` and returns its status as sent in a `cancel` response.
+    pub(super) fn cancel(&mut self, user: Address, asset: u32, oid: OidOrCloid) -> Value {
+        let asset = asset as usize;
+        let Some(order) = self
+            .books
+            .get_mut(asset)
+            .and_then(|book| book.remove(user, &oid))
+        else {
+            return json!({
+                "error": format!("Order was never placed, already canceled, or filled. asset={asset}")
+            });
+        };
+        let update = order_update(&self.assets[asset].name, &order, OrderStatus::Canceled);
+        self.events.push(Event::OrderUpdate(user, update));
+        json!("success")
+    }
+
+    /// Replaces a resting order of `user` with a new one, like a cancel followed by an order.
+    pub(super) fn modify(&mut self, user: Address, modify: &ModifyRequest) -> Value {
+        let status = self.cancel(user, modify.order.asset, modify.oid);
+        if status.get("error").is_some() {
+            return status;
+        }
+        self.place(user, &modify.order)
+    }
+
+    /// Answers an `/info` request, or returns why it isn't supported.
+    pub(super) fn info(&self, request: &Value) -> Result<Value, String> {
+        let user = || {
+            serde_json::from_value::<Address>(request["user"].clone())
+                .map_err(|e| format!("Invalid user: {e}"))
+        };
+        match request["type"].as_str().unwrap_or_default() {
+            "meta" => Ok(json!({
+                "universe": self.assets.iter().map(|asset| json!({
+                    "name": asset.name,
+                    "szDecimals": asset.sz_decimals,
+                    "maxLeverage": asset.max_leverage,
+                })).collect::<Vec<_>>()
+            })),
+            "spotMeta" => Ok(json!({"universe": [], "tokens": []})),
+            "allMids" => Ok(self.all_mids()),
+            "l2Book" => {
+                let coin = request["coin"].as_str().unwrap_or_default();
+                let asset = self
+                    .asset(coin)
+                    .ok_or_else(|| format!("Unknown coin {coin}"))?;
+                Ok(self.l2_book(asset))
+            }
+            "clearinghouseState" => Ok(self.clearinghouse_state(user()?)),
+            "openOrders" => Ok(self.open_orders(user()?)),
+            "userFills" => Ok(json!(self.user_fills(user()?))),
+            request_type => Err(format!(
+                "Info request {request_type:?} is not supported by the mock exchange"
+            )),
+        }
+    }
+
+    pub(super) fn all_mids(&self) -> Value {
+        let mids: serde_json::Map<String, Value> = self
+            .assets
+            .iter()
+            .enumerate()
+            .map(|(asset, meta)| (meta.name.clone(), json!(self.mid(asset))))
+            .collect();
+        Value::Object(mids)
+    }
+
+    pub(super) fn l2_book(&self, asset: usize) -> Value {
+        let book = &self.books[asset];
+        json!({
+            "coin": self.assets[asset].name,
+            "time": now_timestamp_ms(),
+            "levels": [Book::levels(&book.bids), Book::levels(&book.asks)],
+        })
+    }
+
+    pub(super) fn user_fills(&self, user: Address) -> Vec<Value> {
+        self.accounts
+            .get(&user)
+            .map(|account| account.fills.clone())
+            .unwrap_or_default()
+    }
+
+    fn open_orders(&self, user: Address) -> Value {
+        let orders: Vec<Value> = self
+            .books
+            .iter()
+            .zip(&self.assets)
+            .flat_map(|(book, asset)| {
+                book.bids
+                    .iter()
+                    .chain(&book.asks)
+                    .filter(move |order| order.user == user)
+                    .map(move |order| {
+                        json!({
+                            "coin": asset.name,
+                            "limitPx": order.px,
+                            "oid": order.oid,
+                            "side": order.side,
+                            "sz": order.sz,
+                            "timestamp": order.timestamp,
+                            "cloid": order.cloid,
+                        })
+                    })
+            })
+            .collect();
+        json!(orders)
+    }
+
+    fn clearinghouse_state(&self, user: Address) -> Value {
+        let balance = self
+            .accounts
+            .get(&user)
+            .map_or(0.0, |account| account.balance);
+        let mut positions: Vec<(&usize, &Position)> = self
+            .accounts
+            .get(&user)
+            .map(|account| account.positions.iter().collect())
+            .unwrap_or_default();
+        positions.sort_by_key(|(asset, _)| **asset);
+
+        let (mut total_ntl, mut total_margin, mut total_upnl) = (0.0, 0.0, 0.0);
+        let asset_positions: Vec<Value> = positions
+            .into_iter()
+            .filter(|(_, position)| !position.szi.is_zero())
+            .map(|(&asset, position)| {
+                let meta = &self.assets[asset];
+                let mid = self.mid(asset).to_f64();
+                let szi = position.szi.to_f64();
+                let value = szi.abs() * mid;
+                let upnl = szi * (mid - position.entry_px);
+                let margin = value / f64::from(meta.max_leverage);
+                total_ntl += value;
+                total_margin += margin;
+                total_upnl += upnl;
+                json!({
+                    "type": "oneWay",
+                    "position": {
+                        "coin": meta.name,
+                        "entryPx": decimal(position.entry_px),
+                        "leverage": {"type": "cross", "value": meta.max_leverage},
+                        "liquidationPx": null,
+                        "marginUsed": usd(margin),
+                        "positionValue": usd(value),
+                        "returnOnEquity": usd(upnl / margin),
+                        "szi": position.szi,
+                        "unrealizedPnl": usd(upnl),
+                        "maxLeverage": meta.max_leverage,
+                        "cumFunding": {"allTime": "0.0", "sinceOpen": "0.0", "sinceChange": "0.0"},
+                    },
+                })
+            })
+            .collect();
+
+        let account_value = balance + total_upnl;
+        let summary = json!({
+            "accountValue": usd(account_value),
+            "totalMarginUsed": usd(total_margin),
+            "totalNtlPos": usd(total_ntl),
+            "totalRawUsd": usd(balance),
+        });
+        json!({
+            "assetPositions": asset_positions,
+            "crossMarginSummary": summary,
+            "marginSummary": summary,
+            "withdrawable": usd((account_value - total_margin).max(0.0)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OrderRejectReason;
+
+    fn order(is_buy: bool, px: &str, sz: &str, tif: Tif) -> OrderRequest {
+        OrderRequest {
+            asset: 0,
+            is_buy,
+            limit_px: px.to_string(),
+            sz: sz.to_string(),
+            reduce_only: false,
+            order_type: Order::Limit(Limit { tif }),
+            cloid: None,
+        }
+    }
+
+    fn reject_reason(status: &Value) -> OrderRejectReason {
+        OrderRejectReason::from_message(status["error"].as_str().unwrap())
+    }
+
+    #[test]
+    fn test_matching() {
+        let mut engine = Engine::new(vec![MockAsset::new("ETH", 4, 2000)]);
+        let (maker, taker) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        assert_eq!(
+            engine.place(maker, &order(false, "2001", "1", Tif::Gtc)),
+            json!({"resting": {"oid": 1, "cloid": null}})
+        );
+        engine.place(maker, &order(false, "2002", "1", Tif::Gtc));
+        engine.place(maker, &order(true, "1999", "1", Tif::Gtc));
+        assert_eq!(engine.mid(0), Px::from(2000));
+
+        let status = engine.place(taker, &order(true, "2001", "1", Tif::Alo));
+        assert_eq!(
+            reject_reason(&status),
+            OrderRejectReason::PostOnlyWouldCross
+        );
+        let status = engine.place(taker, &order(true, "1999.5", "0.001", Tif::Gtc));
+        assert_eq!(reject_reason(&status), OrderRejectReason::MinNotional);
+
+        // Sweeps both asks at the makers' prices
+        let status = engine.place(taker, &order(true, "2005", "2", Tif::Ioc));
+        assert_eq!(
+            status,
+            json!({"filled": {"totalSz": "2", "avgPx": "2001.5", "oid": 4}})
+        );
+        let status = engine.place(taker, &order(true, "2005", "1", Tif::Ioc));
+        assert_eq!(reject_reason(&status), OrderRejectReason::IocCouldNotMatch);
+
+        // Closing half of the long realizes the loss against the average entry
+        engine.place(taker, &order(false, "1999", "1", Tif::Ioc));
+        let fill = &engine.user_fills(taker)[0];
+        assert_eq!(fill["dir"], "Close Long");
+        assert_eq!(fill["closedPnl"], "-2.5");
+        assert_eq!(engine.user_fills(maker)[0]["closedPnl"], "2.5");
+        assert_eq!(engine.position(taker, 0), Sz::from(1));
+
+        let mut reduce_only = order(true, "2000", "1", Tif::Gtc);
+        reduce_only.reduce_only = true;
+        let status = engine.place(taker, &reduce_only);
+        assert_eq!(reject_reason(&status), OrderRejectReason::ReduceOnly);
+    }
+
+    #[test]
+    fn test_cancel_and_self_trade() {
+        let mut engine = Engine::new(vec![MockAsset::new("ETH", 4, 2000)]);
+        let user = Address::repeat_byte(1);
+        engine.place(user, &order(false, "2001", "1", Tif::Gtc));
+        engine.take_events();
+
+        // Crossing its own ask cancels it instead of trading
+        assert_eq!(
+            engine.place(user, &order(true, "2001", "1", Tif::Gtc)),
+            json!({"resting": {"oid": 2, "cloid": null}})
+        );
+        let statuses: Vec<Value> = engine
+            .take_events()
+            .into_iter()
+            .map(|event| match event {
+                Event::OrderUpdate(_, update) => update["status"].clone(),
+                Event::Fill(..) => panic!("unexpected fill"),
+            })
+            .collect();
+        assert_eq!(statuses, [json!("selfTradeCanceled"), json!("open")]);
+
+        assert_eq!(engine.cancel(user, 0, OidOrCloid::Oid(2)), json!("success"));
+        let status = engine.cancel(user, 0, OidOrCloid::Oid(2));
+        assert_eq!(reject_reason(&status), OrderRejectReason::UnknownOrder);
+        assert_eq!(engine.mid(0), Px::from(2000));
+    }
+}
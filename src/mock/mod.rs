@@ -0,0 +1,5 @@
+mod engine;
+mod server;
+
+pub use engine::MockAsset;
+pub use server::MockExchange;
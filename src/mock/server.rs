@@ -0,0 +1,594 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use alloy::primitives::Address;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    spawn,
+    sync::mpsc::{unbounded_channel, UnboundedSender},
+    task::JoinHandle,
+};
+use tokio_tungstenite::{
+    tungstenite::{
+        handshake::derive_accept_key,
+        protocol::{Message as WsMessage, Role},
+    },
+    WebSocketStream,
+};
+
+use super::engine::{Engine, Event, MockAsset};
+use crate::{
+    exchange::{ExchangePayload, Limit, OrderRequest},
+    helpers::now_timestamp_ms,
+    http_server::{read_body, read_head, write_response},
+    prelude::*,
+    signature::agent::l1,
+    Actions, Eip712, Error, ExchangeDataStatus, OidOrCloid, Order, Px, Sz, Tif,
+};
+
+#[derive(Debug)]
+struct WsClient {
+    id: u64,
+    sender: UnboundedSender<String>,
+    subscriptions: Vec<Value>,
+}
+
+#[derive(Debug)]
+struct State {
+    engine: Engine,
+    /// Approved agent wallets and the account each one trades for.
+    agents: HashMap<Address, Address>,
+    /// Nonces used so far by each signer.
+    nonces: HashMap<Address, HashSet<u64>>,
+    clients: Vec<WsClient>,
+    next_client_id: u64,
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn subscribed_user(subscription: &Value) -> Option<Address> {
+    subscription["user"].as_str()?.parse().ok()
+}
+
+/// Recovers the address that signed `payload`. L1 actions are expected to be signed for
+/// testnet, as the SDK does for every url but mainnet's.
+fn recover_signer(
+    action: &Actions,
+    payload: &ExchangePayload,
+) -> std::result::Result<Address, String> {
+    let hash = match action {
+        Actions::ApproveAgent(approve_agent) => approve_agent.eip712_signing_hash(),
+        _ => {
+            let connection_id = action
+                .hash(payload.nonce, payload.vault_address, payload.expires_after)
+                .map_err(|e| e.to_string())?;
+            l1::Agent {
+                source: "b".to_string(),
+                connectionId: connection_id,
+            }
+            .eip712_signing_hash()
+        }
+    };
+    payload
+        .signature
+        .recover_address_from_prehash(&hash)
+        .map_err(|e| format!("Invalid signature: {e}"))
+}
+
+impl State {
+    /// Answers an `/exchange` request.
+    fn exchange(&mut self, request: &Value) -> Value {
+        match self.try_exchange(request) {
+            Ok(response) => json!({"status": "ok", "response": response}),
+            Err(e) => json!({"status": "err", "response": e}),
+        }
+    }
+
+    fn try_exchange(&mut self, request: &Value) -> std::result::Result<Value, String> {
+        let payload: ExchangePayload = serde_json::from_value(request.clone())
+            .map_err(|e| format!("Failed to deserialize the JSON body: {e}"))?;
+        let action: Actions = serde_json::from_value(payload.action.clone())
+            .map_err(|e| format!("Failed to deserialize the action: {e}"))?;
+        if !matches!(
+            action,
+            Actions::Order(_)
+                | Actions::Cancel(_)
+                | Actions::CancelByCloid(_)
+                | Actions::BatchModify(_)
+                | Actions::ApproveAgent(_)
+                | Actions::Noop(_)
+        ) {
+            return Err(format!(
+                "Action {} is not supported by the mock exchange",
+                payload.action["type"]
+            ));
+        }
+        if payload
+            .expires_after
+            .is_some_and(|expires_after| expires_after < now_timestamp_ms())
+        {
+            return Err("Action has expired".to_string());
+        }
+
+        let signer = recover_signer(&action, &payload)?;
+        let account = self.agents.get(&signer).copied().unwrap_or(signer);
+        if !self.engine.has_account(account) {
+            return Err(format!("User or API Wallet {signer} does not exist."));
+        }
+        if !self.nonces.entry(signer).or_default().insert(payload.nonce) {
+            return Err(format!("Invalid nonce: duplicate nonce {}", payload.nonce));
+        }
+        let user = payload.vault_address.unwrap_or(account);
+
+        let engine = &mut self.engine;
+        let (response_type, statuses): (_, Vec<Value>) = match action {
+            Actions::Order(bulk_order) => (
+                "order",
+                bulk_order
+                    .orders
+                    .iter()
+                    .map(|order| engine.place(user, order))
+                    .collect(),
+            ),
+            Actions::Cancel(bulk_cancel) => (
+                "cancel",
+                bulk_cancel
+                    .cancels
+                    .into_iter()
+                    .map(|cancel| engine.cancel(user, cancel.asset, OidOrCloid::Oid(cancel.oid)))
+                    .collect(),
+            ),
+            Actions::CancelByCloid(bulk_cancel) => (
+                "cancel",
+                bulk_cancel
+                    .cancels
+                    .into_iter()
+                    .map(
+                        |cancel| match serde_json::from_value::<OidOrCloid>(json!(cancel.cloid)) {
+                            Ok(cloid) => engine.cancel(user, cancel.asset, cloid),
+                            Err(e) => json!({ "error": format!("Invalid cloid: {e}") }),
+                        },
+                    )
+                    .collect(),
+            ),
+            Actions::BatchModify(bulk_modify) => (
+                "order",
+                bulk_modify
+                    .modifies
+                    .iter()
+                    .map(|modify| engine.modify(user, modify))
+                    .collect(),
+            ),
+            Actions::ApproveAgent(approve_agent) => {
+                self.agents.insert(approve_agent.agent_address, signer);
+                return Ok(json!({"type": "default"}));
+            }
+            _ => return Ok(json!({"type": "default"})),
+        };
+        Ok(json!({"type": response_type, "data": {"statuses": statuses}}))
+    }
+
+    /// Handles a websocket request and returns the frames to send back.
+    fn websocket(&mut self, client_id: u64, request: &Value) -> Vec<Value> {
+        let Some(client) = self
+            .clients
+            .iter_mut()
+            .find(|client| client.id == client_id)
+        else {
+            return Vec::new();
+        };
+        let subscription = &request["subscription"];
+        match request["method"].as_str().unwrap_or_default() {
+            "ping" => vec![json!({"channel": "pong"})],
+            "subscribe" => {
+                client.subscriptions.push(subscription.clone());
+                let response = json!({"channel": "subscriptionResponse", "data": request});
+                [response]
+                    .into_iter()
+                    .chain(self.snapshot(subscription))
+                    .collect()
+            }
+            "unsubscribe" => {
+                client.subscriptions.retain(|s| s != subscription);
+                vec![json!({"channel": "subscriptionResponse", "data": request})]
+            }
+            "post" => {
+                let payload = &request["request"]["payload"];
+                let response = match request["request"]["type"].as_str() {
+                    Some("info") => match self.engine.info(payload) {
+                        Ok(data) => json!({
+                            "type": "info",
+                            "payload": {"type": payload["type"], "data": data},
+                        }),
+                        Err(e) => json!({"type": "error", "payload": e}),
+                    },
+                    Some("action") => json!({"type": "action", "payload": self.exchange(payload)}),
+                    _ => json!({"type": "error", "payload": "Unknown post request type"}),
+                };
+                vec![
+                    json!({"channel": "post", "data": {"id": request["id"], "response": response}}),
+                ]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Current data of a subscription, sent right after subscribing.
+    fn snapshot(&self, subscription: &Value) -> Option<Value> {
+        match subscription["type"].as_str()? {
+            "allMids" => {
+                Some(json!({"channel": "allMids", "data": {"mids": self.engine.all_mids()}}))
+            }
+            "l2Book" => {
+                let asset = self.engine.asset(subscription["coin"].as_str()?)?;
+                Some(json!({"channel": "l2Book", "data": self.engine.l2_book(asset)}))
+            }
+            "userFills" => {
+                let user = subscribed_user(subscription)?;
+                Some(json!({"channel": "userFills", "data": {
+                    "isSnapshot": true,
+                    "user": user,
+                    "fills": self.engine.user_fills(user),
+                }}))
+            }
+            _ => None,
+        }
+    }
+
+    fn send(&self, frame: &Value, subscribed: impl Fn(&Value) -> bool) {
+        let frame = frame.to_string();
+        for client in &self.clients {
+            if client.subscriptions.iter().any(&subscribed) {
+                let _ = client.sender.send(frame.clone());
+            }
+        }
+    }
+
+    /// Pushes the engine's events to their subscribers, followed by the updated books and mids.
+    fn dispatch(&mut self) {
+        let events = self.engine.take_events();
+        if events.is_empty() {
+            return;
+        }
+        for event in events {
+            let (channel, user, data) = match event {
+                Event::OrderUpdate(user, update) => ("orderUpdates", user, json!([update])),
+                Event::Fill(user, fill) => {
+                    ("userFills", user, json!({"user": user, "fills": [fill]}))
+                }
+            };
+            self.send(&json!({"channel": channel, "data": data}), |subscription| {
+                subscription["type"] == channel && subscribed_user(subscription) == Some(user)
+            });
+        }
+        let subscriptions: HashSet<String> = self
+            .clients
+            .iter()
+            .flat_map(|client| &client.subscriptions)
+            .map(Value::to_string)
+            .collect();
+        for subscription in subscriptions {
+            let subscription: Value = serde_json::from_str(&subscription).unwrap_or_default();
+            if matches!(subscription["type"].as_str(), Some("allMids" | "l2Book")) {
+                if let Some(frame) = self.snapshot(&subscription) {
+                    self.send(&frame, |s| *s == subscription);
+                }
+            }
+        }
+    }
+}
+
+/// Local HTTP and websocket server imitating the exchange, for testing clients and strategies
+/// offline.
+///
+/// `/info` serves `meta`, `spotMeta`, `allMids`, `l2Book`, `clearinghouseState`, `openOrders`
+/// and `userFills`. `/exchange` accepts limit orders, cancels and modifies signed by an
+/// account created with [`MockExchange::deposit`] or one of its approved agents, and matches
+/// them against a simple order book. The websocket at `/ws` serves `allMids`, `l2Book`,
+/// `orderUpdates` and `userFills` subscriptions as well as `post` requests.
+///
+/// Bind it to `127.0.0.1:3001` to use [`crate::BaseUrl::Localhost`], or to port 0 and use
+/// [`MockExchange::url`].
+#[derive(Debug)]
+pub struct MockExchange {
+    local_addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    server: JoinHandle<()>,
+}
+
+impl MockExchange {
+    pub async fn start(addr: impl ToSocketAddrs, assets: Vec<MockAsset>) -> Result<MockExchange> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State {
+            engine: Engine::new(assets),
+            agents: HashMap::new(),
+            nonces: HashMap::new(),
+            clients: Vec::new(),
+            next_client_id: 0,
+        }));
+
+        let server_state = Arc::clone(&state);
+        let server = spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = Arc::clone(&server_state);
+                spawn(async move {
+                    if let Err(err) = serve_connection(state, stream).await {
+                        log::debug!("Mock exchange connection failed: {err}");
+                    }
+                });
+            }
+        });
+
+        Ok(MockExchange {
+            local_addr,
+            state,
+            server,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Url to pass to [`crate::InfoClient::with_url`] and [`crate::ExchangeClient::with_url`].
+    pub fn url(&self) -> String {
+        format!("http://{}", self.local_addr)
+    }
+
+    /// Credits `user` with `usd`, creating the account if needed. Only existing accounts and
+    /// their agents may send actions.
+    pub fn deposit(&self, user: Address, usd: f64) {
+        lock(&self.state).engine.deposit(user, usd);
+    }
+
+    /// Places a Gtc order for `user` without a signature, e.g. to provide liquidity.
+    pub fn place_order(
+        &self,
+        user: Address,
+        coin: &str,
+        is_buy: bool,
+        px: Px,
+        sz: Sz,
+    ) -> Result<ExchangeDataStatus> {
+        let mut state = lock(&self.state);
+        let asset = state.engine.asset(coin).ok_or(Error::AssetNotFound)?;
+        let order = OrderRequest {
+            asset: asset as u32,
+            is_buy,
+            limit_px: px.to_string(),
+            sz: sz.to_string(),
+            reduce_only: false,
+            order_type: Order::Limit(Limit { tif: Tif::Gtc }),
+            cloid: None,
+        };
+        let status = state.engine.place(user, &order);
+        state.dispatch();
+        serde_json::from_value(status).map_err(Error::json_parse)
+    }
+}
+
+impl Drop for MockExchange {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn serve_connection(state: Arc<Mutex<State>>, stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let Some(head) = read_head(&mut reader).await? else {
+            return Ok(());
+        };
+
+        if head
+            .headers
+            .get("upgrade")
+            .is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"))
+        {
+            let key = head
+                .headers
+                .get("sec-websocket-key")
+                .cloned()
+                .unwrap_or_default();
+            let mut stream = reader.into_inner();
+            let response = format!(
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                derive_accept_key(key.as_bytes())
+            );
+            stream.write_all(response.as_bytes()).await?;
+            let ws = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+            serve_websocket(state, ws).await;
+            return Ok(());
+        }
+
+        let body = read_body(&mut reader, &head).await?;
+        let path = head.path.as_str();
+        let (status, response) = match serde_json::from_slice::<Value>(&body) {
+            Ok(request) => {
+                let mut state = lock(&state);
+                match path {
+                    "/info" => match state.engine.info(&request) {
+                        Ok(data) => ("200 OK", data.to_string()),
+                        Err(e) => ("422 Unprocessable Entity", e),
+                    },
+                    "/exchange" => {
+                        let response = state.exchange(&request);
+                        state.dispatch();
+                        ("200 OK", response.to_string())
+                    }
+                    _ => ("404 Not Found", format!("No route for {path}")),
+                }
+            }
+            Err(e) => ("400 Bad Request", format!("Invalid JSON body: {e}")),
+        };
+        write_response(reader.get_mut(), status, &response, true).await?;
+    }
+}
+
+async fn serve_websocket(state: Arc<Mutex<State>>, ws: WebSocketStream<TcpStream>) {
+    let (mut writer, mut reader) = ws.split();
+    let (sender, mut receiver) = unbounded_channel::<String>();
+    let client_id = {
+        let mut state = lock(&state);
+        state.next_client_id += 1;
+        let id = state.next_client_id;
+        state.clients.push(WsClient {
+            id,
+            sender: sender.clone(),
+            subscriptions: Vec::new(),
+        });
+        id
+    };
+
+    let forward = spawn(async move {
+        while let Some(frame) = receiver.recv().await {
+            if writer.send(WsMessage::Text(frame)).await.is_err() {
+                break;
+            }
+        }
+    });
+    while let Some(Ok(message)) = reader.next().await {
+        let WsMessage::Text(text) = message else {
+            continue;
+        };
+        let Ok(request) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+        let mut state = lock(&state);
+        for frame in state.websocket(client_id, &request) {
+            let _ = sender.send(frame.to_string());
+        }
+        state.dispatch();
+    }
+
+    lock(&state).clients.retain(|client| client.id != client_id);
+    forward.abort();
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::signers::local::PrivateKeySigner;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+    use crate::{
+        ClientCancelRequest, ClientLimit, ClientOrder, ClientOrderRequest, ExchangeClient,
+        ExchangeResponseStatus, InfoClient, Message, OrderRejectReason, OrderStatus, Subscription,
+    };
+
+    fn statuses(response: ExchangeResponseStatus) -> Vec<ExchangeDataStatus> {
+        match response {
+            ExchangeResponseStatus::Ok(response) => response.data.unwrap().statuses,
+            ExchangeResponseStatus::Err(e) => panic!("request failed: {e}"),
+        }
+    }
+
+    fn buy(limit_px: f64, sz: f64) -> ClientOrderRequest {
+        ClientOrderRequest {
+            asset: "ETH".to_string(),
+            is_buy: true,
+            reduce_only: false,
+            limit_px: limit_px.try_into().unwrap(),
+            sz: sz.try_into().unwrap(),
+            cloid: None,
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Gtc }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_exchange() -> Result<()> {
+        let exchange =
+            MockExchange::start("127.0.0.1:0", vec![MockAsset::new("ETH", 4, 2000)]).await?;
+        let wallet: PrivateKeySigner =
+            "e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e"
+                .parse()
+                .unwrap();
+        let user = wallet.address();
+        exchange.deposit(user, 10_000.0);
+        exchange.place_order(Address::repeat_byte(1), "ETH", false, 2001.into(), 1.into())?;
+
+        let mut info_client = InfoClient::with_url(None, exchange.url()).await?;
+        let (sender, mut receiver) = unbounded_channel();
+        info_client
+            .subscribe(Subscription::OrderUpdates { user }, sender.clone())
+            .await?;
+        info_client
+            .subscribe(Subscription::UserFills { user }, sender)
+            .await?;
+        let Some(Message::UserFills(snapshot)) = receiver.recv().await else {
+            panic!("expected a userFills snapshot");
+        };
+        assert!(snapshot.data.fills.is_empty());
+
+        let exchange_client =
+            ExchangeClient::with_url(None, wallet, exchange.url(), None, None).await?;
+        let response = exchange_client.order(buy(2005.0, 0.5), None).await?;
+        let ExchangeDataStatus::Filled(filled) = &statuses(response)[0] else {
+            panic!("expected a fill");
+        };
+        assert_eq!(
+            (filled.avg_px, filled.total_sz),
+            (2001.into(), 0.5.try_into()?)
+        );
+        let Some(Message::UserFills(fills)) = receiver.recv().await else {
+            panic!("expected a fill");
+        };
+        assert_eq!(fills.data.fills[0].dir, "Open Long");
+        let Some(Message::OrderUpdates(updates)) = receiver.recv().await else {
+            panic!("expected an order update");
+        };
+        assert_eq!(updates.data[0].status, OrderStatus::Filled);
+
+        let user_state = info_client.user_state(user).await?;
+        assert_eq!(user_state.asset_positions[0].position.szi, 0.5.try_into()?);
+        let book = info_client.l2_snapshot("ETH".to_string()).await?;
+        assert_eq!(
+            (book.levels[1][0].px, book.levels[1][0].sz),
+            (2001.into(), 0.5.try_into()?)
+        );
+
+        let response = exchange_client.order(buy(1990.0, 0.5), None).await?;
+        let ExchangeDataStatus::Resting(resting) = &statuses(response)[0] else {
+            panic!("expected a resting order");
+        };
+        assert_eq!(info_client.open_orders(user).await?[0].oid, resting.oid);
+        let cancel = || ClientCancelRequest {
+            asset: "ETH".to_string(),
+            oid: resting.oid,
+        };
+        let response = exchange_client.cancel(cancel(), None).await?;
+        assert!(matches!(statuses(response)[0], ExchangeDataStatus::Success));
+        let response = exchange_client.cancel(cancel(), None).await?;
+        assert_eq!(
+            statuses(response)[0].reject_reason(),
+            Some(OrderRejectReason::UnknownOrder)
+        );
+
+        // Approved agents trade for the account, unknown signers are rejected
+        let (agent_key, _) = exchange_client.approve_agent(None).await?;
+        let agent = PrivateKeySigner::from_bytes(&agent_key).unwrap();
+        let agent_client =
+            ExchangeClient::with_url(None, agent, exchange.url(), None, None).await?;
+        agent_client.order(buy(1990.0, 0.5), None).await?;
+        assert_eq!(info_client.open_orders(user).await?.len(), 1);
+
+        let stranger_client =
+            ExchangeClient::with_url(None, PrivateKeySigner::random(), exchange.url(), None, None)
+                .await?;
+        let response = stranger_client.order(buy(1990.0, 0.5), None).await?;
+        assert_eq!(
+            response.reject_reason(),
+            Some(OrderRejectReason::UnknownSigner)
+        );
+        Ok(())
+    }
+}
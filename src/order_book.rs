@@ -0,0 +1,337 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{mpsc::UnboundedReceiver, watch};
+
+use crate::{prelude::*, BookLevel, Error, L2BookData, L2SnapshotResponse, Level, Message};
+
+/// A single price level with numeric price and size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceLevel {
+    pub px: f64,
+    pub sz: f64,
+    pub n: u64,
+}
+
+impl From<&BookLevel> for PriceLevel {
+    fn from(level: &BookLevel) -> PriceLevel {
+        PriceLevel {
+            px: level.px.to_f64(),
+            sz: level.sz.to_f64(),
+            n: level.n,
+        }
+    }
+}
+
+impl From<&Level> for PriceLevel {
+    fn from(level: &Level) -> PriceLevel {
+        PriceLevel {
+            px: level.px.to_f64(),
+            sz: level.sz.to_f64(),
+            n: level.n,
+        }
+    }
+}
+
+/// Local copy of an L2 book. Bids are sorted best (highest) first, asks best (lowest) first.
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    pub coin: String,
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+    /// Exchange timestamp of the last update in milliseconds.
+    pub time: u64,
+    /// Local time at which the last update was applied.
+    pub received_at: Instant,
+}
+
+impl OrderBook {
+    fn from_levels<L>(coin: &str, time: u64, levels: &[Vec<L>]) -> OrderBook
+    where
+        for<'a> &'a L: Into<PriceLevel>,
+    {
+        let side = |index: usize| -> Vec<PriceLevel> {
+            levels
+                .get(index)
+                .map(|side| side.iter().map(Into::into).collect())
+                .unwrap_or_default()
+        };
+
+        OrderBook {
+            coin: coin.to_string(),
+            bids: side(0),
+            asks: side(1),
+            time,
+            received_at: Instant::now(),
+        }
+    }
+
+    pub fn from_snapshot(snapshot: &L2SnapshotResponse) -> OrderBook {
+        Self::from_levels(&snapshot.coin, snapshot.time, &snapshot.levels)
+    }
+
+    pub fn from_ws(data: &L2BookData) -> OrderBook {
+        Self::from_levels(&data.coin, data.time, &data.levels)
+    }
+
+    /// Replaces the book with a websocket update. Updates older than the current book are
+    /// ignored; returns whether the update was applied.
+    pub fn apply(&mut self, data: &L2BookData) -> bool {
+        if data.coin != self.coin || data.time < self.time {
+            return false;
+        }
+        *self = Self::from_ws(data);
+        true
+    }
+
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids.first().copied()
+    }
+
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks.first().copied()
+    }
+
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.best_bid()?.px + self.best_ask()?.px) / 2.0)
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()?.px - self.best_bid()?.px)
+    }
+
+    /// Top `n` levels of each side as `(bids, asks)`.
+    pub fn depth(&self, n: usize) -> (&[PriceLevel], &[PriceLevel]) {
+        (
+            &self.bids[..n.min(self.bids.len())],
+            &self.asks[..n.min(self.asks.len())],
+        )
+    }
+
+    /// Levels a taker order on the given side would consume: asks for buys, bids for sells.
+    pub fn taker_levels(&self, is_buy: bool) -> &[PriceLevel] {
+        if is_buy {
+            &self.asks
+        } else {
+            &self.bids
+        }
+    }
+
+    /// Total size resting at prices at least as good as `px` for a taker on the given side,
+    /// i.e. asks at or below `px` for buys and bids at or above `px` for sells.
+    pub fn size_to_price(&self, is_buy: bool, px: f64) -> f64 {
+        self.taker_levels(is_buy)
+            .iter()
+            .take_while(|level| {
+                if is_buy {
+                    level.px <= px
+                } else {
+                    level.px >= px
+                }
+            })
+            .map(|level| level.sz)
+            .sum()
+    }
+
+    /// Size-weighted average price a taker order of size `sz` would fill at, or `None` when the
+    /// book is not deep enough.
+    pub fn vwap(&self, is_buy: bool, sz: f64) -> Option<f64> {
+        let mut remaining = sz;
+        let mut notional = 0.0;
+        for level in self.taker_levels(is_buy) {
+            let fill = remaining.min(level.sz);
+            notional += fill * level.px;
+            remaining -= fill;
+            if remaining <= 0.0 {
+                return Some(notional / sz);
+            }
+        }
+        None
+    }
+
+    /// Price of the last level a taker order of size `sz` would reach, or `None` when the book
+    /// is not deep enough.
+    pub fn worst_price(&self, is_buy: bool, sz: f64) -> Option<f64> {
+        let mut remaining = sz;
+        for level in self.taker_levels(is_buy) {
+            remaining -= level.sz;
+            if remaining <= 0.0 {
+                return Some(level.px);
+            }
+        }
+        None
+    }
+
+    /// Time since the last update was applied locally.
+    pub fn age(&self) -> Duration {
+        self.received_at.elapsed()
+    }
+
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.age() > max_age
+    }
+}
+
+/// Cheap, cloneable handle to an [`OrderBook`] kept up to date by an `L2Book` subscription.
+///
+/// Readers get a shared snapshot through [`OrderBookHandle::book`] and can wait for the next
+/// update with [`OrderBookHandle::changed`]. The background task stops once the subscription is
+/// removed or every handle has been dropped.
+#[derive(Debug, Clone)]
+pub struct OrderBookHandle {
+    pub subscription_id: u32,
+    receiver: watch::Receiver<Arc<OrderBook>>,
+}
+
+impl OrderBookHandle {
+    pub(crate) fn spawn(
+        book: OrderBook,
+        subscription_id: u32,
+        mut messages: UnboundedReceiver<Message>,
+    ) -> OrderBookHandle {
+        let (sender, receiver) = watch::channel(Arc::new(book));
+
+        tokio::spawn(async move {
+            while let Some(message) = messages.recv().await {
+                let Message::L2Book(l2_book) = message else {
+                    continue;
+                };
+
+                let mut book = OrderBook::clone(&sender.borrow());
+                if book.apply(&l2_book.data) && sender.send(Arc::new(book)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        OrderBookHandle {
+            subscription_id,
+            receiver,
+        }
+    }
+
+    /// Latest book. Cloning the returned `Arc` is cheap, so it can be held across awaits.
+    pub fn book(&self) -> Arc<OrderBook> {
+        self.receiver.borrow().clone()
+    }
+
+    /// Waits for the next update and returns it. Fails once the book is no longer maintained.
+    pub async fn changed(&mut self) -> Result<Arc<OrderBook>> {
+        self.receiver
+            .changed()
+            .await
+            .map_err(|_| Error::SubscriptionNotFound)?;
+        Ok(self.receiver.borrow_and_update().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+    use crate::{InfoClient, L2Book, MockTransport};
+
+    fn level(px: &str, sz: &str) -> BookLevel {
+        BookLevel {
+            px: px.parse().unwrap(),
+            sz: sz.parse().unwrap(),
+            n: 1,
+        }
+    }
+
+    fn book_data(time: u64, best_bid: &str) -> L2BookData {
+        L2BookData {
+            coin: "ETH".to_string(),
+            time,
+            levels: vec![
+                vec![
+                    level(best_bid, "1.5"),
+                    level("1999", "2"),
+                    level("1998", "4"),
+                ],
+                vec![level("2001", "1"), level("2002", "3"), level("2005", "10")],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_book_queries() {
+        let book = OrderBook::from_ws(&book_data(1, "2000"));
+
+        assert_eq!(book.best_bid().unwrap().px, 2000.0);
+        assert_eq!(book.best_ask().unwrap().px, 2001.0);
+        assert_eq!(book.mid(), Some(2000.5));
+        assert_eq!(book.spread(), Some(1.0));
+
+        let (bids, asks) = book.depth(2);
+        assert_eq!((bids.len(), asks.len()), (2, 2));
+        assert_eq!(book.depth(10).0.len(), 3);
+
+        assert_eq!(book.size_to_price(true, 2002.0), 4.0);
+        assert_eq!(book.size_to_price(true, 2000.0), 0.0);
+        assert_eq!(book.size_to_price(false, 1999.0), 3.5);
+
+        assert_eq!(book.worst_price(true, 1.0), Some(2001.0));
+        assert_eq!(book.worst_price(true, 2.0), Some(2002.0));
+        assert_eq!(book.vwap(true, 2.0), Some(2001.5));
+        assert_eq!(
+            book.vwap(false, 3.5),
+            Some((2000.0 * 1.5 + 1999.0 * 2.0) / 3.5)
+        );
+        assert_eq!(book.vwap(true, 14.5), None);
+        assert_eq!(book.worst_price(false, 7.6), None);
+    }
+
+    #[test]
+    fn test_apply_ignores_older_updates() {
+        let mut book = OrderBook::from_ws(&book_data(5, "2000"));
+
+        assert!(!book.apply(&book_data(4, "1990")));
+        assert_eq!(book.best_bid().unwrap().px, 2000.0);
+
+        assert!(book.apply(&book_data(6, "2000.5")));
+        assert_eq!(book.best_bid().unwrap().px, 2000.5);
+        assert_eq!(book.time, 6);
+    }
+
+    #[tokio::test]
+    async fn test_handle_follows_updates() -> Result<()> {
+        let (sender, receiver) = unbounded_channel();
+        let mut handle =
+            OrderBookHandle::spawn(OrderBook::from_ws(&book_data(1, "2000")), 0, receiver);
+        let reader = handle.clone();
+
+        sender
+            .send(Message::L2Book(L2Book {
+                data: book_data(2, "2000.5"),
+            }))
+            .unwrap();
+        let book = handle.changed().await?;
+        assert_eq!(book.best_bid().unwrap().px, 2000.5);
+        assert_eq!(reader.book().time, 2);
+
+        drop(sender);
+        assert!(handle.changed().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_snapshot_unsubscribes() {
+        // No l2Book response is registered, so the snapshot request fails
+        let mock = MockTransport::new();
+        let mut info_client = InfoClient::with_transport(Arc::new(mock.clone()), None);
+        assert!(info_client.order_book("ETH".to_string()).await.is_err());
+        assert_eq!(
+            mock.ws_sent(),
+            [
+                r#"{"method":"subscribe","subscription":{"coin":"ETH","type":"l2Book"}}"#,
+                r#"{"method":"unsubscribe","subscription":{"coin":"ETH","type":"l2Book"}}"#,
+            ]
+        );
+    }
+}
@@ -0,0 +1,861 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use alloy::primitives::Address;
+use log::{error, warn};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use uuid::Uuid;
+
+use crate::{
+    helpers::uuid_to_hex_string, prelude::*, ClientOrderRequest, Decimal, Error, ExchangeClient,
+    ExchangeDataStatus, ExchangeResponseStatus, InfoClient, Message, OpenOrdersResponse, OrderInfo,
+    OrderRejectReason, OrderStatus, OrderUpdate, Px, RequestOptions, Subscription, Sz, TradeInfo,
+    UserData,
+};
+
+/// Lifecycle of an order tracked by [`OrderManager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    /// Sent to the exchange, no response yet.
+    Pending,
+    Open,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Rejected,
+}
+
+impl OrderState {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OrderState::Filled | OrderState::Canceled | OrderState::Rejected
+        )
+    }
+
+    /// Maps an `orderUpdates` / `orderStatus` status. `filled` is only reported once the whole
+    /// order is done, partial fills show up as `open` with a reduced size. Statuses this SDK
+    /// doesn't know map to `None`, leaving the state as it was.
+    fn from_status(status: OrderStatus) -> Option<OrderState> {
+        match status {
+            OrderStatus::Unknown => None,
+            OrderStatus::Filled => Some(OrderState::Filled),
+            status if status.is_open() => Some(OrderState::Open),
+            status if status.is_rejected() => Some(OrderState::Rejected),
+            _ => Some(OrderState::Canceled),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackedOrder {
+    pub oid: Option<u64>,
+    /// Hex encoded cloid, as sent on the wire.
+    pub cloid: Option<String>,
+    pub coin: String,
+    pub is_buy: bool,
+    pub limit_px: Px,
+    pub orig_sz: Sz,
+    pub filled_sz: Sz,
+    /// Sum of `px * sz` over all fills.
+    pub filled_notional: Decimal,
+    pub state: OrderState,
+    /// Exchange status of the last update.
+    pub status: Option<OrderStatus>,
+    /// Error returned by the exchange when the order was placed.
+    pub error: Option<String>,
+    /// Exchange timestamp of the last update in milliseconds, 0 until the exchange reports one.
+    pub updated_at: u64,
+    tids: HashSet<u64>,
+}
+
+impl TrackedOrder {
+    pub fn remaining_sz(&self) -> Sz {
+        // Both are non-negative and filled_sz is capped at orig_sz, so this cannot overflow
+        self.orig_sz.checked_sub(self.filled_sz).unwrap_or_default()
+    }
+
+    pub fn reject_reason(&self) -> Option<OrderRejectReason> {
+        self.error.as_deref().map(OrderRejectReason::from_message)
+    }
+
+    pub fn avg_fill_px(&self) -> Option<f64> {
+        (!self.filled_sz.is_zero()).then(|| self.filled_notional.to_f64() / self.filled_sz.to_f64())
+    }
+
+    /// Applies the result of an `orderStatus` query.
+    fn apply_order_info(&mut self, info: &OrderInfo) {
+        self.oid = Some(info.order.oid);
+        if let Some(state) = OrderState::from_status(info.status) {
+            self.state = state;
+        }
+        self.status = Some(info.status);
+        self.updated_at = info.status_timestamp;
+        if self.state == OrderState::Filled {
+            self.filled_sz = self.orig_sz;
+        }
+    }
+
+    fn from_open_order(order: &OpenOrdersResponse) -> TrackedOrder {
+        TrackedOrder {
+            oid: Some(order.oid),
+            cloid: order.cloid.clone(),
+            coin: order.coin.clone(),
+            is_buy: order.side.is_buy(),
+            limit_px: order.limit_px,
+            orig_sz: order.sz,
+            filled_sz: Decimal::ZERO,
+            filled_notional: Decimal::ZERO,
+            state: OrderState::Open,
+            status: Some(OrderStatus::Open),
+            error: None,
+            updated_at: order.timestamp,
+            tids: HashSet::new(),
+        }
+    }
+}
+
+/// Emitted whenever a tracked order changes.
+#[derive(Debug, Clone)]
+pub struct OrderEvent {
+    /// State before the change, `None` for orders seen for the first time.
+    pub previous: Option<OrderState>,
+    pub order: TrackedOrder,
+}
+
+#[derive(Debug, Default)]
+struct OrderBookkeeping {
+    /// Keyed by a local id that stays valid while requests are in flight, even when closed
+    /// orders are pruned in the meantime. Ids only grow, so iteration follows insertion order.
+    orders: BTreeMap<u64, TrackedOrder>,
+    next_id: u64,
+    by_oid: HashMap<u64, u64>,
+    by_cloid: HashMap<String, u64>,
+    listeners: Vec<UnboundedSender<OrderEvent>>,
+}
+
+impl OrderBookkeeping {
+    fn insert(&mut self, order: TrackedOrder) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        if let Some(oid) = order.oid {
+            self.by_oid.insert(oid, id);
+        }
+        if let Some(cloid) = &order.cloid {
+            self.by_cloid.insert(cloid.clone(), id);
+        }
+        self.orders.insert(id, order);
+        self.notify(id, None);
+        id
+    }
+
+    fn find(&self, oid: u64, cloid: Option<&str>) -> Option<u64> {
+        self.by_oid
+            .get(&oid)
+            .or_else(|| cloid.and_then(|cloid| self.by_cloid.get(cloid)))
+            .copied()
+    }
+
+    /// Applies `f` to the order with local `id` and notifies listeners if anything changed.
+    /// Orders pruned in the meantime are skipped.
+    fn update(&mut self, id: u64, f: impl FnOnce(&mut TrackedOrder)) {
+        let Some(order) = self.orders.get_mut(&id) else {
+            return;
+        };
+        let previous = (order.state, order.filled_sz, order.oid, order.status);
+        f(order);
+        if let Some(oid) = order.oid {
+            self.by_oid.insert(oid, id);
+        }
+        if previous != (order.state, order.filled_sz, order.oid, order.status) {
+            self.notify(id, Some(previous.0));
+        }
+    }
+
+    fn notify(&mut self, id: u64, previous: Option<OrderState>) {
+        let event = OrderEvent {
+            previous,
+            order: self.orders[&id].clone(),
+        };
+        self.listeners
+            .retain(|listener| listener.send(event.clone()).is_ok());
+    }
+
+    fn apply_order_update(&mut self, update: &OrderUpdate) {
+        let order = &update.order;
+        let state = OrderState::from_status(update.status);
+        let id = match self.find(order.oid, order.cloid.as_deref()) {
+            Some(id) => id,
+            None => self.insert(TrackedOrder {
+                oid: Some(order.oid),
+                cloid: order.cloid.clone(),
+                coin: order.coin.clone(),
+                is_buy: order.side.is_buy(),
+                limit_px: order.limit_px,
+                orig_sz: order.orig_sz,
+                filled_sz: Decimal::ZERO,
+                filled_notional: Decimal::ZERO,
+                state: OrderState::Pending,
+                status: None,
+                error: None,
+                updated_at: update.status_timestamp,
+                tids: HashSet::new(),
+            }),
+        };
+
+        self.update(id, |tracked| {
+            if update.status_timestamp < tracked.updated_at {
+                return;
+            }
+            tracked.oid = Some(order.oid);
+            tracked.updated_at = update.status_timestamp;
+            tracked.status = Some(update.status);
+            tracked.state = match state {
+                // Fills may arrive before or after the order update, trust the larger size
+                Some(OrderState::Open) if order.sz < tracked.orig_sz => {
+                    let filled = tracked.orig_sz.checked_sub(order.sz).unwrap_or_default();
+                    tracked.filled_sz = tracked.filled_sz.max(filled);
+                    OrderState::PartiallyFilled
+                }
+                Some(OrderState::Open) if !tracked.filled_sz.is_zero() => {
+                    OrderState::PartiallyFilled
+                }
+                Some(OrderState::Filled) => {
+                    tracked.filled_sz = tracked.orig_sz;
+                    OrderState::Filled
+                }
+                Some(state) => state,
+                None => {
+                    warn!("Unknown status for order {}, keeping its state", order.oid);
+                    tracked.state
+                }
+            };
+        });
+    }
+
+    fn apply_fill(&mut self, fill: &TradeInfo) {
+        let Some(id) = self.find(fill.oid, fill.cloid.as_deref()) else {
+            return;
+        };
+        self.update(id, |tracked| {
+            if tracked.tids.contains(&fill.tid) {
+                return;
+            }
+            let notional = fill
+                .px
+                .checked_mul(fill.sz)
+                .and_then(|notional| tracked.filled_notional.checked_add(notional));
+            let (Ok(notional), Ok(filled_sz)) = (notional, tracked.filled_sz.checked_add(fill.sz))
+            else {
+                warn!(
+                    "Fill {} of order {} overflows, ignoring it",
+                    fill.tid, fill.oid
+                );
+                return;
+            };
+            tracked.tids.insert(fill.tid);
+            tracked.oid = Some(fill.oid);
+            tracked.filled_notional = notional;
+            tracked.filled_sz = filled_sz.min(tracked.orig_sz);
+            tracked.updated_at = tracked.updated_at.max(fill.time);
+            if !tracked.state.is_terminal() {
+                tracked.state = if tracked.remaining_sz().is_zero() {
+                    OrderState::Filled
+                } else {
+                    OrderState::PartiallyFilled
+                };
+            }
+        });
+    }
+
+    fn apply_cancel(&mut self, oid: u64) {
+        if let Some(id) = self.by_oid.get(&oid).copied() {
+            self.update(id, |tracked| {
+                if !tracked.state.is_terminal() {
+                    tracked.state = OrderState::Canceled;
+                    tracked.status = Some(OrderStatus::Canceled);
+                }
+            });
+        }
+    }
+}
+
+/// Tracks the state of orders placed through [`OrderManager::bulk_order`] and follows their
+/// lifecycle from `orderUpdates`, `userFills` and non-user cancel websocket events.
+///
+/// The manager is a cheap handle: clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct OrderManager {
+    inner: Arc<Mutex<OrderBookkeeping>>,
+}
+
+impl OrderManager {
+    pub fn new() -> OrderManager {
+        OrderManager::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, OrderBookkeeping> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Stream of every change to a tracked order.
+    pub fn events(&self) -> UnboundedReceiver<OrderEvent> {
+        let (sender, receiver) = unbounded_channel();
+        self.lock().listeners.push(sender);
+        receiver
+    }
+
+    pub fn order_by_oid(&self, oid: u64) -> Option<TrackedOrder> {
+        let inner = self.lock();
+        inner
+            .by_oid
+            .get(&oid)
+            .and_then(|id| inner.orders.get(id))
+            .cloned()
+    }
+
+    /// Looks up an order by its hex encoded cloid.
+    pub fn order_by_cloid(&self, cloid: &str) -> Option<TrackedOrder> {
+        let inner = self.lock();
+        inner
+            .by_cloid
+            .get(cloid)
+            .and_then(|id| inner.orders.get(id))
+            .cloned()
+    }
+
+    pub fn orders(&self) -> Vec<TrackedOrder> {
+        self.lock().orders.values().cloned().collect()
+    }
+
+    /// Orders that are pending, open or partially filled.
+    pub fn open_orders(&self) -> Vec<TrackedOrder> {
+        self.lock()
+            .orders
+            .values()
+            .filter(|order| !order.state.is_terminal())
+            .cloned()
+            .collect()
+    }
+
+    /// Forgets filled, canceled and rejected orders.
+    pub fn prune_closed(&self) {
+        let mut inner = self.lock();
+        let inner = &mut *inner;
+        inner.orders.retain(|_, order| !order.state.is_terminal());
+        let orders = &inner.orders;
+        inner.by_oid.retain(|_, id| orders.contains_key(id));
+        inner.by_cloid.retain(|_, id| orders.contains_key(id));
+    }
+
+    /// Places orders through `exchange_client` and tracks them from the moment they are sent.
+    pub async fn bulk_order(
+        &self,
+        exchange_client: &ExchangeClient,
+        orders: Vec<ClientOrderRequest>,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<ExchangeResponseStatus> {
+        let ids: Vec<u64> = {
+            let mut inner = self.lock();
+            orders
+                .iter()
+                .map(|order| {
+                    inner.insert(TrackedOrder {
+                        oid: None,
+                        cloid: order.cloid.map(uuid_to_hex_string),
+                        coin: order.asset.clone(),
+                        is_buy: order.is_buy,
+                        limit_px: order.limit_px,
+                        orig_sz: order.sz,
+                        filled_sz: Decimal::ZERO,
+                        filled_notional: Decimal::ZERO,
+                        state: OrderState::Pending,
+                        status: None,
+                        error: None,
+                        updated_at: 0,
+                        tids: HashSet::new(),
+                    })
+                })
+                .collect()
+        };
+
+        let response = exchange_client.bulk_order(orders, options).await;
+        self.apply_response(&ids, &response);
+        response
+    }
+
+    pub async fn order(
+        &self,
+        exchange_client: &ExchangeClient,
+        order: ClientOrderRequest,
+        options: Option<RequestOptions<'_>>,
+    ) -> Result<ExchangeResponseStatus> {
+        self.bulk_order(exchange_client, vec![order], options).await
+    }
+
+    fn apply_response(&self, ids: &[u64], response: &Result<ExchangeResponseStatus>) {
+        let statuses = match response {
+            Ok(ExchangeResponseStatus::Ok(response)) => response
+                .data
+                .as_ref()
+                .map(|data| data.statuses.clone())
+                .unwrap_or_default(),
+            Ok(ExchangeResponseStatus::Err(e)) => {
+                vec![ExchangeDataStatus::Error(e.clone()); ids.len()]
+            }
+            // The orders may still have reached the exchange, leave them pending for websocket
+            // events or `reconcile` to settle
+            Err(_) => return,
+        };
+
+        let mut inner = self.lock();
+        for (&id, status) in ids.iter().zip(statuses) {
+            inner.update(id, |tracked| match status {
+                ExchangeDataStatus::Resting(resting) => {
+                    tracked.oid = Some(resting.oid);
+                    if tracked.state == OrderState::Pending {
+                        tracked.state = OrderState::Open;
+                    }
+                }
+                ExchangeDataStatus::Filled(filled) => {
+                    tracked.oid = Some(filled.oid);
+                    tracked.state = OrderState::Filled;
+                    tracked.filled_sz = filled.total_sz;
+                    match filled.total_sz.checked_mul(filled.avg_px) {
+                        Ok(notional) => tracked.filled_notional = notional,
+                        Err(_) => warn!("Fill notional of order {} overflows", filled.oid),
+                    }
+                }
+                ExchangeDataStatus::Error(e) => {
+                    tracked.state = OrderState::Rejected;
+                    tracked.error = Some(e);
+                }
+                // Fills are left to the fill stream, which dedups them by tid
+                ExchangeDataStatus::Resolved(info) => {
+                    tracked.oid = Some(info.order.oid);
+                    if let Some(state) = OrderState::from_status(info.status) {
+                        tracked.state = state;
+                    }
+                    tracked.status = Some(info.status);
+                }
+                ExchangeDataStatus::WaitingForFill | ExchangeDataStatus::WaitingForTrigger => {
+                    tracked.state = OrderState::Open;
+                }
+                ExchangeDataStatus::Success => {}
+            });
+        }
+    }
+
+    /// Applies a websocket message. Messages unrelated to orders are ignored.
+    pub fn handle_message(&self, message: &Message) {
+        let mut inner = self.lock();
+        match message {
+            Message::OrderUpdates(updates) => {
+                for update in &updates.data {
+                    inner.apply_order_update(update);
+                }
+            }
+            Message::UserFills(fills) => {
+                for fill in &fills.data.fills {
+                    inner.apply_fill(fill);
+                }
+            }
+            Message::User(user) => match &user.data {
+                UserData::Fills(fills) => {
+                    for fill in fills {
+                        inner.apply_fill(fill);
+                    }
+                }
+                UserData::NonUserCancel(cancels) => {
+                    for cancel in cancels {
+                        inner.apply_cancel(cancel.oid);
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Brings tracked state in line with the exchange: adopts open orders the manager doesn't
+    /// know about, refreshes remaining sizes, looks up the final status of orders that are no
+    /// longer open and looks up pending orders by cloid.
+    pub async fn reconcile(&self, info_client: &InfoClient, user: Address) -> Result<()> {
+        let open_orders = info_client.open_orders(user).await?;
+        let open_oids: HashSet<u64> = open_orders.iter().map(|order| order.oid).collect();
+
+        let (missing, unconfirmed): (Vec<u64>, Vec<String>) = {
+            let mut inner = self.lock();
+            for open_order in &open_orders {
+                match inner.find(open_order.oid, open_order.cloid.as_deref()) {
+                    Some(id) => inner.update(id, |tracked| {
+                        tracked.oid = Some(open_order.oid);
+                        let filled = tracked
+                            .orig_sz
+                            .checked_sub(open_order.sz)
+                            .unwrap_or_default();
+                        if filled > tracked.filled_sz {
+                            tracked.filled_sz = filled;
+                        }
+                        tracked.state = if tracked.filled_sz.is_zero() {
+                            OrderState::Open
+                        } else {
+                            OrderState::PartiallyFilled
+                        };
+                    }),
+                    None => {
+                        inner.insert(TrackedOrder::from_open_order(open_order));
+                    }
+                }
+            }
+
+            let missing = inner
+                .orders
+                .values()
+                .filter(|order| !order.state.is_terminal())
+                .filter_map(|order| order.oid)
+                .filter(|oid| !open_oids.contains(oid))
+                .collect();
+            // Orders whose placement response never arrived
+            let unconfirmed = inner
+                .orders
+                .values()
+                .filter(|order| order.state == OrderState::Pending && order.oid.is_none())
+                .filter_map(|order| order.cloid.clone())
+                .collect();
+            (missing, unconfirmed)
+        };
+
+        for oid in missing {
+            let status = info_client.query_order_by_oid(user, oid).await?;
+            let Some(order_info) = status.order else {
+                continue;
+            };
+            let mut inner = self.lock();
+            if let Some(id) = inner.by_oid.get(&oid).copied() {
+                inner.update(id, |tracked| tracked.apply_order_info(&order_info));
+            }
+        }
+
+        for cloid in unconfirmed {
+            let Ok(uuid) = Uuid::parse_str(cloid.trim_start_matches("0x")) else {
+                continue;
+            };
+            let status = info_client.query_order_by_cloid(user, uuid).await?;
+            let Some(order_info) = status.order else {
+                continue;
+            };
+            let mut inner = self.lock();
+            if let Some(id) = inner.by_cloid.get(&cloid).copied() {
+                inner.update(id, |tracked| tracked.apply_order_info(&order_info));
+            }
+        }
+        Ok(())
+    }
+
+    /// Subscribes to `orderUpdates`, `userFills` and user events for `user` on `info_client`
+    /// and keeps the manager up to date in the background, reconciling after reconnects.
+    ///
+    /// Non-user cancels only arrive through user events, which can be subscribed to once per
+    /// connection. If the client already has a user events subscription, forward those messages
+    /// through [`OrderManager::handle_message`] instead.
+    pub async fn start(&self, info_client: &mut InfoClient, user: Address) -> Result<()> {
+        let (sender, mut receiver) = unbounded_channel();
+        let mut subscription_ids = Vec::new();
+        for subscription in [
+            Subscription::OrderUpdates { user },
+            Subscription::UserFills { user },
+            Subscription::UserEvents { user },
+        ] {
+            match info_client.subscribe(subscription, sender.clone()).await {
+                Ok(subscription_id) => subscription_ids.push(subscription_id),
+                Err(Error::UserEvents) => {
+                    warn!("User events already subscribed, non-user cancels must be forwarded")
+                }
+                Err(err) => {
+                    info_client.unsubscribe_all(&subscription_ids).await;
+                    return Err(err);
+                }
+            }
+        }
+
+        let manager = self.clone();
+        let info_client = InfoClient::with_http_client(info_client.http_client.clone());
+        tokio::spawn(async move {
+            let mut disconnected = false;
+            while let Some(message) = receiver.recv().await {
+                if let Message::NoData = message {
+                    disconnected = true;
+                    continue;
+                }
+                if disconnected {
+                    disconnected = false;
+                    if let Err(err) = manager.reconcile(&info_client, user).await {
+                        error!("Failed to reconcile orders after reconnect: {err}");
+                    }
+                }
+                manager.handle_message(&message);
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        BaseUrl, BasicOrder, MockTransport, NonUserCancel, OrderUpdates, Side, User, UserFills,
+        UserFillsData,
+    };
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    fn order_update(oid: u64, status: OrderStatus, sz: &str, time: u64) -> Message {
+        Message::OrderUpdates(OrderUpdates {
+            data: vec![OrderUpdate {
+                order: BasicOrder {
+                    coin: "ETH".to_string(),
+                    side: Side::Buy,
+                    limit_px: dec("1800"),
+                    sz: dec(sz),
+                    oid,
+                    timestamp: 1,
+                    orig_sz: dec("1"),
+                    cloid: Some("0x01".to_string()),
+                },
+                status,
+                status_timestamp: time,
+            }],
+        })
+    }
+
+    fn fill(oid: u64, tid: u64, px: &str, sz: &str) -> TradeInfo {
+        TradeInfo {
+            coin: "ETH".to_string(),
+            side: Side::Buy,
+            px: dec(px),
+            sz: dec(sz),
+            time: 10,
+            hash: String::new(),
+            start_position: Decimal::ZERO,
+            dir: "Open Long".to_string(),
+            closed_pnl: "0".to_string(),
+            oid,
+            cloid: None,
+            crossed: false,
+            fee: "0".to_string(),
+            fee_token: "USDC".to_string(),
+            tid,
+        }
+    }
+
+    fn user_fills(fills: Vec<TradeInfo>) -> Message {
+        Message::UserFills(UserFills {
+            data: UserFillsData {
+                is_snapshot: None,
+                user: Address::ZERO,
+                fills,
+            },
+        })
+    }
+
+    fn track_pending(manager: &OrderManager, cloid: &str) -> u64 {
+        manager.lock().insert(TrackedOrder {
+            oid: None,
+            cloid: Some(cloid.to_string()),
+            coin: "ETH".to_string(),
+            is_buy: true,
+            limit_px: dec("1800"),
+            orig_sz: dec("1"),
+            filled_sz: Decimal::ZERO,
+            filled_notional: Decimal::ZERO,
+            state: OrderState::Pending,
+            status: None,
+            error: None,
+            updated_at: 0,
+            tids: HashSet::new(),
+        })
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let manager = OrderManager::new();
+        let mut events = manager.events();
+        track_pending(&manager, "0x01");
+
+        // Matched by cloid until the oid is known
+        manager.handle_message(&order_update(7, OrderStatus::Open, "1", 1));
+        let order = manager.order_by_oid(7).unwrap();
+        assert_eq!(order.state, OrderState::Open);
+        assert_eq!(manager.order_by_cloid("0x01").unwrap().oid, Some(7));
+
+        manager.handle_message(&user_fills(vec![fill(7, 100, "1800", "0.25")]));
+        // Same fill through user events is not counted twice
+        manager.handle_message(&Message::User(User {
+            data: UserData::Fills(vec![fill(7, 100, "1800", "0.25")]),
+        }));
+        let order = manager.order_by_oid(7).unwrap();
+        assert_eq!(order.state, OrderState::PartiallyFilled);
+        assert_eq!(order.filled_sz, dec("0.25"));
+
+        manager.handle_message(&user_fills(vec![fill(7, 101, "1799", "0.75")]));
+        let order = manager.order_by_oid(7).unwrap();
+        assert_eq!(order.state, OrderState::Filled);
+        assert_eq!(order.avg_fill_px(), Some(1799.25));
+        assert!(manager.open_orders().is_empty());
+
+        let states: Vec<_> = std::iter::from_fn(|| events.try_recv().ok())
+            .map(|event| event.order.state)
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                OrderState::Pending,
+                OrderState::Open,
+                OrderState::PartiallyFilled,
+                OrderState::Filled
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cancels_and_stale_updates() {
+        let manager = OrderManager::new();
+        manager.handle_message(&order_update(8, OrderStatus::Open, "1", 5));
+        manager.handle_message(&order_update(8, OrderStatus::Open, "0.4", 6));
+        assert_eq!(
+            manager.order_by_oid(8).unwrap().state,
+            OrderState::PartiallyFilled
+        );
+
+        // Older updates are ignored
+        manager.handle_message(&order_update(8, OrderStatus::Open, "1", 4));
+        assert_eq!(manager.order_by_oid(8).unwrap().filled_sz, dec("0.6"));
+
+        manager.handle_message(&Message::User(User {
+            data: UserData::NonUserCancel(vec![NonUserCancel {
+                coin: "ETH".to_string(),
+                oid: 8,
+            }]),
+        }));
+        let order = manager.order_by_oid(8).unwrap();
+        assert_eq!(order.state, OrderState::Canceled);
+        assert_eq!(order.status, Some(OrderStatus::Canceled));
+
+        manager.handle_message(&order_update(9, OrderStatus::MarginCanceled, "1", 7));
+        assert_eq!(manager.order_by_oid(9).unwrap().state, OrderState::Canceled);
+
+        manager.prune_closed();
+        assert!(manager.orders().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_status_keeps_state() {
+        let manager = OrderManager::new();
+        manager.handle_message(&order_update(8, OrderStatus::Open, "0.4", 5));
+        manager.handle_message(&order_update(8, OrderStatus::Unknown, "0.4", 6));
+        let order = manager.order_by_oid(8).unwrap();
+        assert_eq!(order.state, OrderState::PartiallyFilled);
+        assert_eq!(order.status, Some(OrderStatus::Unknown));
+        assert_eq!(manager.open_orders().len(), 1);
+
+        manager.handle_message(&order_update(8, OrderStatus::Filled, "0", 7));
+        assert_eq!(manager.order_by_oid(8).unwrap().state, OrderState::Filled);
+    }
+
+    #[test]
+    fn test_apply_response() {
+        let manager = OrderManager::new();
+        let ids = [
+            track_pending(&manager, "0x01"),
+            track_pending(&manager, "0x02"),
+            track_pending(&manager, "0x03"),
+        ];
+        let response: ExchangeResponseStatus = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "order", "data": {"statuses": [
+                {"resting": {"oid": 1}},
+                {"filled": {"totalSz": "1", "avgPx": "1800.5", "oid": 2}},
+                {"error": "Order must have minimum value of $10."}
+            ]}}}"#,
+        )
+        .unwrap();
+        manager.apply_response(&ids, &Ok(response));
+
+        assert_eq!(manager.order_by_oid(1).unwrap().state, OrderState::Open);
+        let filled = manager.order_by_oid(2).unwrap();
+        assert_eq!(filled.state, OrderState::Filled);
+        assert_eq!(filled.avg_fill_px(), Some(1800.5));
+        let rejected = manager.order_by_cloid("0x03").unwrap();
+        assert_eq!(rejected.state, OrderState::Rejected);
+        assert_eq!(
+            rejected.error.as_deref(),
+            Some("Order must have minimum value of $10.")
+        );
+        assert!(rejected.oid.is_none());
+    }
+
+    #[test]
+    fn test_prune_while_pending() {
+        let manager = OrderManager::new();
+        manager.handle_message(&order_update(5, OrderStatus::Filled, "0", 1));
+        let ids = [
+            track_pending(&manager, "0x02"),
+            track_pending(&manager, "0x03"),
+        ];
+
+        // Closed orders placed before the request are pruned while it is in flight
+        manager.prune_closed();
+        assert!(manager.order_by_oid(5).is_none());
+
+        let response: ExchangeResponseStatus = serde_json::from_str(
+            r#"{"status": "ok", "response": {"type": "order", "data": {"statuses": [
+                {"resting": {"oid": 6}},
+                {"resting": {"oid": 7}}
+            ]}}}"#,
+        )
+        .unwrap();
+        manager.apply_response(&ids, &Ok(response));
+
+        assert_eq!(manager.order_by_cloid("0x02").unwrap().oid, Some(6));
+        assert_eq!(manager.order_by_cloid("0x03").unwrap().oid, Some(7));
+        assert_eq!(manager.open_orders().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_transport_error_keeps_pending() -> Result<()> {
+        let manager = OrderManager::new();
+        let cloid = "0x1e60610f0b3d420597c88c1fed2ad5ee";
+        let id = track_pending(&manager, cloid);
+        manager.apply_response(&[id], &Err(Error::GenericRequest("timed out".to_string())));
+        let order = manager.order_by_cloid(cloid).unwrap();
+        assert_eq!(order.state, OrderState::Pending);
+        assert!(order.error.is_none());
+
+        // The order did reach the exchange
+        let mock = MockTransport::new();
+        mock.respond("/info", "openOrders", "[]");
+        mock.respond(
+            "/info",
+            "orderStatus",
+            r#"{"status": "order", "order": {"order": {
+                "coin": "ETH", "side": "B", "limitPx": "1800.0", "sz": "0", "oid": 42,
+                "timestamp": 1, "triggerCondition": "N/A", "isTrigger": false,
+                "triggerPx": "0.0", "isPositionTpsl": false, "reduceOnly": false,
+                "orderType": "Limit", "origSz": "1", "tif": "Gtc",
+                "cloid": "0x1e60610f0b3d420597c88c1fed2ad5ee"
+            }, "status": "filled", "statusTimestamp": 2}}"#,
+        );
+        let info_client = InfoClient::with_transport(Arc::new(mock), Some(BaseUrl::Testnet));
+        manager.reconcile(&info_client, Address::ZERO).await?;
+
+        let order = manager.order_by_oid(42).unwrap();
+        assert_eq!(order.state, OrderState::Filled);
+        assert_eq!(order.filled_sz, dec("1"));
+        Ok(())
+    }
+}
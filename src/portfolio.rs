@@ -0,0 +1,612 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+use alloy::primitives::Address;
+
+use crate::{
+    prelude::*, Decimal, Error, InfoClient, LedgerUpdate, Message, Px, SpotMeta, Sz, TradeInfo,
+    UserData, UserFunding, UserStateResponse, UserTokenBalanceResponse,
+};
+
+/// Number of fill and funding keys remembered to skip events delivered twice.
+const SEEN_CAPACITY: usize = 10_000;
+
+fn amount(value: &str) -> Result<f64> {
+    value.parse().map_err(|_| Error::FloatStringParse)
+}
+
+/// Spot pairs are named `@{index}` or `BASE/QUOTE`.
+fn is_spot(coin: &str) -> bool {
+    coin.starts_with('@') || coin.contains('/')
+}
+
+/// Keys of the latest applied events, so an event received from two subscriptions is applied
+/// once.
+#[derive(Debug, Clone, Default)]
+struct Seen<K> {
+    keys: HashSet<K>,
+    order: VecDeque<K>,
+}
+
+impl<K: Eq + Hash + Clone> Seen<K> {
+    /// Records `key`, returns false if it was already seen.
+    fn insert(&mut self, key: K) -> bool {
+        if !self.keys.insert(key.clone()) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > SEEN_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Position and PnL of a single asset. Money amounts are in USDC, except fees paid in another
+/// token, which are added at face value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Position {
+    pub coin: String,
+    /// Signed size, positive for longs.
+    pub szi: Sz,
+    /// Average entry price of the open position, `None` when flat.
+    pub entry_px: Option<f64>,
+    pub realized_pnl: f64,
+    pub fees_paid: f64,
+    /// Funding paid, negative when funding was received.
+    pub funding_paid: f64,
+    /// Last mid used for mark-to-market.
+    pub mark_px: Option<f64>,
+}
+
+impl Position {
+    pub fn is_flat(&self) -> bool {
+        self.szi.is_zero()
+    }
+
+    pub fn unrealized_pnl(&self) -> f64 {
+        match (self.entry_px, self.mark_px) {
+            (Some(entry_px), Some(mark_px)) => self.szi.to_f64() * (mark_px - entry_px),
+            _ => 0.0,
+        }
+    }
+
+    /// Realized plus unrealized PnL, net of fees and funding.
+    pub fn net_pnl(&self) -> f64 {
+        self.realized_pnl + self.unrealized_pnl() - self.fees_paid - self.funding_paid
+    }
+
+    fn apply_fill(&mut self, is_buy: bool, px: f64, sz: Sz) -> Result<()> {
+        let fill = if is_buy { sz } else { -sz };
+        let current = self.szi;
+        let entry_px = self.entry_px.unwrap_or(px);
+        self.szi = current.checked_add(fill)?;
+
+        if current.is_zero() || current.is_sign_negative() == fill.is_sign_negative() {
+            let (current, fill) = (current.abs().to_f64(), fill.abs().to_f64());
+            self.entry_px = Some((entry_px * current + px * fill) / (current + fill));
+            return Ok(());
+        }
+
+        let closed = current.abs().min(fill.abs()).to_f64();
+        let direction = if current.is_sign_negative() {
+            -1.0
+        } else {
+            1.0
+        };
+        self.realized_pnl += closed * (px - entry_px) * direction;
+        self.entry_px = if self.szi.is_zero() {
+            None
+        } else if self.szi.is_sign_negative() == current.is_sign_negative() {
+            Some(entry_px)
+        } else {
+            // Flipped through zero, the remainder was opened at the fill price
+            Some(px)
+        };
+        Ok(())
+    }
+}
+
+/// Difference between the local view of a position and `clearinghouseState`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionDrift {
+    pub coin: String,
+    pub local_szi: Sz,
+    pub exchange_szi: Sz,
+    pub local_entry_px: Option<f64>,
+    pub exchange_entry_px: Option<f64>,
+}
+
+/// Per-asset positions and PnL maintained from `userFills`, `userFundings` and
+/// `userNonFundingLedgerUpdates` messages, seeded from `clearinghouseState` and
+/// `spotClearinghouseState`.
+///
+/// Snapshot messages sent right after subscribing are skipped, since the seed already contains
+/// their effect. Fills and fundings are deduplicated, so `userFills`/`userFundings` and
+/// `userEvents` can be forwarded together. Spot fills are tracked as positions under the pair name
+/// (e.g. `@107`) and move the balances of the pair's tokens.
+#[derive(Debug, Clone, Default)]
+pub struct Portfolio {
+    pub user: Address,
+    pub positions: HashMap<String, Position>,
+    /// Spot token balances keyed by token name.
+    pub spot_balances: HashMap<String, Decimal>,
+    /// Net USDC moved into the perp account through deposits, withdrawals and transfers.
+    pub net_transfers: f64,
+    /// Base and quote token names keyed by spot pair name.
+    spot_pairs: HashMap<String, (String, String)>,
+    seen_tids: Seen<u64>,
+    seen_fundings: Seen<(u64, String)>,
+}
+
+impl Portfolio {
+    pub fn new(
+        user: Address,
+        state: &UserStateResponse,
+        balances: &UserTokenBalanceResponse,
+        spot_meta: &SpotMeta,
+    ) -> Result<Portfolio> {
+        let token_names: HashMap<usize, &str> = spot_meta
+            .tokens
+            .iter()
+            .map(|token| (token.index, token.name.as_str()))
+            .collect();
+        let spot_pairs = spot_meta
+            .universe
+            .iter()
+            .filter_map(|pair| {
+                let base = token_names.get(&pair.tokens[0])?;
+                let quote = token_names.get(&pair.tokens[1])?;
+                Some((pair.name.clone(), (base.to_string(), quote.to_string())))
+            })
+            .collect();
+        let mut portfolio = Portfolio {
+            user,
+            spot_pairs,
+            ..Default::default()
+        };
+        portfolio.sync(state);
+        for balance in &balances.balances {
+            let total = balance.total.parse().map_err(|_| Error::FloatStringParse)?;
+            portfolio.spot_balances.insert(balance.coin.clone(), total);
+        }
+        Ok(portfolio)
+    }
+
+    /// Seeds a portfolio from the exchange's current user and spot state.
+    pub async fn load(info_client: &InfoClient, user: Address) -> Result<Portfolio> {
+        let state = info_client.user_state(user).await?;
+        let balances = info_client.user_token_balances(user).await?;
+        let spot_meta = info_client.spot_meta().await?;
+        Portfolio::new(user, &state, &balances, &spot_meta)
+    }
+
+    pub fn position(&self, coin: &str) -> Option<&Position> {
+        self.positions.get(coin)
+    }
+
+    pub fn realized_pnl(&self) -> f64 {
+        self.positions.values().map(|p| p.realized_pnl).sum()
+    }
+
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.positions.values().map(Position::unrealized_pnl).sum()
+    }
+
+    pub fn fees_paid(&self) -> f64 {
+        self.positions.values().map(|p| p.fees_paid).sum()
+    }
+
+    pub fn funding_paid(&self) -> f64 {
+        self.positions.values().map(|p| p.funding_paid).sum()
+    }
+
+    fn position_mut(&mut self, coin: &str) -> &mut Position {
+        self.positions
+            .entry(coin.to_string())
+            .or_insert_with(|| Position {
+                coin: coin.to_string(),
+                ..Default::default()
+            })
+    }
+
+    /// Resets sizes and entry prices to the exchange's, keeping accumulated PnL, fees and
+    /// funding.
+    pub fn sync(&mut self, state: &UserStateResponse) {
+        let exchange: HashMap<&str, _> = state
+            .asset_positions
+            .iter()
+            .map(|asset| (asset.position.coin.as_str(), &asset.position))
+            .collect();
+
+        for (coin, position) in self.positions.iter_mut() {
+            if !exchange.contains_key(coin.as_str()) && !is_spot(coin) {
+                position.szi = Decimal::ZERO;
+                position.entry_px = None;
+            }
+        }
+        for (coin, data) in exchange {
+            let position = self.position_mut(coin);
+            position.szi = data.szi;
+            position.entry_px = data.entry_px.map(|px| px.to_f64());
+        }
+    }
+
+    /// Perp positions whose size differs from `state`, or whose entry price differs by more
+    /// than `entry_px_tolerance` (relative).
+    pub fn drift(&self, state: &UserStateResponse, entry_px_tolerance: f64) -> Vec<PositionDrift> {
+        let exchange: HashMap<&str, _> = state
+            .asset_positions
+            .iter()
+            .map(|asset| (asset.position.coin.as_str(), &asset.position))
+            .collect();
+
+        let mut coins: Vec<&str> = self
+            .positions
+            .iter()
+            .filter(|(coin, position)| !position.is_flat() && !is_spot(coin))
+            .map(|(coin, _)| coin.as_str())
+            .chain(exchange.keys().copied())
+            .collect();
+        coins.sort_unstable();
+        coins.dedup();
+
+        coins
+            .into_iter()
+            .filter_map(|coin| {
+                let local = self.positions.get(coin);
+                let local_szi = local.map(|p| p.szi).unwrap_or_default();
+                let local_entry_px = local.and_then(|p| p.entry_px);
+                let exchange_szi = exchange.get(coin).map(|p| p.szi).unwrap_or_default();
+                let exchange_entry_px = exchange
+                    .get(coin)
+                    .and_then(|p| p.entry_px)
+                    .map(|px| px.to_f64());
+
+                let entry_drifted = match (local_entry_px, exchange_entry_px) {
+                    (Some(local), Some(exchange)) => {
+                        (local - exchange).abs() > exchange.abs() * entry_px_tolerance
+                    }
+                    (local, exchange) => local.is_some() != exchange.is_some(),
+                };
+                (local_szi != exchange_szi || entry_drifted).then(|| PositionDrift {
+                    coin: coin.to_string(),
+                    local_szi,
+                    exchange_szi,
+                    local_entry_px,
+                    exchange_entry_px,
+                })
+            })
+            .collect()
+    }
+
+    /// Fetches `clearinghouseState` and reports drift against it.
+    pub async fn check_drift(
+        &self,
+        info_client: &InfoClient,
+        entry_px_tolerance: f64,
+    ) -> Result<Vec<PositionDrift>> {
+        let state = info_client.user_state(self.user).await?;
+        Ok(self.drift(&state, entry_px_tolerance))
+    }
+
+    /// Marks positions to the given mids.
+    pub fn update_mids(&mut self, mids: &HashMap<String, Px>) {
+        for (coin, position) in self.positions.iter_mut() {
+            if let Some(mid) = mids.get(coin) {
+                position.mark_px = Some(mid.to_f64());
+            }
+        }
+    }
+
+    pub fn apply_fill(&mut self, fill: &TradeInfo) -> Result<()> {
+        if !self.seen_tids.insert(fill.tid) {
+            return Ok(());
+        }
+        let fee = amount(&fill.fee)?;
+        let position = self.position_mut(&fill.coin);
+        position.apply_fill(fill.side.is_buy(), fill.px.to_f64(), fill.sz)?;
+        position.fees_paid += fee;
+
+        if let Some((base, quote)) = self.spot_pairs.get(&fill.coin).cloned() {
+            let quote_sz = fill.px.checked_mul(fill.sz)?;
+            let (base_sz, quote_sz) = if fill.side.is_buy() {
+                (fill.sz, -quote_sz)
+            } else {
+                (-fill.sz, quote_sz)
+            };
+            let fee: Decimal = fill.fee.parse().map_err(|_| Error::FloatStringParse)?;
+            self.add_spot_balance(&base, base_sz)?;
+            self.add_spot_balance(&quote, quote_sz)?;
+            self.add_spot_balance(&fill.fee_token, -fee)?;
+        }
+        Ok(())
+    }
+
+    fn add_spot_balance(&mut self, token: &str, amount: Decimal) -> Result<()> {
+        let balance = self.spot_balances.entry(token.to_string()).or_default();
+        *balance = balance.checked_add(amount)?;
+        Ok(())
+    }
+
+    pub fn apply_funding(&mut self, funding: &UserFunding) -> Result<()> {
+        if !self
+            .seen_fundings
+            .insert((funding.time, funding.coin.clone()))
+        {
+            return Ok(());
+        }
+        let usdc = amount(&funding.usdc)?;
+        self.position_mut(&funding.coin).funding_paid -= usdc;
+        Ok(())
+    }
+
+    pub fn apply_ledger_update(&mut self, update: &LedgerUpdate) -> Result<()> {
+        let user = self.user;
+        let signed = |usdc: &str, destination: Address| -> Result<f64> {
+            let usdc = amount(usdc)?;
+            Ok(if destination == user { usdc } else { -usdc })
+        };
+
+        match update {
+            LedgerUpdate::Deposit(deposit) => self.net_transfers += amount(&deposit.usdc)?,
+            LedgerUpdate::Withdraw(withdraw) => {
+                self.net_transfers -= amount(&withdraw.usdc)? + amount(&withdraw.fee)?
+            }
+            LedgerUpdate::InternalTransfer(transfer) => {
+                self.net_transfers += signed(&transfer.usdc, transfer.destination)?
+            }
+            LedgerUpdate::SubAccountTransfer(transfer) => {
+                self.net_transfers += signed(&transfer.usdc, transfer.destination)?
+            }
+            LedgerUpdate::AccountClassTransfer(transfer) => {
+                let usdc = amount(&transfer.usdc)?;
+                let (perp, spot) = if transfer.to_perp {
+                    (usdc, -usdc)
+                } else {
+                    (-usdc, usdc)
+                };
+                self.net_transfers += perp;
+                self.add_spot_balance("USDC", Decimal::try_from(spot)?)?;
+            }
+            LedgerUpdate::SpotTransfer(transfer) => {
+                let amount: Decimal = transfer
+                    .amount
+                    .parse()
+                    .map_err(|_| Error::FloatStringParse)?;
+                let amount = if transfer.destination == user {
+                    amount
+                } else {
+                    -amount
+                };
+                self.add_spot_balance(&transfer.token, amount)?;
+            }
+            LedgerUpdate::LedgerLiquidation(liquidation) => {
+                for liquidated in &liquidation.liquidated_positions {
+                    let position = self.position_mut(&liquidated.coin);
+                    position.szi = Decimal::ZERO;
+                    position.entry_px = None;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Applies a websocket message. Messages that don't affect the portfolio are ignored.
+    pub fn handle_message(&mut self, message: &Message) -> Result<()> {
+        match message {
+            Message::UserFills(fills) if fills.data.is_snapshot != Some(true) => {
+                for fill in &fills.data.fills {
+                    self.apply_fill(fill)?;
+                }
+            }
+            Message::UserFundings(fundings) if fundings.data.is_snapshot != Some(true) => {
+                for funding in &fundings.data.fundings {
+                    self.apply_funding(funding)?;
+                }
+            }
+            Message::UserNonFundingLedgerUpdates(updates)
+                if updates.data.is_snapshot != Some(true) =>
+            {
+                for update in &updates.data.non_funding_ledger_updates {
+                    self.apply_ledger_update(&update.delta)?;
+                }
+            }
+            Message::User(user) => match &user.data {
+                UserData::Fills(fills) => {
+                    for fill in fills {
+                        self.apply_fill(fill)?;
+                    }
+                }
+                UserData::Funding(funding) => self.apply_funding(funding)?,
+                _ => {}
+            },
+            Message::AllMids(all_mids) => self.update_mids(&all_mids.data.mids),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Side, User, UserFills, UserFillsData, UserFundings, UserFundingsData};
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    fn fill(tid: u64, side: Side, px: &str, sz: &str) -> TradeInfo {
+        TradeInfo {
+            coin: "ETH".to_string(),
+            side,
+            px: dec(px),
+            sz: dec(sz),
+            time: tid,
+            hash: String::new(),
+            start_position: Decimal::ZERO,
+            dir: String::new(),
+            closed_pnl: "0".to_string(),
+            oid: 1,
+            cloid: None,
+            crossed: true,
+            fee: "0.5".to_string(),
+            fee_token: "USDC".to_string(),
+            tid,
+        }
+    }
+
+    fn state(json: &str) -> UserStateResponse {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn spot_meta() -> SpotMeta {
+        serde_json::from_str(
+            r#"{
+                "universe": [{"tokens": [1, 0], "name": "@1", "index": 1, "isCanonical": false}],
+                "tokens": [
+                    {"name": "USDC", "szDecimals": 8, "weiDecimals": 8, "index": 0,
+                     "tokenId": "0x6d1e7cde53ba9467b783cb7c530ce054", "isCanonical": true},
+                    {"name": "PURR", "szDecimals": 0, "weiDecimals": 5, "index": 1,
+                     "tokenId": "0xc1fb593aeffbeb02f85e0308e9956a90", "isCanonical": true}
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    const ETH_LONG: &str = r#"{
+        "assetPositions": [{"type": "oneWay", "position": {
+            "coin": "ETH", "entryPx": "2000.0", "leverage": {"type": "cross", "value": 20},
+            "liquidationPx": null, "marginUsed": "100", "positionValue": "2000",
+            "returnOnEquity": "0", "szi": "1.0", "unrealizedPnl": "0", "maxLeverage": 50,
+            "cumFunding": {"allTime": "0", "sinceOpen": "0", "sinceChange": "0"}
+        }}],
+        "crossMarginSummary": {"accountValue": "1000", "totalMarginUsed": "100", "totalNtlPos": "2000", "totalRawUsd": "0"},
+        "marginSummary": {"accountValue": "1000", "totalMarginUsed": "100", "totalNtlPos": "2000", "totalRawUsd": "0"},
+        "withdrawable": "900"
+    }"#;
+
+    #[test]
+    fn test_position_accounting() {
+        let mut position = Position::default();
+        position.apply_fill(true, 2000.0, dec("1")).unwrap();
+        position.apply_fill(true, 2100.0, dec("1")).unwrap();
+        assert_eq!(position.entry_px, Some(2050.0));
+
+        position.apply_fill(false, 2150.0, dec("0.5")).unwrap();
+        assert_eq!(position.szi, dec("1.5"));
+        assert_eq!(position.entry_px, Some(2050.0));
+        assert_eq!(position.realized_pnl, 50.0);
+
+        // Flip to short 0.5 at 2000
+        position.apply_fill(false, 2000.0, dec("2")).unwrap();
+        assert_eq!(position.szi, dec("-0.5"));
+        assert_eq!(position.entry_px, Some(2000.0));
+        assert_eq!(position.realized_pnl, -25.0);
+
+        position.mark_px = Some(1990.0);
+        assert_eq!(position.unrealized_pnl(), 5.0);
+
+        position.apply_fill(true, 1990.0, dec("0.5")).unwrap();
+        assert!(position.is_flat());
+        assert_eq!(position.entry_px, None);
+        assert_eq!(position.realized_pnl, -20.0);
+    }
+
+    #[test]
+    fn test_messages_and_drift() -> Result<()> {
+        let balances = serde_json::from_str(
+            r#"{"balances": [{"coin": "USDC", "hold": "0", "total": "10.5", "entryNtl": "0"}]}"#,
+        )
+        .unwrap();
+        let mut portfolio =
+            Portfolio::new(Address::ZERO, &state(ETH_LONG), &balances, &spot_meta())?;
+        assert_eq!(portfolio.position("ETH").unwrap().entry_px, Some(2000.0));
+        assert_eq!(portfolio.spot_balances["USDC"], dec("10.5"));
+
+        let fills = |is_snapshot, fills| {
+            Message::UserFills(UserFills {
+                data: UserFillsData {
+                    is_snapshot,
+                    user: Address::ZERO,
+                    fills,
+                },
+            })
+        };
+        // Snapshots are already part of the seed
+        portfolio.handle_message(&fills(Some(true), vec![fill(1, Side::Buy, "1900", "5")]))?;
+        portfolio.handle_message(&fills(None, vec![fill(2, Side::Sell, "2100", "0.5")]))?;
+        portfolio.handle_message(&fills(None, vec![fill(2, Side::Sell, "2100", "0.5")]))?;
+
+        let eth = portfolio.position("ETH").unwrap();
+        assert_eq!(eth.szi, dec("0.5"));
+        assert_eq!(eth.realized_pnl, 50.0);
+        assert_eq!(eth.fees_paid, 0.5);
+
+        let mut mids = HashMap::new();
+        mids.insert("ETH".to_string(), dec("2010"));
+        portfolio.update_mids(&mids);
+        assert_eq!(portfolio.unrealized_pnl(), 5.0);
+
+        let drift = portfolio.drift(&state(ETH_LONG), 1e-6);
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0].local_szi, dec("0.5"));
+        assert_eq!(drift[0].exchange_szi, dec("1"));
+
+        portfolio.sync(&state(ETH_LONG));
+        assert!(portfolio.drift(&state(ETH_LONG), 1e-6).is_empty());
+        assert_eq!(portfolio.realized_pnl(), 50.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_spot_fills_and_duplicate_fundings() -> Result<()> {
+        let balances = serde_json::from_str(
+            r#"{"balances": [{"coin": "USDC", "hold": "0", "total": "100", "entryNtl": "0"}]}"#,
+        )
+        .unwrap();
+        let mut portfolio =
+            Portfolio::new(Address::ZERO, &state(ETH_LONG), &balances, &spot_meta())?;
+
+        let mut buy = fill(1, Side::Buy, "0.2", "100");
+        buy.coin = "@1".to_string();
+        buy.fee = "0.1".to_string();
+        buy.fee_token = "PURR".to_string();
+        let mut sell = fill(2, Side::Sell, "0.25", "40");
+        sell.coin = "@1".to_string();
+        sell.fee = "0.01".to_string();
+        portfolio.apply_fill(&buy)?;
+        portfolio.apply_fill(&sell)?;
+        assert_eq!(portfolio.spot_balances["PURR"], dec("59.9"));
+        assert_eq!(portfolio.spot_balances["USDC"], dec("89.99"));
+        assert_eq!(portfolio.position("@1").unwrap().szi, dec("60"));
+
+        // The same payment arrives through userFundings and userEvents
+        let funding = UserFunding {
+            time: 1,
+            coin: "ETH".to_string(),
+            usdc: "-1.5".to_string(),
+            szi: dec("1"),
+            funding_rate: "0.0001".to_string(),
+        };
+        portfolio.handle_message(&Message::UserFundings(UserFundings {
+            data: UserFundingsData {
+                is_snapshot: None,
+                user: Address::ZERO,
+                fundings: vec![funding.clone()],
+            },
+        }))?;
+        portfolio.handle_message(&Message::User(User {
+            data: UserData::Funding(funding),
+        }))?;
+        assert_eq!(portfolio.funding_paid(), 1.5);
+        Ok(())
+    }
+}
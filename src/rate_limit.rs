@@ -0,0 +1,249 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::{self, Instant};
+
+use crate::{prelude::*, Error, UserRateLimitResponse};
+
+/// Weight the exchange allows per IP and minute, across all REST requests.
+pub const IP_WEIGHT_PER_MINUTE: u32 = 1200;
+/// Once an address has used up its request budget, it may send one request this often.
+const THROTTLED_REQUEST_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Cost of a request against the exchange's rate limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RequestWeight {
+    /// Weight counted against the per-IP limit.
+    pub ip: u32,
+    /// Requests counted against the address-based limit. Only exchange actions use it, a batch
+    /// counting once per order or cancel.
+    pub address: u32,
+}
+
+impl RequestWeight {
+    pub fn info(ip: u32) -> RequestWeight {
+        RequestWeight { ip, address: 0 }
+    }
+
+    /// Weight of an exchange action batching `batch_len` orders, cancels or modifies.
+    pub fn action(batch_len: usize) -> RequestWeight {
+        let batch_len = batch_len.max(1) as u32;
+        RequestWeight {
+            ip: 1 + batch_len / 40,
+            address: batch_len,
+        }
+    }
+}
+
+/// What [`RateLimiter::acquire`] does when a request would exceed a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RateLimitMode {
+    /// Sleep until the request fits.
+    #[default]
+    Wait,
+    /// Return [`Error::RateLimited`] right away.
+    FailFast,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    ip_capacity: f64,
+    ip_tokens: f64,
+    refilled_at: Instant,
+    /// Requests left before the address-based limit applies, `None` until synced.
+    address_remaining: Option<u64>,
+    /// Earliest time the next request may be sent once the address budget is used up.
+    throttled_until: Option<Instant>,
+}
+
+impl LimiterState {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.ip_tokens = (self.ip_tokens + elapsed * self.ip_capacity / 60.0).min(self.ip_capacity);
+        self.refilled_at = now;
+    }
+
+    /// Takes `weight` if it fits, otherwise returns how long to wait before trying again.
+    fn try_acquire(&mut self, weight: RequestWeight, now: Instant) -> Option<Duration> {
+        self.refill(now);
+        let ip_weight = f64::from(weight.ip).min(self.ip_capacity);
+        let mut wait = Duration::ZERO;
+        if self.ip_tokens < ip_weight {
+            let missing = ip_weight - self.ip_tokens;
+            wait = Duration::from_secs_f64(missing * 60.0 / self.ip_capacity);
+        }
+
+        let address = u64::from(weight.address);
+        let throttled = self
+            .address_remaining
+            .is_some_and(|remaining| address > 0 && remaining < address);
+        if throttled {
+            if let Some(until) = self.throttled_until {
+                wait = wait.max(until.saturating_duration_since(now));
+            }
+        }
+        if !wait.is_zero() {
+            return Some(wait);
+        }
+
+        self.ip_tokens -= ip_weight;
+        if throttled {
+            self.throttled_until = Some(now + THROTTLED_REQUEST_INTERVAL);
+        } else if let Some(remaining) = &mut self.address_remaining {
+            *remaining -= address;
+        }
+        None
+    }
+}
+
+/// Token bucket modelling the exchange's per-IP weight limit, plus the address-based request
+/// budget once synced with [`RateLimiter::sync`]. Clones share the same budget, so one limiter
+/// can be set on the `HttpClient`s of an [`crate::InfoClient`] and an [`crate::ExchangeClient`]
+/// with `HttpClient::with_rate_limiter`.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    pub mode: RateLimitMode,
+    state: Arc<Mutex<LimiterState>>,
+}
+
+impl RateLimiter {
+    pub fn new(mode: RateLimitMode) -> RateLimiter {
+        RateLimiter::with_ip_limit(mode, IP_WEIGHT_PER_MINUTE)
+    }
+
+    /// Limiter allowing `weight_per_minute` instead of the exchange's limit, e.g. to leave room
+    /// for other processes sharing the IP.
+    pub fn with_ip_limit(mode: RateLimitMode, weight_per_minute: u32) -> RateLimiter {
+        let ip_capacity = f64::from(weight_per_minute.max(1));
+        RateLimiter {
+            mode,
+            state: Arc::new(Mutex::new(LimiterState {
+                ip_capacity,
+                ip_tokens: ip_capacity,
+                refilled_at: Instant::now(),
+                address_remaining: None,
+                throttled_until: None,
+            })),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LimiterState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Takes `weight` from the budget, waiting or failing as set by `mode` when it doesn't fit.
+    pub async fn acquire(&self, weight: RequestWeight) -> Result<()> {
+        loop {
+            let Some(wait) = self.lock().try_acquire(weight, Instant::now()) else {
+                return Ok(());
+            };
+            match self.mode {
+                RateLimitMode::Wait => time::sleep(wait).await,
+                RateLimitMode::FailFast => return Err(Error::RateLimited { retry_after: wait }),
+            }
+        }
+    }
+
+    /// Weight currently available against the per-IP limit.
+    pub fn ip_available(&self) -> u32 {
+        let mut state = self.lock();
+        state.refill(Instant::now());
+        state.ip_tokens as u32
+    }
+
+    /// Requests left before the address-based limit applies, `None` until synced.
+    pub fn address_remaining(&self) -> Option<u64> {
+        self.lock().address_remaining
+    }
+
+    /// Resets the address budget to what the exchange reports in `userRateLimit`.
+    pub fn sync(&self, limits: &UserRateLimitResponse) {
+        let mut state = self.lock();
+        state.address_remaining =
+            Some(limits.n_requests_cap.saturating_sub(limits.n_requests_used));
+        state.throttled_until = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::Address;
+
+    use super::*;
+    use crate::{req::HttpClient, InfoRequest};
+
+    #[test]
+    fn test_info_weights() {
+        let user = Address::ZERO;
+        assert_eq!(InfoRequest::AllMids.weight().ip, 2);
+        assert_eq!(InfoRequest::OpenOrders { user }.weight().ip, 20);
+        assert_eq!(InfoRequest::UserRole { user }.weight().ip, 60);
+    }
+
+    #[tokio::test]
+    async fn test_ip_limit() -> Result<()> {
+        let limiter = RateLimiter::with_ip_limit(RateLimitMode::FailFast, 60);
+        limiter.acquire(RequestWeight::info(20)).await?;
+        limiter.acquire(RequestWeight::info(40)).await?;
+        let Err(Error::RateLimited { retry_after }) = limiter.acquire(RequestWeight::info(2)).await
+        else {
+            panic!("expected rate limit");
+        };
+        assert!(retry_after > Duration::from_millis(1900) && retry_after <= Duration::from_secs(2));
+
+        // The bucket refills at a rate of 60 per minute
+        let mut state = limiter.lock();
+        let now = state.refilled_at;
+        assert!(state.try_acquire(RequestWeight::info(2), now).is_some());
+        let now = now + Duration::from_secs(2);
+        assert!(state.try_acquire(RequestWeight::info(2), now).is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shared_by_http_clients() -> Result<()> {
+        let limiter = RateLimiter::with_ip_limit(RateLimitMode::FailFast, 60);
+        let http_client = HttpClient::new(reqwest::Client::new(), String::new())
+            .with_rate_limiter(limiter.clone());
+        http_client.throttle(RequestWeight::info(50)).await?;
+        assert!(limiter.ip_available() < 20);
+        assert!(http_client
+            .clone()
+            .throttle(RequestWeight::info(20))
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_address_budget() {
+        assert_eq!(
+            RequestWeight::action(81),
+            RequestWeight { ip: 3, address: 81 }
+        );
+
+        let limiter = RateLimiter::new(RateLimitMode::FailFast);
+        limiter.sync(&UserRateLimitResponse {
+            cum_vlm: "100.0".to_string(),
+            n_requests_used: 95,
+            n_requests_cap: 100,
+        });
+        let mut state = limiter.lock();
+        let now = state.refilled_at;
+        assert!(state.try_acquire(RequestWeight::action(5), now).is_none());
+        assert_eq!(state.address_remaining, Some(0));
+        // Info requests don't count against the address budget
+        assert!(state.try_acquire(RequestWeight::info(20), now).is_none());
+
+        // One request per interval once the budget is used up
+        assert!(state.try_acquire(RequestWeight::action(1), now).is_none());
+        assert_eq!(
+            state.try_acquire(RequestWeight::action(1), now),
+            Some(THROTTLED_REQUEST_INTERVAL)
+        );
+        let now = now + THROTTLED_REQUEST_INTERVAL;
+        assert!(state.try_acquire(RequestWeight::action(1), now).is_none());
+    }
+}
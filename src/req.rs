@@ -1,7 +1,13 @@
+use std::sync::Arc;
+
+use alloy::primitives::Address;
 use reqwest::{Client, Response};
 use serde::Deserialize;
 
-use crate::{prelude::*, BaseUrl, Error};
+use crate::{
+    info::info_client::InfoRequest, prelude::*, BaseUrl, DefaultTransport, Error, RateLimiter,
+    RequestWeight, Transport, UserRateLimitResponse,
+};
 
 #[derive(Deserialize, Debug)]
 struct ErrorData {
@@ -10,18 +16,16 @@ struct ErrorData {
     msg: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HttpClient {
-    pub client: Client,
+    pub transport: Arc<dyn Transport>,
     pub base_url: String,
+    rate_limiter: Option<RateLimiter>,
 }
 
 async fn parse_response(response: Response) -> Result<String> {
     let status_code = response.status().as_u16();
-    let text = response
-        .text()
-        .await
-        .map_err(|e| Error::GenericRequest(e.to_string()))?;
+    let text = response.text().await?;
 
     if status_code < 400 {
         return Ok(text);
@@ -51,51 +55,99 @@ async fn parse_response(response: Response) -> Result<String> {
     })
 }
 
+/// Posts `data` as JSON with reqwest, see [`DefaultTransport`].
+pub(crate) async fn post_json(client: &Client, url: String, data: String) -> Result<String> {
+    let perf_profile = std::env::var("HL_PERF_PROFILE").is_ok();
+    let http_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
+
+    // Step 1: Build request
+    let step1_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
+    let request = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .body(data)
+        .build()?;
+    if let Some(start) = step1_start {
+        let time = start.elapsed().as_secs_f64() * 1000.0;
+        eprintln!("[PERF] HTTP Step 1 - Build request: {:.2}ms", time);
+    }
+
+    // Step 2: Execute request (network round trip + server processing)
+    let step2_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
+    let result = client.execute(request).await?;
+    if let Some(start) = step2_start {
+        let step2_time = start.elapsed().as_secs_f64() * 1000.0;
+        eprintln!("[PERF] HTTP Step 2 - Execute (network + server): {:.2}ms", step2_time);
+    }
+
+    // Step 3: Parse response
+    let step3_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
+    let result = parse_response(result).await;
+    if let Some(start) = step3_start {
+        let time = start.elapsed().as_secs_f64() * 1000.0;
+        eprintln!("[PERF] HTTP Step 3 - Parse response: {:.2}ms", time);
+    }
+    if let Some(start) = http_start {
+        let time = start.elapsed().as_secs_f64() * 1000.0;
+        eprintln!("[PERF] HTTP total time: {:.2}ms", time);
+    }
+    
+    result
+}
+
 impl HttpClient {
-    pub async fn post(&self, url_path: &'static str, data: String) -> Result<String> {
-        let perf_profile = std::env::var("HL_PERF_PROFILE").is_ok();
-        let http_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
-
-        // Step 1: Build request
-        let step1_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
-        let full_url = format!("{}{url_path}", self.base_url);
-        let request = self
-            .client
-            .post(full_url)
-            .header("Content-Type", "application/json")
-            .body(data)
-            .build()
-            .map_err(|e| Error::GenericRequest(e.to_string()))?;
-        if let Some(start) = step1_start {
-            let time = start.elapsed().as_secs_f64() * 1000.0;
-            eprintln!("[PERF] HTTP Step 1 - Build request: {:.2}ms", time);
+    pub(crate) fn new(client: Client, base_url: String) -> HttpClient {
+        HttpClient::with_transport(Arc::new(DefaultTransport::new(client)), base_url)
+    }
+
+    pub(crate) fn with_transport(transport: Arc<dyn Transport>, base_url: String) -> HttpClient {
+        HttpClient {
+            transport,
+            base_url,
+            rate_limiter: None,
         }
+    }
 
-        // Step 2: Execute request (network round trip + server processing)
-        let step2_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
-        let result = self
-            .client
-            .execute(request)
-            .await
-            .map_err(|e| Error::GenericRequest(e.to_string()))?;
-        if let Some(start) = step2_start {
-            let step2_time = start.elapsed().as_secs_f64() * 1000.0;
-            eprintln!("[PERF] HTTP Step 2 - Execute (network + server): {:.2}ms", step2_time);
+    /// Makes requests wait for (or fail without) room under the exchange's rate limits. Clients
+    /// sharing a limiter share its budget.
+    pub fn with_rate_limiter(self, rate_limiter: RateLimiter) -> HttpClient {
+        HttpClient {
+            rate_limiter: Some(rate_limiter),
+            ..self
         }
+    }
+
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
 
-        // Step 3: Parse response
-        let step3_start = if perf_profile { Some(std::time::Instant::now()) } else { None };
-        let result = parse_response(result).await;
-        if let Some(start) = step3_start {
-            let time = start.elapsed().as_secs_f64() * 1000.0;
-            eprintln!("[PERF] HTTP Step 3 - Parse response: {:.2}ms", time);
+    /// Posts `data` to `url_path` (`/info` or `/exchange`) of the base url.
+    pub async fn post(&self, url_path: &'static str, data: String) -> Result<String> {
+        let url = format!("{}{url_path}", self.base_url);
+        self.transport.post(url, data).await
+    }
+
+    /// Takes `weight` from the rate limiter, if any. Called once per request, whichever
+    /// transport ends up sending it.
+    pub async fn throttle(&self, weight: RequestWeight) -> Result<()> {
+        match &self.rate_limiter {
+            Some(rate_limiter) => rate_limiter.acquire(weight).await,
+            None => Ok(()),
         }
-        if let Some(start) = http_start {
-            let time = start.elapsed().as_secs_f64() * 1000.0;
-            eprintln!("[PERF] HTTP total time: {:.2}ms", time);
+    }
+
+    /// Queries the address-based budget of `user` and syncs the rate limiter with it.
+    pub async fn sync_rate_limit(&self, user: Address) -> Result<UserRateLimitResponse> {
+        let request = InfoRequest::UserRateLimit { user };
+        self.throttle(request.weight()).await?;
+        let data = serde_json::to_string(&request).map_err(Error::serialize)?;
+        let output = self.post("/info", data).await?;
+        let limits: UserRateLimitResponse =
+            serde_json::from_str(&output).map_err(|e| Error::response_parse(e, &output))?;
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.sync(&limits);
         }
-        
-        result
+        Ok(limits)
     }
 
     pub fn is_mainnet(&self) -> bool {
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use crate::{Error, OrderRejectReason};
+
+/// Failures a [`RetryPolicy`] can be configured to retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryClass {
+    /// The request timed out, it may or may not have reached the exchange.
+    Timeout,
+    /// The connection failed or dropped before a response arrived.
+    Connection,
+    /// HTTP 429, a rate limit rejection or the client-side [`crate::RateLimiter`].
+    RateLimited,
+    /// HTTP 5xx.
+    ServerError,
+}
+
+impl RetryClass {
+    pub fn of(error: &Error) -> Option<RetryClass> {
+        if error.is_timeout() {
+            return Some(RetryClass::Timeout);
+        }
+        match error.status() {
+            Some(429) => return Some(RetryClass::RateLimited),
+            Some(status_code) if status_code >= 500 => return Some(RetryClass::ServerError),
+            _ => {}
+        }
+        match error {
+            Error::Http(_) | Error::WebsocketTransport(_) if error.is_retryable() => {
+                Some(RetryClass::Connection)
+            }
+            Error::RateLimited { .. } | Error::OrderRejected(OrderRejectReason::RateLimited) => {
+                Some(RetryClass::RateLimited)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a request failing this way may still have been processed by the exchange.
+    pub fn is_ambiguous(&self) -> bool {
+        !matches!(self, RetryClass::RateLimited)
+    }
+}
+
+/// How exchange actions are retried. A retry re-sends the exact signed payload, nonce included,
+/// so an attempt that did reach the exchange can't be executed twice: the duplicate is rejected
+/// for reusing the nonce.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every further one.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub retry_on: Vec<RetryClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(2),
+            retry_on: vec![
+                RetryClass::Timeout,
+                RetryClass::Connection,
+                RetryClass::RateLimited,
+                RetryClass::ServerError,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry`, starting at 1.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Whether to try again after `attempts` attempts ended with `error`.
+    pub fn should_retry(&self, error: &Error, attempts: u32) -> bool {
+        attempts < self.max_attempts
+            && RetryClass::of(error).is_some_and(|class| self.retry_on.contains(&class))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(10), Duration::from_secs(2));
+
+        let server_error = Error::ServerRequest {
+            status_code: 502,
+            error_message: "Bad gateway".to_string(),
+        };
+        assert_eq!(RetryClass::of(&server_error), Some(RetryClass::ServerError));
+        assert!(policy.should_retry(&server_error, 1));
+        assert!(!policy.should_retry(&server_error, 3));
+        assert!(policy.should_retry(&Error::WsPostTimeout, 2));
+        assert!(!policy.should_retry(&Error::AssetNotFound, 1));
+
+        let policy = RetryPolicy {
+            retry_on: vec![RetryClass::RateLimited],
+            ..RetryPolicy::default()
+        };
+        assert!(!policy.should_retry(&Error::WsPostTimeout, 1));
+        assert!(policy.should_retry(&OrderRejectReason::RateLimited.into(), 1));
+    }
+}
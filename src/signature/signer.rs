@@ -0,0 +1,237 @@
+use std::{fmt, future::Future};
+
+use alloy::{
+    primitives::{Address, Signature, B256},
+    signers::Signer,
+};
+use futures_util::future::BoxFuture;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, Error};
+
+/// Anything that can produce an ECDSA signature over a 32 byte digest on behalf of an address.
+///
+/// Every alloy [`Signer`] (local keys, Ledger, AWS/GCP KMS, ...) implements this trait out of the
+/// box. [`RemoteSigner`] and [`FnSigner`] cover signers that live behind an HTTP service or
+/// custom code.
+pub trait HyperliquidSigner: fmt::Debug + Send + Sync {
+    fn address(&self) -> Address;
+
+    fn sign_hash(&self, hash: B256) -> BoxFuture<'_, Result<Signature>>;
+}
+
+impl<S> HyperliquidSigner for S
+where
+    S: Signer + fmt::Debug + Send + Sync,
+{
+    fn address(&self) -> Address {
+        Signer::address(self)
+    }
+
+    fn sign_hash(&self, hash: B256) -> BoxFuture<'_, Result<Signature>> {
+        Box::pin(async move {
+            Signer::sign_hash(self, &hash)
+                .await
+                .map_err(|e| Error::SignatureFailure(e.to_string()))
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct RemoteSignRequest {
+    address: Address,
+    hash: B256,
+}
+
+#[derive(Deserialize)]
+struct RemoteSignResponse {
+    signature: String,
+}
+
+/// Signer that delegates to an HTTP signing service.
+///
+/// The service receives `POST {"address": "0x..", "hash": "0x.."}` and must answer with
+/// `{"signature": "0x.."}` holding the 65 byte `r || s || v` signature.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    client: Client,
+    url: String,
+    address: Address,
+}
+
+impl RemoteSigner {
+    pub fn new(client: Option<Client>, url: impl Into<String>, address: Address) -> RemoteSigner {
+        RemoteSigner {
+            client: client.unwrap_or_default(),
+            url: url.into(),
+            address,
+        }
+    }
+
+    async fn request_signature(&self, hash: B256) -> Result<Signature> {
+        let data = serde_json::to_string(&RemoteSignRequest {
+            address: self.address,
+            hash,
+        })
+        .map_err(Error::serialize)?;
+
+        let response = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .body(data)
+            .send()
+            .await?;
+
+        let status_code = response.status().as_u16();
+        let text = response.text().await?;
+        if status_code >= 400 {
+            return Err(Error::SignatureFailure(format!(
+                "remote signer responded with status code {status_code}: {text}"
+            )));
+        }
+
+        let response: RemoteSignResponse =
+            serde_json::from_str(&text).map_err(|e| Error::response_parse(e, &text))?;
+        response
+            .signature
+            .parse::<Signature>()
+            .map_err(|e| Error::SignatureFailure(e.to_string()))
+    }
+}
+
+impl HyperliquidSigner for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_hash(&self, hash: B256) -> BoxFuture<'_, Result<Signature>> {
+        Box::pin(self.request_signature(hash))
+    }
+}
+
+/// Signer backed by a user supplied async closure.
+pub struct FnSigner<F> {
+    address: Address,
+    sign: F,
+}
+
+impl<F, Fut> FnSigner<F>
+where
+    F: Fn(B256) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Signature>> + Send + 'static,
+{
+    pub fn new(address: Address, sign: F) -> FnSigner<F> {
+        FnSigner { address, sign }
+    }
+}
+
+impl<F> fmt::Debug for FnSigner<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnSigner")
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F, Fut> HyperliquidSigner for FnSigner<F>
+where
+    F: Fn(B256) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Signature>> + Send + 'static,
+{
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_hash(&self, hash: B256) -> BoxFuture<'_, Result<Signature>> {
+        Box::pin((self.sign)(hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use alloy::signers::{local::PrivateKeySigner, SignerSync};
+    use tokio::{io::BufReader, net::TcpListener};
+
+    use super::*;
+    use crate::http_server::{read_request, write_response};
+
+    fn get_wallet() -> PrivateKeySigner {
+        "e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e"
+            .parse()
+            .unwrap()
+    }
+
+    fn get_hash() -> B256 {
+        B256::from_str("0xde6c4037798a4434ca03cd05f00e3b803126221375cd1e7eaaaf041768be06eb")
+            .unwrap()
+    }
+
+    #[derive(Deserialize)]
+    struct SignRequest {
+        hash: B256,
+    }
+
+    /// Minimal stand-in for a signing service: answers a single HTTP request by signing the
+    /// posted hash with `wallet`.
+    async fn spawn_signer_service(wallet: PrivateKeySigner) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/sign", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(stream);
+            let (_, body) = read_request(&mut reader).await.unwrap().unwrap();
+
+            let request: SignRequest = serde_json::from_str(&body).unwrap();
+            let signature = wallet.sign_hash_sync(&request.hash).unwrap();
+            let body = format!("{{\"signature\":\"{signature}\"}}");
+            write_response(reader.get_mut(), "200 OK", &body, false)
+                .await
+                .unwrap();
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn test_alloy_signer() -> Result<()> {
+        let wallet = get_wallet();
+        let expected = wallet.sign_hash_sync(&get_hash()).unwrap();
+
+        let signer: &dyn HyperliquidSigner = &wallet;
+        assert_eq!(signer.address(), wallet.address());
+        assert_eq!(signer.sign_hash(get_hash()).await?, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer() -> Result<()> {
+        let wallet = get_wallet();
+        let expected = wallet.sign_hash_sync(&get_hash()).unwrap();
+
+        let url = spawn_signer_service(wallet.clone()).await;
+        let signer = RemoteSigner::new(None, url, wallet.address());
+        assert_eq!(signer.sign_hash(get_hash()).await?, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fn_signer() -> Result<()> {
+        let wallet = get_wallet();
+        let expected = wallet.sign_hash_sync(&get_hash()).unwrap();
+
+        let inner = wallet.clone();
+        let signer = FnSigner::new(wallet.address(), move |hash| {
+            let signature = inner
+                .sign_hash_sync(&hash)
+                .map_err(|e| Error::SignatureFailure(e.to_string()));
+            async move { signature }
+        });
+        assert_eq!(signer.sign_hash(get_hash()).await?, expected);
+        Ok(())
+    }
+}
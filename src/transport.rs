@@ -0,0 +1,293 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::{future::BoxFuture, sink, stream, Sink, SinkExt, Stream, StreamExt};
+use reqwest::Client;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio_tungstenite::{connect_async, tungstenite::protocol};
+
+use crate::{prelude::*, req::post_json, Error};
+
+/// Outgoing text frames of a websocket connection.
+pub type WsSink = Pin<Box<dyn Sink<String, Error = Error> + Send>>;
+/// Incoming text frames of a websocket connection. Control frames are handled by the transport.
+pub type WsStream = Pin<Box<dyn Stream<Item = Result<String>> + Send>>;
+
+/// An open websocket connection.
+pub struct WsConnection {
+    pub sink: WsSink,
+    pub stream: WsStream,
+}
+
+/// How requests reach the exchange: HTTP posts to `/info` and `/exchange`, and the websocket.
+/// [`DefaultTransport`] uses reqwest and tungstenite; [`MockTransport`] answers from memory.
+pub trait Transport: fmt::Debug + Send + Sync {
+    /// Posts the JSON `body` to `url`, the base url followed by `/info` or `/exchange`, and
+    /// returns the response body. Error statuses are returned as [`Error::ClientRequest`] or
+    /// [`Error::ServerRequest`].
+    fn post(&self, url: String, body: String) -> BoxFuture<'_, Result<String>>;
+
+    /// Opens a websocket connection to `url`.
+    fn connect_ws(&self, url: String) -> BoxFuture<'_, Result<WsConnection>>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DefaultTransport {
+    pub client: Client,
+}
+
+impl DefaultTransport {
+    pub fn new(client: Client) -> DefaultTransport {
+        DefaultTransport { client }
+    }
+}
+
+impl Transport for DefaultTransport {
+    fn post(&self, url: String, body: String) -> BoxFuture<'_, Result<String>> {
+        Box::pin(post_json(&self.client, url, body))
+    }
+
+    fn connect_ws(&self, url: String) -> BoxFuture<'_, Result<WsConnection>> {
+        Box::pin(async move {
+            let (writer, reader) = connect_async(url).await?.0.split();
+            let sink = writer
+                .with(|text| async move { Ok::<_, Error>(protocol::Message::Text(text)) })
+                .sink_map_err(Error::from);
+            let stream = reader.filter_map(|message| async move {
+                match message {
+                    Ok(message @ (protocol::Message::Text(_) | protocol::Message::Binary(_))) => {
+                        Some(
+                            message
+                                .into_text()
+                                .map_err(|e| Error::ReaderTextConversion(e.to_string())),
+                        )
+                    }
+                    Ok(_) => None,
+                    Err(e) => Some(Err(Error::GenericReader(e.to_string()))),
+                }
+            });
+            Ok(WsConnection {
+                sink: Box::pin(sink),
+                stream: Box::pin(stream),
+            })
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    responses: HashMap<(String, String), String>,
+    requests: Vec<(String, serde_json::Value)>,
+    ws_sent: Vec<String>,
+    ws_clients: Vec<UnboundedSender<String>>,
+}
+
+/// In-memory [`Transport`] for tests. Posts are answered with the responses registered with
+/// [`MockTransport::respond`], websocket connections receive what is pushed with
+/// [`MockTransport::push_ws`]. Clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockTransport {
+    pub fn new() -> MockTransport {
+        MockTransport::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Answers every post to `path` (`/info` or `/exchange`) of the given type with `response`.
+    /// The type is the `type` of info requests and the action `type` of exchange requests.
+    pub fn respond(&self, path: &str, request_type: &str, response: impl Into<String>) {
+        self.lock().responses.insert(
+            (path.to_string(), request_type.to_string()),
+            response.into(),
+        );
+    }
+
+    /// Path and body of every post received so far.
+    pub fn requests(&self) -> Vec<(String, serde_json::Value)> {
+        self.lock().requests.clone()
+    }
+
+    /// Frames sent over websocket connections so far.
+    pub fn ws_sent(&self) -> Vec<String> {
+        self.lock().ws_sent.clone()
+    }
+
+    /// Sends `frame` to every open websocket connection.
+    pub fn push_ws(&self, frame: impl Into<String>) {
+        let frame = frame.into();
+        self.lock()
+            .ws_clients
+            .retain(|client| client.send(frame.clone()).is_ok());
+    }
+
+    /// Closes every open websocket connection.
+    pub fn disconnect_ws(&self) {
+        self.lock().ws_clients.clear();
+    }
+}
+
+fn request_type(path: &str, body: &serde_json::Value) -> Option<String> {
+    let request = match path {
+        "/exchange" => body.get("action")?,
+        _ => body,
+    };
+    Some(request.get("type")?.as_str()?.to_string())
+}
+
+impl Transport for MockTransport {
+    fn post(&self, url: String, body: String) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            let body: serde_json::Value = serde_json::from_str(&body).map_err(Error::json_parse)?;
+            let path = ["/info", "/exchange"]
+                .into_iter()
+                .find(|path| url.ends_with(path))
+                .unwrap_or(url.as_str())
+                .to_string();
+            let request_type = request_type(&path, &body).unwrap_or_default();
+
+            let mut state = self.lock();
+            state.requests.push((path.clone(), body));
+            state
+                .responses
+                .get(&(path.clone(), request_type.clone()))
+                .cloned()
+                .ok_or_else(|| Error::ClientRequest {
+                    status_code: 404,
+                    error_code: None,
+                    error_message: format!("No mock response for {request_type} on {path}"),
+                    error_data: None,
+                })
+        })
+    }
+
+    fn connect_ws(&self, _url: String) -> BoxFuture<'_, Result<WsConnection>> {
+        Box::pin(async move {
+            let (sender, receiver) = unbounded_channel();
+            self.lock().ws_clients.push(sender);
+
+            let state = Arc::clone(&self.state);
+            let sink = sink::unfold((), move |(), frame: String| {
+                let state = Arc::clone(&state);
+                async move {
+                    state
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .ws_sent
+                        .push(frame);
+                    Ok::<_, Error>(())
+                }
+            });
+            let stream = stream::unfold(receiver, |mut receiver| async move {
+                let frame = receiver.recv().await?;
+                Some((Ok(frame), receiver))
+            });
+            Ok(WsConnection {
+                sink: Box::pin(sink),
+                stream: Box::pin(stream),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::signers::local::PrivateKeySigner;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::*;
+    use crate::{
+        BaseUrl, ClientLimit, ClientOrder, ClientOrderRequest, ExchangeClient,
+        ExchangeResponseStatus, InfoClient, Message, Subscription, Tif,
+    };
+
+    fn mock() -> MockTransport {
+        let mock = MockTransport::new();
+        mock.respond(
+            "/info",
+            "meta",
+            r#"{"universe": [{"name": "ETH", "szDecimals": 4, "maxLeverage": 50}]}"#,
+        );
+        mock.respond("/info", "spotMeta", r#"{"universe": [], "tokens": []}"#);
+        mock.respond("/info", "allMids", r#"{"ETH": "1800.5"}"#);
+        mock.respond(
+            "/exchange",
+            "order",
+            r#"{"status": "ok", "response": {"type": "order", "data": {"statuses": [
+                {"resting": {"oid": 7}}
+            ]}}}"#,
+        );
+        mock
+    }
+
+    #[tokio::test]
+    async fn test_mock_http() -> Result<()> {
+        let mock = mock();
+        let transport = Arc::new(mock.clone());
+
+        let info_client = InfoClient::with_transport(transport.clone(), Some(BaseUrl::Testnet));
+        assert_eq!(info_client.all_mids().await?["ETH"], 1800.5.try_into()?);
+
+        let wallet: PrivateKeySigner =
+            "e908f86dbb4d55ac876378565aafeabc187f6690f046459397b17d9b9a19688e"
+                .parse()
+                .unwrap();
+        let exchange_client =
+            ExchangeClient::with_transport(transport, wallet, Some(BaseUrl::Testnet), None, None)
+                .await?;
+        let order = ClientOrderRequest {
+            asset: "ETH".to_string(),
+            is_buy: true,
+            reduce_only: false,
+            limit_px: 1800.0.try_into()?,
+            sz: 0.01.try_into()?,
+            cloid: None,
+            order_type: ClientOrder::Limit(ClientLimit { tif: Tif::Gtc }),
+        };
+        let response = exchange_client.order(order, None).await?;
+        assert!(matches!(response, ExchangeResponseStatus::Ok(_)));
+
+        let (path, body) = mock.requests().pop().unwrap();
+        assert_eq!(path, "/exchange");
+        assert_eq!(body["action"]["orders"][0]["p"], "1800");
+        assert!(matches!(
+            info_client.user_fills(Default::default()).await,
+            Err(Error::ClientRequest {
+                status_code: 404,
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mock_ws() -> Result<()> {
+        let mock = MockTransport::new();
+        let mut info_client = InfoClient::with_transport(Arc::new(mock.clone()), None);
+        let (sender, mut receiver) = unbounded_channel();
+        info_client.subscribe(Subscription::AllMids, sender).await?;
+        assert_eq!(
+            mock.ws_sent(),
+            [r#"{"method":"subscribe","subscription":{"type":"allMids"}}"#]
+        );
+
+        mock.push_ws(r#"{"channel": "allMids", "data": {"mids": {"ETH": "1800.5"}}}"#);
+        let Some(Message::AllMids(all_mids)) = receiver.recv().await else {
+            panic!("expected allMids");
+        };
+        assert_eq!(all_mids.data.mids["ETH"], 1800.5.try_into()?);
+
+        mock.disconnect_ws();
+        assert!(matches!(receiver.recv().await, Some(Message::NoData)));
+        Ok(())
+    }
+}
@@ -47,6 +47,16 @@ pub struct UserNonFundingLedgerUpdates {
     pub data: UserNonFundingLedgerUpdatesData,
 }
 
+#[derive(Deserialize, Clone, Debug)]
+pub struct UserTwapSliceFills {
+    pub data: UserTwapSliceFillsData,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct UserTwapHistory {
+    pub data: UserTwapHistoryData,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct Notification {
     pub data: NotificationData,
@@ -76,3 +86,8 @@ pub struct ActiveAssetData {
 pub struct Bbo {
     pub data: BboData,
 }
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Post {
+    pub data: PostData,
+}
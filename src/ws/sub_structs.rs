@@ -3,14 +3,14 @@ use std::collections::HashMap;
 use alloy::primitives::Address;
 use serde::{Deserialize, Serialize};
 
-use crate::Leverage;
+use crate::{Leverage, OrderStatus, Px, Side, Sz};
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct Trade {
     pub coin: String,
-    pub side: String,
-    pub px: String,
-    pub sz: String,
+    pub side: Side,
+    pub px: Px,
+    pub sz: Sz,
     pub time: u64,
     pub hash: String,
     pub tid: u64,
@@ -19,8 +19,8 @@ pub struct Trade {
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct BookLevel {
-    pub px: String,
-    pub sz: String,
+    pub px: Px,
+    pub sz: Sz,
     pub n: u64,
 }
 
@@ -33,19 +33,19 @@ pub struct L2BookData {
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct AllMidsData {
-    pub mids: HashMap<String, String>,
+    pub mids: HashMap<String, Px>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct TradeInfo {
     pub coin: String,
-    pub side: String,
-    pub px: String,
-    pub sz: String,
+    pub side: Side,
+    pub px: Px,
+    pub sz: Sz,
     pub time: u64,
     pub hash: String,
-    pub start_position: String,
+    pub start_position: Sz,
     pub dir: String,
     pub closed_pnl: String,
     pub oid: u64,
@@ -116,7 +116,7 @@ pub struct CandleData {
 #[serde(rename_all = "camelCase")]
 pub struct OrderUpdate {
     pub order: BasicOrder,
-    pub status: String,
+    pub status: OrderStatus,
     pub status_timestamp: u64,
 }
 
@@ -124,12 +124,12 @@ pub struct OrderUpdate {
 #[serde(rename_all = "camelCase")]
 pub struct BasicOrder {
     pub coin: String,
-    pub side: String,
-    pub limit_px: String,
-    pub sz: String,
+    pub side: Side,
+    pub limit_px: Px,
+    pub sz: Sz,
     pub oid: u64,
     pub timestamp: u64,
-    pub orig_sz: String,
+    pub orig_sz: Sz,
     pub cloid: Option<String>,
 }
 
@@ -147,7 +147,7 @@ pub struct UserFunding {
     pub time: u64,
     pub coin: String,
     pub usdc: String,
-    pub szi: String,
+    pub szi: Sz,
     pub funding_rate: String,
 }
 
@@ -223,7 +223,7 @@ pub struct LedgerLiquidation {
 #[derive(Deserialize, Clone, Debug)]
 pub struct LiquidatedPosition {
     pub coin: String,
-    pub szi: String,
+    pub szi: Sz,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -274,6 +274,68 @@ pub struct SpotGenesis {
     pub amount: String,
 }
 
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TwapSliceFill {
+    pub fill: TradeInfo,
+    pub twap_id: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UserTwapSliceFillsData {
+    pub is_snapshot: Option<bool>,
+    pub user: Address,
+    pub twap_slice_fills: Vec<TwapSliceFill>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TwapState {
+    pub coin: String,
+    pub user: Address,
+    pub side: Side,
+    pub sz: Sz,
+    pub executed_sz: Sz,
+    pub executed_ntl: String,
+    pub minutes: u64,
+    pub reduce_only: bool,
+    pub randomize: bool,
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TwapStatus {
+    Activated,
+    Finished,
+    Terminated,
+    Error,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TwapHistoryStatus {
+    pub status: TwapStatus,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TwapHistory {
+    pub time: u64,
+    pub state: TwapState,
+    pub status: TwapHistoryStatus,
+    pub twap_id: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UserTwapHistoryData {
+    pub is_snapshot: Option<bool>,
+    pub user: Address,
+    pub history: Vec<TwapHistory>,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct NotificationData {
     pub notification: String,
@@ -304,9 +366,9 @@ pub enum AssetCtx {
 #[serde(rename_all = "camelCase")]
 pub struct SharedAssetCtx {
     pub day_ntl_vlm: String,
-    pub prev_day_px: String,
-    pub mark_px: String,
-    pub mid_px: Option<String>,
+    pub prev_day_px: Px,
+    pub mark_px: Px,
+    pub mid_px: Option<Px>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -316,7 +378,7 @@ pub struct PerpsAssetCtx {
     pub shared: SharedAssetCtx,
     pub funding: String,
     pub open_interest: String,
-    pub oracle_px: String,
+    pub oracle_px: Px,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -340,7 +402,7 @@ pub struct ActiveAssetDataData {
     pub user: Address,
     pub coin: String,
     pub leverage: Leverage,
-    pub max_trade_szs: Vec<String>,
+    pub max_trade_szs: Vec<Sz>,
     pub available_to_trade: Vec<String>,
 }
 
@@ -351,3 +413,25 @@ pub struct BboData {
     pub time: u64,
     pub bbo: Vec<Option<BookLevel>>,
 }
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PostData {
+    pub id: u64,
+    pub response: PostResponse,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "type", content = "payload")]
+pub enum PostResponse {
+    Info(InfoPostPayload),
+    Action(serde_json::Value),
+    Error(String),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InfoPostPayload {
+    #[serde(rename = "type")]
+    pub type_string: String,
+    pub data: serde_json::Value,
+}
@@ -1,53 +1,86 @@
 use std::{
     borrow::BorrowMut,
     collections::HashMap,
+    fmt,
     ops::DerefMut,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
     time::Duration,
 };
 
 use alloy::primitives::Address;
-use futures_util::{stream::SplitSink, SinkExt, StreamExt};
+use futures_util::{SinkExt, StreamExt};
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 use tokio::{
-    net::TcpStream,
     spawn,
-    sync::{mpsc::UnboundedSender, Mutex},
+    sync::{mpsc::UnboundedSender, oneshot, Mutex},
     time,
 };
-use tokio_tungstenite::{
-    connect_async,
-    tungstenite::{self, protocol},
-    MaybeTlsStream, WebSocketStream,
-};
 
 use crate::{
     prelude::*,
     ws::message_types::{
-        ActiveAssetData, ActiveSpotAssetCtx, AllMids, Bbo, Candle, L2Book, OrderUpdates, Trades,
-        User,
+        ActiveAssetData, ActiveSpotAssetCtx, AllMids, Bbo, Candle, L2Book, OrderUpdates, Post,
+        Trades, User,
     },
-    ActiveAssetCtx, Error, Notification, UserFills, UserFundings, UserNonFundingLedgerUpdates,
-    WebData2,
+    ActiveAssetCtx, Error, Notification, PostResponse, Transport, UserFills, UserFundings,
+    UserNonFundingLedgerUpdates, UserTwapHistory, UserTwapSliceFills, WebData2, WsSink,
 };
 
+type PendingPosts = Arc<Mutex<HashMap<u64, oneshot::Sender<PostResponse>>>>;
+
 #[derive(Debug)]
 struct SubscriptionData {
     sending_channel: UnboundedSender<Message>,
     subscription_id: u32,
     id: String,
 }
-#[derive(Debug)]
 pub(crate) struct WsManager {
     stop_flag: Arc<AtomicBool>,
-    writer: Arc<Mutex<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, protocol::Message>>>,
+    writer: Arc<Mutex<WsSink>>,
     subscriptions: Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
     subscription_id: u32,
     subscription_identifiers: HashMap<u32, String>,
+    connected: Arc<AtomicBool>,
+    pending_posts: PendingPosts,
+    next_post_id: Arc<AtomicU64>,
+}
+
+/// Handle for sending `post` requests over an existing websocket connection.
+///
+/// Responses are matched to requests by id. The handle stays usable for as long as the
+/// [`InfoClient`](crate::InfoClient) that created it is alive; while the connection is down
+/// requests are sent over HTTP instead.
+#[derive(Clone)]
+pub struct WsPostTransport {
+    stop_flag: Arc<AtomicBool>,
+    writer: Arc<Mutex<WsSink>>,
+    connected: Arc<AtomicBool>,
+    pending_posts: PendingPosts,
+    next_post_id: Arc<AtomicU64>,
+    timeout: Duration,
+}
+
+impl fmt::Debug for WsManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WsManager")
+            .field("subscription_id", &self.subscription_id)
+            .field("subscription_identifiers", &self.subscription_identifiers)
+            .field("connected", &self.connected)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for WsPostTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WsPostTransport")
+            .field("connected", &self.connected)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -65,6 +98,8 @@ pub enum Subscription {
     UserFills { user: Address },
     UserFundings { user: Address },
     UserNonFundingLedgerUpdates { user: Address },
+    UserTwapSliceFills { user: Address },
+    UserTwapHistory { user: Address },
     ActiveAssetCtx { coin: String },
     ActiveAssetData { user: Address, coin: String },
     Bbo { coin: String },
@@ -86,12 +121,15 @@ pub enum Message {
     OrderUpdates(OrderUpdates),
     UserFundings(UserFundings),
     UserNonFundingLedgerUpdates(UserNonFundingLedgerUpdates),
+    UserTwapSliceFills(UserTwapSliceFills),
+    UserTwapHistory(UserTwapHistory),
     Notification(Notification),
     WebData2(WebData2),
     ActiveAssetCtx(ActiveAssetCtx),
     ActiveAssetData(ActiveAssetData),
     ActiveSpotAssetCtx(ActiveSpotAssetCtx),
     Bbo(Bbo),
+    Post(Post),
     Pong,
 }
 
@@ -106,32 +144,63 @@ pub(crate) struct Ping {
     method: &'static str,
 }
 
+#[derive(Serialize)]
+pub(crate) struct PostSendData<'a> {
+    method: &'static str,
+    id: u64,
+    request: PostRequest<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", content = "payload")]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum PostRequest<'a> {
+    Info(&'a serde_json::Value),
+    Action(&'a serde_json::Value),
+}
+
 impl WsManager {
     const SEND_PING_INTERVAL: u64 = 50;
 
-    pub(crate) async fn new(url: String, reconnect: bool) -> Result<WsManager> {
+    pub(crate) async fn new(
+        transport: Arc<dyn Transport>,
+        url: String,
+        reconnect: bool,
+    ) -> Result<WsManager> {
         let stop_flag = Arc::new(AtomicBool::new(false));
 
-        let (writer, mut reader) = Self::connect(&url).await?.split();
-        let writer = Arc::new(Mutex::new(writer));
+        let connection = transport.connect_ws(url.clone()).await?;
+        let mut reader = connection.stream;
+        let writer = Arc::new(Mutex::new(connection.sink));
 
         let subscriptions_map: HashMap<String, Vec<SubscriptionData>> = HashMap::new();
         let subscriptions = Arc::new(Mutex::new(subscriptions_map));
         let subscriptions_copy = Arc::clone(&subscriptions);
+        let connected = Arc::new(AtomicBool::new(true));
+        let pending_posts: PendingPosts = Arc::new(Mutex::new(HashMap::new()));
 
         {
             let writer = writer.clone();
             let stop_flag = Arc::clone(&stop_flag);
+            let connected = Arc::clone(&connected);
+            let pending_posts = Arc::clone(&pending_posts);
             let reader_fut = async move {
                 while !stop_flag.load(Ordering::Relaxed) {
                     if let Some(data) = reader.next().await {
-                        if let Err(err) =
-                            WsManager::parse_and_send_data(data, &subscriptions_copy).await
+                        if let Err(err) = WsManager::parse_and_send_data(
+                            data,
+                            &subscriptions_copy,
+                            &pending_posts,
+                        )
+                        .await
                         {
                             error!("Error processing data received by WsManager reader: {err}");
                         }
                     } else {
                         warn!("WsManager disconnected");
+                        connected.store(false, Ordering::Relaxed);
+                        // Dropping the senders wakes up in-flight post requests so they can fall back to HTTP
+                        pending_posts.lock().await.clear();
                         if let Err(err) = WsManager::send_to_all_subscriptions(
                             &subscriptions_copy,
                             Message::NoData,
@@ -144,12 +213,11 @@ impl WsManager {
                             // Always sleep for 1 second before attempting to reconnect so it does not spin during reconnecting. This could be enhanced with exponential backoff.
                             tokio::time::sleep(Duration::from_secs(1)).await;
                             info!("WsManager attempting to reconnect");
-                            match Self::connect(&url).await {
-                                Ok(ws) => {
-                                    let (new_writer, new_reader) = ws.split();
-                                    reader = new_reader;
+                            match transport.connect_ws(url.clone()).await {
+                                Ok(connection) => {
+                                    reader = connection.stream;
                                     let mut writer_guard = writer.lock().await;
-                                    *writer_guard = new_writer;
+                                    *writer_guard = connection.sink;
                                     for (identifier, v) in subscriptions_copy.lock().await.iter() {
                                         // TODO should these special keys be removed and instead use the simpler direct identifier mapping?
                                         if identifier.eq("userEvents")
@@ -174,6 +242,7 @@ impl WsManager {
                                             error!("Could not resubscribe correctly {identifier}: {err}");
                                         }
                                     }
+                                    connected.store(true, Ordering::Relaxed);
                                     info!("WsManager reconnect finished");
                                 }
                                 Err(err) => error!("Could not connect to websocket {err}"),
@@ -197,7 +266,7 @@ impl WsManager {
                     match serde_json::to_string(&Ping { method: "ping" }) {
                         Ok(payload) => {
                             let mut writer = writer.lock().await;
-                            if let Err(err) = writer.send(protocol::Message::Text(payload)).await {
+                            if let Err(err) = writer.send(payload).await {
                                 error!("Error pinging server: {err}")
                             }
                         }
@@ -216,25 +285,33 @@ impl WsManager {
             subscriptions,
             subscription_id: 0,
             subscription_identifiers: HashMap::new(),
+            connected,
+            pending_posts,
+            next_post_id: Arc::new(AtomicU64::new(0)),
         })
     }
 
-    async fn connect(url: &str) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
-        Ok(connect_async(url)
-            .await
-            .map_err(|e| Error::Websocket(e.to_string()))?
-            .0)
+    pub(crate) fn post_transport(&self, timeout: Duration) -> WsPostTransport {
+        WsPostTransport {
+            stop_flag: Arc::clone(&self.stop_flag),
+            writer: Arc::clone(&self.writer),
+            connected: Arc::clone(&self.connected),
+            pending_posts: Arc::clone(&self.pending_posts),
+            next_post_id: Arc::clone(&self.next_post_id),
+            timeout,
+        }
     }
 
     fn get_identifier(message: &Message) -> Result<String> {
         match message {
-            Message::AllMids(_) => serde_json::to_string(&Subscription::AllMids)
-                .map_err(|e| Error::JsonParse(e.to_string())),
+            Message::AllMids(_) => {
+                serde_json::to_string(&Subscription::AllMids).map_err(Error::serialize)
+            }
             Message::User(_) => Ok("userEvents".to_string()),
             Message::UserFills(fills) => serde_json::to_string(&Subscription::UserFills {
                 user: fills.data.user,
             })
-            .map_err(|e| Error::JsonParse(e.to_string())),
+            .map_err(Error::serialize),
             Message::Trades(trades) => {
                 if trades.data.is_empty() {
                     Ok(String::default())
@@ -242,112 +319,120 @@ impl WsManager {
                     serde_json::to_string(&Subscription::Trades {
                         coin: trades.data[0].coin.clone(),
                     })
-                    .map_err(|e| Error::JsonParse(e.to_string()))
+                    .map_err(Error::serialize)
                 }
             }
             Message::L2Book(l2_book) => serde_json::to_string(&Subscription::L2Book {
                 coin: l2_book.data.coin.clone(),
             })
-            .map_err(|e| Error::JsonParse(e.to_string())),
+            .map_err(Error::serialize),
             Message::Candle(candle) => serde_json::to_string(&Subscription::Candle {
                 coin: candle.data.coin.clone(),
                 interval: candle.data.interval.clone(),
             })
-            .map_err(|e| Error::JsonParse(e.to_string())),
+            .map_err(Error::serialize),
             Message::OrderUpdates(_) => Ok("orderUpdates".to_string()),
             Message::UserFundings(fundings) => serde_json::to_string(&Subscription::UserFundings {
                 user: fundings.data.user,
             })
-            .map_err(|e| Error::JsonParse(e.to_string())),
+            .map_err(Error::serialize),
             Message::UserNonFundingLedgerUpdates(user_non_funding_ledger_updates) => {
                 serde_json::to_string(&Subscription::UserNonFundingLedgerUpdates {
                     user: user_non_funding_ledger_updates.data.user,
                 })
-                .map_err(|e| Error::JsonParse(e.to_string()))
+                .map_err(Error::serialize)
+            }
+            Message::UserTwapSliceFills(slice_fills) => {
+                serde_json::to_string(&Subscription::UserTwapSliceFills {
+                    user: slice_fills.data.user,
+                })
+                .map_err(Error::serialize)
+            }
+            Message::UserTwapHistory(history) => {
+                serde_json::to_string(&Subscription::UserTwapHistory {
+                    user: history.data.user,
+                })
+                .map_err(Error::serialize)
             }
             Message::Notification(_) => Ok("notification".to_string()),
             Message::WebData2(web_data2) => serde_json::to_string(&Subscription::WebData2 {
                 user: web_data2.data.user,
             })
-            .map_err(|e| Error::JsonParse(e.to_string())),
+            .map_err(Error::serialize),
             Message::ActiveAssetCtx(active_asset_ctx) => {
                 serde_json::to_string(&Subscription::ActiveAssetCtx {
                     coin: active_asset_ctx.data.coin.clone(),
                 })
-                .map_err(|e| Error::JsonParse(e.to_string()))
+                .map_err(Error::serialize)
             }
             Message::ActiveSpotAssetCtx(active_spot_asset_ctx) => {
                 serde_json::to_string(&Subscription::ActiveAssetCtx {
                     coin: active_spot_asset_ctx.data.coin.clone(),
                 })
-                .map_err(|e| Error::JsonParse(e.to_string()))
+                .map_err(Error::serialize)
             }
             Message::ActiveAssetData(active_asset_data) => {
                 serde_json::to_string(&Subscription::ActiveAssetData {
                     user: active_asset_data.data.user,
                     coin: active_asset_data.data.coin.clone(),
                 })
-                .map_err(|e| Error::JsonParse(e.to_string()))
+                .map_err(Error::serialize)
             }
             Message::Bbo(bbo) => serde_json::to_string(&Subscription::Bbo {
                 coin: bbo.data.coin.clone(),
             })
-            .map_err(|e| Error::JsonParse(e.to_string())),
-            Message::SubscriptionResponse | Message::Pong => Ok(String::default()),
+            .map_err(Error::serialize),
+            Message::SubscriptionResponse | Message::Pong | Message::Post(_) => {
+                Ok(String::default())
+            }
             Message::NoData => Ok("".to_string()),
             Message::HyperliquidError(err) => Ok(format!("hyperliquid error: {err:?}")),
         }
     }
 
     async fn parse_and_send_data(
-        data: std::result::Result<protocol::Message, tungstenite::Error>,
+        data: Result<String>,
         subscriptions: &Arc<Mutex<HashMap<String, Vec<SubscriptionData>>>>,
+        pending_posts: &PendingPosts,
     ) -> Result<()> {
         match data {
-            Ok(data) => match data.into_text() {
-                Ok(data) => {
-                    if !data.starts_with('{') {
-                        return Ok(());
-                    }
-                    let message = serde_json::from_str::<Message>(&data)
-                        .map_err(|e| Error::JsonParse(e.to_string()))?;
-                    let identifier = WsManager::get_identifier(&message)?;
-                    if identifier.is_empty() {
-                        return Ok(());
+            Ok(data) => {
+                if !data.starts_with('{') {
+                    return Ok(());
+                }
+                let message = serde_json::from_str::<Message>(&data).map_err(Error::json_parse)?;
+                if let Message::Post(post) = message {
+                    // The requester may have timed out already, in which case there is nobody to notify
+                    if let Some(sender) = pending_posts.lock().await.remove(&post.data.id) {
+                        let _ = sender.send(post.data.response);
                     }
+                    return Ok(());
+                }
+                let identifier = WsManager::get_identifier(&message)?;
+                if identifier.is_empty() {
+                    return Ok(());
+                }
 
-                    let mut subscriptions = subscriptions.lock().await;
-                    let mut res = Ok(());
-                    if let Some(subscription_datas) = subscriptions.get_mut(&identifier) {
-                        for subscription_data in subscription_datas {
-                            if let Err(e) = subscription_data
-                                .sending_channel
-                                .send(message.clone())
-                                .map_err(|e| Error::WsSend(e.to_string()))
-                            {
-                                res = Err(e);
-                            }
+                let mut subscriptions = subscriptions.lock().await;
+                let mut res = Ok(());
+                if let Some(subscription_datas) = subscriptions.get_mut(&identifier) {
+                    for subscription_data in subscription_datas {
+                        if let Err(e) = subscription_data
+                            .sending_channel
+                            .send(message.clone())
+                            .map_err(|e| Error::WsSend(e.to_string()))
+                        {
+                            res = Err(e);
                         }
                     }
-                    res
-                }
-                Err(err) => {
-                    let error = Error::ReaderTextConversion(err.to_string());
-                    Ok(WsManager::send_to_all_subscriptions(
-                        subscriptions,
-                        Message::HyperliquidError(error.to_string()),
-                    )
-                    .await?)
                 }
-            },
-            Err(err) => {
-                let error = Error::GenericReader(err.to_string());
-                Ok(WsManager::send_to_all_subscriptions(
-                    subscriptions,
-                    Message::HyperliquidError(error.to_string()),
-                )
-                .await?)
+                res
             }
+            Err(err) => Ok(WsManager::send_to_all_subscriptions(
+                subscriptions,
+                Message::HyperliquidError(err.to_string()),
+            )
+            .await?),
         }
     }
 
@@ -373,34 +458,25 @@ impl WsManager {
 
     async fn send_subscription_data(
         method: &'static str,
-        writer: &mut SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, protocol::Message>,
+        writer: &mut WsSink,
         identifier: &str,
     ) -> Result<()> {
         let payload = serde_json::to_string(&SubscriptionSendData {
             method,
             subscription: &serde_json::from_str::<serde_json::Value>(identifier)
-                .map_err(|e| Error::JsonParse(e.to_string()))?,
+                .map_err(Error::json_parse)?,
         })
-        .map_err(|e| Error::JsonParse(e.to_string()))?;
+        .map_err(Error::serialize)?;
 
-        writer
-            .send(protocol::Message::Text(payload))
-            .await
-            .map_err(|e| Error::Websocket(e.to_string()))?;
+        writer.send(payload).await?;
         Ok(())
     }
 
-    async fn subscribe(
-        writer: &mut SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, protocol::Message>,
-        identifier: &str,
-    ) -> Result<()> {
+    async fn subscribe(writer: &mut WsSink, identifier: &str) -> Result<()> {
         Self::send_subscription_data("subscribe", writer, identifier).await
     }
 
-    async fn unsubscribe(
-        writer: &mut SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, protocol::Message>,
-        identifier: &str,
-    ) -> Result<()> {
+    async fn unsubscribe(writer: &mut WsSink, identifier: &str) -> Result<()> {
         Self::send_subscription_data("unsubscribe", writer, identifier).await
     }
 
@@ -412,13 +488,11 @@ impl WsManager {
         let mut subscriptions = self.subscriptions.lock().await;
 
         let identifier_entry = if let Subscription::UserEvents { user: _ } =
-            serde_json::from_str::<Subscription>(&identifier)
-                .map_err(|e| Error::JsonParse(e.to_string()))?
+            serde_json::from_str::<Subscription>(&identifier).map_err(Error::json_parse)?
         {
             "userEvents".to_string()
         } else if let Subscription::OrderUpdates { user: _ } =
-            serde_json::from_str::<Subscription>(&identifier)
-                .map_err(|e| Error::JsonParse(e.to_string()))?
+            serde_json::from_str::<Subscription>(&identifier).map_err(Error::json_parse)?
         {
             "orderUpdates".to_string()
         } else {
@@ -457,13 +531,11 @@ impl WsManager {
             .clone();
 
         let identifier_entry = if let Subscription::UserEvents { user: _ } =
-            serde_json::from_str::<Subscription>(&identifier)
-                .map_err(|e| Error::JsonParse(e.to_string()))?
+            serde_json::from_str::<Subscription>(&identifier).map_err(Error::json_parse)?
         {
             "userEvents".to_string()
         } else if let Subscription::OrderUpdates { user: _ } =
-            serde_json::from_str::<Subscription>(&identifier)
-                .map_err(|e| Error::JsonParse(e.to_string()))?
+            serde_json::from_str::<Subscription>(&identifier).map_err(Error::json_parse)?
         {
             "orderUpdates".to_string()
         } else {
@@ -490,8 +562,249 @@ impl WsManager {
     }
 }
 
+impl WsPostTransport {
+    pub fn is_connected(&self) -> bool {
+        !self.stop_flag.load(Ordering::Relaxed) && self.connected.load(Ordering::Relaxed)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> WsPostTransport {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sends `request` over the websocket and waits for the matching response payload.
+    ///
+    /// Returns `Ok(None)` when the connection is down, so the caller can send the request over HTTP instead.
+    pub(crate) async fn post(&self, request: PostRequest<'_>) -> Result<Option<String>> {
+        if !self.is_connected() {
+            return Ok(None);
+        }
+
+        let id = self.next_post_id.fetch_add(1, Ordering::Relaxed);
+        let payload = serde_json::to_string(&PostSendData {
+            method: "post",
+            id,
+            request,
+        })
+        .map_err(Error::serialize)?;
+
+        let (sender, receiver) = oneshot::channel();
+        self.pending_posts.lock().await.insert(id, sender);
+        if let Err(err) = self.writer.lock().await.send(payload).await {
+            warn!("Could not send post request over websocket: {err}");
+            self.pending_posts.lock().await.remove(&id);
+            return Ok(None);
+        }
+
+        match time::timeout(self.timeout, receiver).await {
+            Ok(Ok(PostResponse::Info(info))) => Ok(Some(info.data.to_string())),
+            Ok(Ok(PostResponse::Action(payload))) => Ok(Some(payload.to_string())),
+            Ok(Ok(PostResponse::Error(err))) => Err(Error::GenericRequest(err)),
+            // The connection dropped before the response arrived
+            Ok(Err(_)) => Ok(None),
+            Err(_) => {
+                self.pending_posts.lock().await.remove(&id);
+                Err(Error::WsPostTimeout)
+            }
+        }
+    }
+}
+
 impl Drop for WsManager {
     fn drop(&mut self) {
         self.stop_flag.store(true, Ordering::Relaxed);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::{accept_async, tungstenite::protocol};
+
+    use super::*;
+    use crate::DefaultTransport;
+
+    /// Serves a single websocket connection, answering post requests through `respond`.
+    /// Requests for which `respond` returns `None` are left unanswered.
+    async fn spawn_post_server<F>(respond: F) -> String
+    where
+        F: Fn(Vec<(u64, serde_json::Value)>) -> Vec<String> + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+
+        spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            let mut requests = Vec::new();
+            while let Some(Ok(message)) = ws.next().await {
+                let Ok(text) = message.into_text() else {
+                    continue;
+                };
+                let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+                if value["method"] != "post" {
+                    continue;
+                }
+                requests.push((value["id"].as_u64().unwrap(), value["request"].clone()));
+                for response in respond(requests.clone()) {
+                    ws.send(protocol::Message::Text(response)).await.unwrap();
+                }
+            }
+        });
+
+        url
+    }
+
+    fn info_response(id: u64, data: serde_json::Value) -> String {
+        serde_json::json!({
+            "channel": "post",
+            "data": {
+                "id": id,
+                "response": {"type": "info", "payload": {"type": "allMids", "data": data}},
+            },
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_post_correlates_responses_by_id() -> Result<()> {
+        // Answer only once both requests arrived, and in reverse order
+        let url = spawn_post_server(|requests| {
+            if requests.len() < 2 {
+                return Vec::new();
+            }
+            requests
+                .iter()
+                .rev()
+                .map(|(id, request)| info_response(*id, request["payload"]["coin"].clone()))
+                .collect()
+        })
+        .await;
+
+        let ws_manager = WsManager::new(Arc::new(DefaultTransport::default()), url, false).await?;
+        let transport = ws_manager.post_transport(Duration::from_secs(5));
+        let eth = serde_json::json!({"type": "l2Book", "coin": "ETH"});
+        let btc = serde_json::json!({"type": "l2Book", "coin": "BTC"});
+
+        let (eth, btc) = tokio::join!(
+            transport.post(PostRequest::Info(&eth)),
+            transport.post(PostRequest::Info(&btc))
+        );
+        assert_eq!(eth?.as_deref(), Some("\"ETH\""));
+        assert_eq!(btc?.as_deref(), Some("\"BTC\""));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_post_action_and_error_responses() -> Result<()> {
+        let url = spawn_post_server(|requests| {
+            let (id, request) = requests.last().unwrap();
+            let response = if request["type"] == "action" {
+                serde_json::json!({"type": "action", "payload": {"status": "ok", "response": {"type": "default"}}})
+            } else {
+                serde_json::json!({"type": "error", "payload": "Invalid request"})
+            };
+            vec![serde_json::json!({"channel": "post", "data": {"id": id, "response": response}})
+                .to_string()]
+        })
+        .await;
+
+        let ws_manager = WsManager::new(Arc::new(DefaultTransport::default()), url, false).await?;
+        let transport = ws_manager.post_transport(Duration::from_secs(5));
+
+        let action = serde_json::json!({"action": {"type": "noop"}, "nonce": 0});
+        let output = transport.post(PostRequest::Action(&action)).await?.unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["status"], "ok");
+
+        let info = serde_json::json!({"type": "unknown"});
+        assert!(matches!(
+            transport.post(PostRequest::Info(&info)).await,
+            Err(Error::GenericRequest(err)) if err == "Invalid request"
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_post_timeout() -> Result<()> {
+        let url = spawn_post_server(|_| Vec::new()).await;
+
+        let ws_manager = WsManager::new(Arc::new(DefaultTransport::default()), url, false).await?;
+        let transport = ws_manager.post_transport(Duration::from_millis(100));
+        let request = serde_json::json!({"type": "allMids"});
+        assert!(matches!(
+            transport.post(PostRequest::Info(&request)).await,
+            Err(Error::WsPostTimeout)
+        ));
+        assert!(transport.pending_posts.lock().await.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_post_falls_back_when_disconnected() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            ws.close(None).await.unwrap();
+        });
+
+        let ws_manager = WsManager::new(Arc::new(DefaultTransport::default()), url, false).await?;
+        let transport = ws_manager.post_transport(Duration::from_secs(5));
+        while transport.is_connected() {
+            time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let request = serde_json::json!({"type": "allMids"});
+        assert!(transport.post(PostRequest::Info(&request)).await?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_twap_messages_match_subscriptions() -> Result<()> {
+        let user = "0x0000000000000000000000000000000000000001";
+        let history: Message = serde_json::from_str(&format!(
+            r#"{{"channel": "userTwapHistory", "data": {{"isSnapshot": true, "user": "{user}", "history": [{{
+                "time": 1700000000,
+                "state": {{"coin": "BTC", "user": "{user}", "side": "B", "sz": "0.1", "executedSz": "0.02",
+                    "executedNtl": "1800.0", "minutes": 30, "reduceOnly": false, "randomize": true,
+                    "timestamp": 1700000000000}},
+                "status": {{"status": "activated"}},
+                "twapId": 5
+            }}]}}}}"#
+        ))
+        .unwrap();
+        let slice_fills: Message = serde_json::from_str(&format!(
+            r#"{{"channel": "userTwapSliceFills", "data": {{"user": "{user}", "twapSliceFills": [{{
+                "fill": {{"coin": "BTC", "side": "B", "px": "90000", "sz": "0.01", "time": 1700000060000,
+                    "hash": "0x00", "startPosition": "0", "dir": "Open Long", "closedPnl": "0",
+                    "oid": 1, "crossed": true, "fee": "0.3", "feeToken": "USDC", "tid": 9}},
+                "twapId": 5
+            }}]}}}}"#
+        ))
+        .unwrap();
+
+        let user = user.parse().unwrap();
+        assert_eq!(
+            WsManager::get_identifier(&history)?,
+            serde_json::to_string(&Subscription::UserTwapHistory { user }).unwrap()
+        );
+        assert_eq!(
+            WsManager::get_identifier(&slice_fills)?,
+            serde_json::to_string(&Subscription::UserTwapSliceFills { user }).unwrap()
+        );
+        let Message::UserTwapHistory(history) = history else {
+            unreachable!()
+        };
+        assert_eq!(
+            history.data.history[0].state.executed_sz.to_string(),
+            "0.02"
+        );
+        Ok(())
+    }
+}